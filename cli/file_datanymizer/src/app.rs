@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+};
+
+use crate::options::{CsvOptions, FileOptions, Options};
+
+use datanymizer_dumper::{
+    files::{csv::CsvTransformer, ndjson::NdjsonTransformer, parquet::ParquetTransformer},
+    indicator::{ConsoleIndicator, Indicator, SilentIndicator},
+};
+use datanymizer_engine::{Engine, Settings};
+
+pub struct App {
+    options: Options,
+}
+
+impl App {
+    pub fn from_options(options: Options) -> Result<Self> {
+        Ok(App { options })
+    }
+
+    pub fn run(&self) -> Result<()> {
+        match &self.options {
+            Options::Csv(options) => match &options.common.file {
+                Some(filename) => Self::csv(
+                    options,
+                    BufWriter::new(File::create(filename)?),
+                    ConsoleIndicator::new(),
+                ),
+                None => Self::csv(options, BufWriter::new(io::stdout()), SilentIndicator),
+            },
+            Options::Ndjson(options) => match &options.file {
+                Some(filename) => Self::ndjson(
+                    options,
+                    BufWriter::new(File::create(filename)?),
+                    ConsoleIndicator::new(),
+                ),
+                None => Self::ndjson(options, BufWriter::new(io::stdout()), SilentIndicator),
+            },
+            // Parquet writers buffer row groups themselves
+            Options::Parquet(options) => match &options.file {
+                Some(filename) => {
+                    Self::parquet(options, File::create(filename)?, ConsoleIndicator::new())
+                }
+                None => Self::parquet(options, io::stdout(), SilentIndicator),
+            },
+        }
+    }
+
+    fn csv<W: Write, I: Indicator>(options: &CsvOptions, writer: W, indicator: I) -> Result<()> {
+        let mut transformer = CsvTransformer::new(Self::engine(&options.common)?, indicator)?;
+        transformer.set_delimiter(options.delimiter());
+        transformer.transform(
+            &options.common.table_names()?,
+            BufReader::new(Self::input(&options.common)?),
+            writer,
+        )
+    }
+
+    fn ndjson<W: Write, I: Indicator>(
+        options: &FileOptions,
+        writer: W,
+        indicator: I,
+    ) -> Result<()> {
+        NdjsonTransformer::new(Self::engine(options)?, indicator)?.transform(
+            &options.table_names()?,
+            BufReader::new(Self::input(options)?),
+            writer,
+        )
+    }
+
+    // Parquet metadata is at the end of the file, so it can't be read from stdin
+    fn parquet<W: Write + Send, I: Indicator>(
+        options: &FileOptions,
+        writer: W,
+        indicator: I,
+    ) -> Result<()> {
+        let input = options
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("The input file is required for Parquet"))?;
+        ParquetTransformer::new(Self::engine(options)?, indicator)?.transform(
+            &options.table_names()?,
+            File::open(input)?,
+            writer,
+        )
+    }
+
+    fn input(options: &FileOptions) -> Result<Box<dyn Read>> {
+        Ok(match &options.input {
+            Some(filename) => Box::new(File::open(filename)?),
+            None => Box::new(io::stdin()),
+        })
+    }
+
+    fn engine(options: &FileOptions) -> Result<Engine> {
+        let settings = Settings::new(options.config.clone())?;
+        Ok(Engine::new(settings))
+    }
+}
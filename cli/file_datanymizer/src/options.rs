@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Result};
+use datanymizer_dumper::files;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(name = "file_datanymizer")]
+pub enum Options {
+    #[structopt(about = "Anonymize a CSV (or TSV) file with headers")]
+    Csv(CsvOptions),
+    #[structopt(about = "Anonymize an NDJSON (JSON Lines) file")]
+    Ndjson(FileOptions),
+    #[structopt(about = "Anonymize a Parquet file (the input file is required)")]
+    Parquet(FileOptions),
+}
+
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct FileOptions {
+    #[structopt(
+        short,
+        long,
+        help = "Path to config file",
+        default_value = "./config.yml"
+    )]
+    pub config: String,
+
+    #[structopt(
+        short,
+        long,
+        name = "FILE",
+        help = "Path to the output file (stdout by default)"
+    )]
+    pub file: Option<String>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Table name for rules (by default, the input file name with or without the extension)"
+    )]
+    pub table: Option<String>,
+
+    #[structopt(name = "INPUT", help = "Path to the input file (stdin by default)")]
+    pub input: Option<String>,
+}
+
+impl FileOptions {
+    pub fn table_names(&self) -> Result<Vec<String>> {
+        match (&self.table, &self.input) {
+            (Some(table), _) => Ok(vec![table.clone()]),
+            (None, Some(input)) => Ok(files::table_names(input)),
+            (None, None) => Err(anyhow!(
+                "The table name (--table) is required when reading from stdin"
+            )),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct CsvOptions {
+    #[structopt(flatten)]
+    pub common: FileOptions,
+
+    #[structopt(
+        short,
+        long,
+        parse(try_from_str = parse_delimiter),
+        help = "Field delimiter: a character or `tab` [default: `tab` for *.tsv files, `,` for others]"
+    )]
+    pub delimiter: Option<u8>,
+}
+
+impl CsvOptions {
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter.unwrap_or_else(|| {
+            match self.common.input.as_ref().and_then(|i| i.rsplit_once('.')) {
+                Some((_, ext)) if ext.eq_ignore_ascii_case("tsv") => b'\t',
+                _ => b',',
+            }
+        })
+    }
+}
+
+fn parse_delimiter(s: &str) -> Result<u8> {
+    match s {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        s if s.len() == 1 => Ok(s.as_bytes()[0]),
+        _ => Err(anyhow!("The delimiter must be one ASCII character")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_options(args: Vec<&str>) -> CsvOptions {
+        match Options::from_iter(args) {
+            Options::Csv(o) => o,
+            _ => panic!("No csv command"),
+        }
+    }
+
+    #[test]
+    fn parse_csv_args() {
+        let options = csv_options(vec![
+            "file_datanymizer",
+            "csv",
+            "-c",
+            "some_config.yml",
+            "-f",
+            "out.csv",
+            "-d",
+            ";",
+            "/tmp/users.csv",
+        ]);
+
+        assert_eq!(options.common.config, "some_config.yml");
+        assert_eq!(options.common.file, Some("out.csv".to_string()));
+        assert_eq!(options.delimiter(), b';');
+        assert_eq!(
+            options.common.table_names().unwrap(),
+            vec!["users.csv", "users"]
+        );
+    }
+
+    #[test]
+    fn default_delimiter() {
+        let options = csv_options(vec!["file_datanymizer", "csv", "users.TSV"]);
+        assert_eq!(options.delimiter(), b'\t');
+
+        let options = csv_options(vec!["file_datanymizer", "csv", "users.csv"]);
+        assert_eq!(options.delimiter(), b',');
+
+        let options = csv_options(vec!["file_datanymizer", "csv", "-d", "tab", "users.csv"]);
+        assert_eq!(options.delimiter(), b'\t');
+
+        let result = Options::from_iter_safe(vec!["file_datanymizer", "csv", "-d", ";;"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_ndjson_args() {
+        let args = vec!["file_datanymizer", "ndjson", "-t", "events", "events.jsonl"];
+        match Options::from_iter(args) {
+            Options::Ndjson(o) => {
+                assert_eq!(o.config, "./config.yml");
+                assert_eq!(o.table_names().unwrap(), vec!["events"]);
+                assert_eq!(o.input, Some("events.jsonl".to_string()));
+            }
+            _ => panic!("No ndjson command"),
+        }
+    }
+
+    #[test]
+    fn parse_parquet_args() {
+        let args = vec![
+            "file_datanymizer",
+            "parquet",
+            "-f",
+            "out.parquet",
+            "users.parquet",
+        ];
+        match Options::from_iter(args) {
+            Options::Parquet(o) => {
+                assert_eq!(o.file, Some("out.parquet".to_string()));
+                assert_eq!(o.table_names().unwrap(), vec!["users.parquet", "users"]);
+            }
+            _ => panic!("No parquet command"),
+        }
+    }
+
+    #[test]
+    fn table_names() {
+        let options = csv_options(vec!["file_datanymizer", "csv", "-t", "users"]);
+        assert_eq!(options.common.table_names().unwrap(), vec!["users"]);
+
+        let options = csv_options(vec!["file_datanymizer", "csv"]);
+        assert!(options.common.table_names().is_err());
+    }
+}
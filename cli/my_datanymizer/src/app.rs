@@ -0,0 +1,53 @@
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use crate::options::Options;
+
+use datanymizer_dumper::{
+    indicator::{ConsoleIndicator, Indicator, SilentIndicator},
+    mysql::{connector::Connector, dumper::MyDumper},
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+pub struct App {
+    options: Options,
+}
+
+impl App {
+    pub fn from_options(options: Options) -> Result<Self> {
+        options.database_url()?;
+        Ok(App { options })
+    }
+
+    pub fn run(&self) -> Result<()> {
+        match &self.options.file {
+            Some(filename) => self.dump(File::create(filename)?, ConsoleIndicator::new()),
+            None => self.dump(io::stdout(), SilentIndicator),
+        }
+    }
+
+    fn dump<W, I>(&self, writer: W, indicator: I) -> Result<()>
+    where
+        W: 'static + Write + Send,
+        I: 'static + Indicator + Send,
+    {
+        let mut connection = Connector::new(self.options.database_url()?).connect()?;
+        let mut dumper = MyDumper::new(
+            self.engine()?,
+            self.options.mysqldump_location.clone(),
+            writer,
+            indicator,
+            self.options.mysqldump_args.clone(),
+        )?;
+        dumper.dump(&mut connection)
+    }
+
+    fn engine(&self) -> Result<Engine> {
+        let settings = Settings::new(self.options.config.clone())?;
+        Ok(Engine::new(settings))
+    }
+}
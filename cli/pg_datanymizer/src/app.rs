@@ -1,62 +1,215 @@
 use anyhow::Result;
-use std::{fs::File, io};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Seek, SeekFrom, Write},
+};
 use url::Url;
 
-use crate::options::{Options, TransactionConfig};
+use crate::options::{
+    Command, CopyFormatConfig, DecryptOptions, Options, TransactionConfig, TransformDumpOptions,
+};
 
 use datanymizer_dumper::{
-    indicator::{ConsoleIndicator, SilentIndicator},
-    postgres::{connector::Connector, dumper::PgDumper, IsolationLevel},
-    Dumper,
+    checkpoint::Checkpoint,
+    encryption::{self, EncryptedWriter},
+    indicator::{ConsoleIndicator, Indicator, SilentIndicator},
+    postgres::{
+        anonymizer::{PgAnonymizer, DEFAULT_BATCH_SIZE},
+        connector::Connector,
+        dump_transformer::DumpTransformer,
+        dumper::PgDumper,
+        loader::PgLoader,
+        parquet::ParquetTarget,
+        schema_inspector::PgSchemaInspector,
+        CopyFormat, IsolationLevel,
+    },
+    Dumper, SchemaInspector,
 };
 use datanymizer_engine::{Engine, Settings};
 
 pub struct App {
     options: Options,
-    database_url: Url,
 }
 
 impl App {
     pub fn from_options(options: Options) -> Result<Self> {
-        let database_url = options.database_url()?;
+        if options.command.is_none() {
+            options.database_url()?;
+        }
 
-        Ok(App {
-            options,
-            database_url,
-        })
+        Ok(App { options })
     }
 
     pub fn run(&self) -> Result<()> {
-        let mut connection = self.connector().connect()?;
-        let engine = self.engine()?;
+        match &self.options.command {
+            Some(Command::Decrypt(options)) => return Self::decrypt(options),
+            Some(Command::TransformDump(options)) => return Self::transform_dump(options),
+            None => {}
+        }
+
+        if self.options.anonymize_in_place {
+            return self.anonymize();
+        }
+
+        if let Some(dir) = &self.options.parquet_dir {
+            return self.dump_to_parquet(dir);
+        }
+
+        if let Some(target_url) = self.options.target_database_url()? {
+            let connection = self.connector(target_url).connect()?;
+            return self
+                .dump(
+                    PgLoader::new(connection.client),
+                    ConsoleIndicator::new(),
+                    None,
+                )?
+                .finish();
+        }
 
         match &self.options.file {
-            Some(filename) => PgDumper::new(
-                engine,
-                self.dump_isolation_level(),
-                self.options.pg_dump_location.clone(),
-                File::create(filename)?,
-                ConsoleIndicator::new(),
-                self.options.pg_dump_args.clone(),
-            )?
-            .dump(&mut connection),
-
-            None => PgDumper::new(
-                engine,
-                self.dump_isolation_level(),
-                self.options.pg_dump_location.clone(),
-                io::stdout(),
-                SilentIndicator,
-                self.options.pg_dump_args.clone(),
-            )?
-            .dump(&mut connection),
+            Some(filename) if self.options.encrypt_recipients.is_empty() => {
+                self.dump_to_file(filename)
+            }
+            Some(filename) => self.dump_to(File::create(filename)?, ConsoleIndicator::new()),
+            None => self.dump_to(io::stdout(), SilentIndicator),
         }
     }
 
-    fn connector(&self) -> Connector {
+    // Plain file dumps can be resumed, so we record checkpoints for them
+    fn dump_to_file(&self, filename: &str) -> Result<()> {
+        let checkpoint_path = Checkpoint::path_for(filename);
+        let checkpoint = if self.options.resume {
+            Checkpoint::open(checkpoint_path)?
+        } else {
+            Checkpoint::create(checkpoint_path)?
+        };
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(filename)?;
+        file.set_len(checkpoint.offset())?;
+        file.seek(SeekFrom::End(0))?;
+
+        self.dump(file, ConsoleIndicator::new(), Some(checkpoint))?;
+        Ok(())
+    }
+
+    fn dump_to<W, I>(&self, writer: W, indicator: I) -> Result<()>
+    where
+        W: 'static + Write + Send,
+        I: 'static + Indicator + Send,
+    {
+        let recipients = &self.options.encrypt_recipients;
+        if recipients.is_empty() {
+            self.dump(writer, indicator, None)?;
+        } else {
+            self.dump(EncryptedWriter::new(writer, recipients)?, indicator, None)?
+                .finish()?;
+        }
+
+        Ok(())
+    }
+
+    fn dump<W, I>(&self, writer: W, indicator: I, checkpoint: Option<Checkpoint>) -> Result<W>
+    where
+        W: 'static + Write + Send,
+        I: 'static + Indicator + Send,
+    {
+        let mut connection = self.connector(self.options.database_url()?).connect()?;
+        let mut dumper = PgDumper::new(
+            self.engine()?,
+            self.dump_isolation_level(),
+            self.options.pg_dump_location.clone(),
+            writer,
+            indicator,
+            self.options.pg_dump_args.clone(),
+        )?;
+        dumper.set_copy_format(self.copy_format());
+        if let Some(checkpoint) = checkpoint {
+            dumper.set_checkpoint(checkpoint);
+        }
+        dumper.dump(&mut connection)?;
+
+        Ok(dumper.into_writer())
+    }
+
+    // Only the data stage is dumped (the schema is not needed for Parquet files)
+    fn dump_to_parquet(&self, dir: &str) -> Result<()> {
+        let mut connection = self.connector(self.options.database_url()?).connect()?;
+        let tables = PgSchemaInspector {}.get_tables(&mut connection)?;
+        let mut dumper = PgDumper::new(
+            self.engine()?,
+            self.dump_isolation_level(),
+            self.options.pg_dump_location.clone(),
+            ParquetTarget::new(dir, &tables)?,
+            ConsoleIndicator::new(),
+            self.options.pg_dump_args.clone(),
+        )?;
+        dumper.set_copy_format(self.copy_format());
+        dumper.data(&mut connection)?;
+
+        dumper.into_writer().finish()
+    }
+
+    fn anonymize(&self) -> Result<()> {
+        let mut connection = self.connector(self.options.database_url()?).connect()?;
+        let mut anonymizer = PgAnonymizer::new(
+            self.engine()?,
+            ConsoleIndicator::new(),
+            self.options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            self.options.disable_triggers,
+        );
+
+        anonymizer.anonymize(&mut connection)
+    }
+
+    fn decrypt(options: &DecryptOptions) -> Result<()> {
+        let input: Box<dyn io::Read> = match &options.input {
+            Some(filename) => Box::new(File::open(filename)?),
+            None => Box::new(io::stdin()),
+        };
+
+        match &options.file {
+            Some(filename) => {
+                encryption::decrypt(input, &mut File::create(filename)?, &options.identity)?
+            }
+            None => encryption::decrypt(input, &mut io::stdout(), &options.identity)?,
+        };
+
+        Ok(())
+    }
+
+    fn transform_dump(options: &TransformDumpOptions) -> Result<()> {
+        let input: Box<dyn io::Read> = match &options.input {
+            Some(filename) => Box::new(File::open(filename)?),
+            None => Box::new(io::stdin()),
+        };
+        let engine = Engine::new(Settings::new(options.config.clone())?);
+
+        match &options.file {
+            Some(filename) => {
+                let mut writer = BufWriter::new(File::create(filename)?);
+                DumpTransformer::new(engine, ConsoleIndicator::new())?
+                    .transform(BufReader::new(input), &mut writer)?;
+                writer.flush()?;
+            }
+            None => {
+                let mut writer = BufWriter::new(io::stdout());
+                DumpTransformer::new(engine, SilentIndicator)?
+                    .transform(BufReader::new(input), &mut writer)?;
+                writer.flush()?;
+            }
+        };
+
+        Ok(())
+    }
+
+    fn connector(&self, url: Url) -> Connector {
         let options = &self.options;
         Connector::new(
-            self.database_url.clone(),
+            url,
             options.accept_invalid_hostnames,
             options.accept_invalid_certs,
         )
@@ -67,6 +220,13 @@ fn engine(&self) -> Result<Engine> {
         Ok(Engine::new(settings))
     }
 
+    fn copy_format(&self) -> CopyFormat {
+        match self.options.copy_format {
+            CopyFormatConfig::Text => CopyFormat::Text,
+            CopyFormatConfig::Binary => CopyFormat::Binary,
+        }
+    }
+
     fn dump_isolation_level(&self) -> Option<IsolationLevel> {
         match self.options.dump_transaction {
             TransactionConfig::NoTransaction => None,
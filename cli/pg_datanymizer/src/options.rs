@@ -19,11 +19,28 @@ fn default() -> Self {
     }
 }
 
+arg_enum! {
+    #[derive(Debug, Clone)]
+    pub enum CopyFormatConfig {
+        Text,
+        Binary,
+    }
+}
+
+impl Default for CopyFormatConfig {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
 #[derive(StructOpt, Debug, Clone, Default)]
 #[structopt(name = "pg_datanymizer")]
 pub struct Options {
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+
     #[structopt(name = "DBNAME")]
-    database: String,
+    database: Option<String>,
 
     #[structopt(
         short,
@@ -75,6 +92,15 @@ pub struct Options {
     )]
     pub dump_transaction: TransactionConfig,
 
+    #[structopt(
+        long = "copy-format",
+        default_value,
+        case_insensitive = true,
+        possible_values = &CopyFormatConfig::variants(),
+        help = "COPY format for reading tables with rules. `binary` falls back to `text` for tables with `sql` rules",
+    )]
+    pub copy_format: CopyFormatConfig,
+
     #[structopt(
         long = "pg_dump",
         help = "pg_dump file location",
@@ -94,6 +120,60 @@ pub struct Options {
     )]
     pub accept_invalid_certs: bool,
 
+    #[structopt(
+        long = "target-db",
+        name = "TARGET_DB",
+        conflicts_with_all = &["FILE", "RECIPIENT"],
+        help = "Target database URL. The anonymized dump is restored directly into it instead of writing to a file"
+    )]
+    pub target_db: Option<String>,
+
+    #[structopt(
+        long,
+        requires = "FILE",
+        conflicts_with_all = &["RECIPIENT", "TARGET_DB", "anonymize-in-place"],
+        help = "Resume an interrupted dump from the last completed table (according to the `<FILE>.checkpoint` file)"
+    )]
+    pub resume: bool,
+
+    #[structopt(
+        long = "anonymize-in-place",
+        conflicts_with_all = &["FILE", "TARGET_DB", "RECIPIENT"],
+        help = "Anonymize the database in place (with `UPDATE`s) instead of dumping it. Use it only for disposable copies!"
+    )]
+    pub anonymize_in_place: bool,
+
+    #[structopt(
+        long = "parquet-dir",
+        name = "PARQUET_DIR",
+        conflicts_with_all = &["FILE", "TARGET_DB", "RECIPIENT", "anonymize-in-place", "resume"],
+        help = "Export the anonymized data of tables to Parquet files (one per table) in this directory instead of dumping"
+    )]
+    pub parquet_dir: Option<String>,
+
+    #[structopt(
+        long = "batch-size",
+        requires = "anonymize-in-place",
+        help = "Number of rows updated in one batch in the in-place anonymization mode [default: 1000]"
+    )]
+    pub batch_size: Option<usize>,
+
+    #[structopt(
+        long = "disable-triggers",
+        requires = "anonymize-in-place",
+        help = "Disable table triggers during the in-place anonymization"
+    )]
+    pub disable_triggers: bool,
+
+    #[structopt(
+        long = "encrypt",
+        name = "RECIPIENT",
+        number_of_values = 1,
+        conflicts_with = "TARGET_DB",
+        help = "Encrypt the dump with the age public key (can be repeated for several recipients)"
+    )]
+    pub encrypt_recipients: Vec<String>,
+
     #[structopt(
         name = "PG_DUMP_ARGS",
         help = "The remaining arguments are passed directly to `pg_dump` calls. You should add `--` before <DBNAME> in such cases"
@@ -101,15 +181,86 @@ pub struct Options {
     pub pg_dump_args: Vec<String>,
 }
 
+#[derive(StructOpt, Debug, Clone)]
+pub enum Command {
+    #[structopt(about = "Decrypt a dump that was encrypted with the `--encrypt` option")]
+    Decrypt(DecryptOptions),
+    #[structopt(
+        about = "Anonymize an existing plain-text dump (made by `pg_dump`) without a database connection"
+    )]
+    TransformDump(TransformDumpOptions),
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct DecryptOptions {
+    #[structopt(
+        short,
+        long,
+        name = "IDENTITY",
+        help = "Path to the age identity file with the private key"
+    )]
+    pub identity: String,
+
+    #[structopt(
+        short,
+        long,
+        name = "FILE",
+        help = "Path to the output file (stdout by default)"
+    )]
+    pub file: Option<String>,
+
+    #[structopt(name = "INPUT", help = "Path to the encrypted dump (stdin by default)")]
+    pub input: Option<String>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct TransformDumpOptions {
+    #[structopt(
+        short,
+        long,
+        help = "Path to config file",
+        default_value = "./config.yml"
+    )]
+    pub config: String,
+
+    #[structopt(
+        short,
+        long,
+        name = "FILE",
+        help = "Path to the output file (stdout by default)"
+    )]
+    pub file: Option<String>,
+
+    #[structopt(
+        name = "INPUT",
+        help = "Path to the plain-text dump (stdin by default)"
+    )]
+    pub input: Option<String>,
+}
+
 impl Options {
+    pub fn target_database_url(&self) -> Result<Option<Url>> {
+        match &self.target_db {
+            Some(target_db) => {
+                let url = Url::parse(target_db)?;
+                match url.scheme() {
+                    "postgres" | "postgresql" => Ok(Some(url)),
+                    _ => Err(anyhow!("Scheme url error")),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn database_url(&self) -> Result<Url> {
-        if let Ok(url) = Url::parse(self.database.as_str()) {
+        let database = self.database.clone().unwrap_or_default();
+        if let Ok(url) = Url::parse(database.as_str()) {
             return match url.scheme() {
                 "postgres" | "postgresql" => Ok(url),
                 _ => Err(anyhow!("Scheme url error")),
             };
         }
-        self.build_url(Some(self.database.to_string()).filter(|x| !x.is_empty()))
+        self.build_url(Some(database).filter(|x| !x.is_empty()))
     }
 
     fn build_url(&self, override_db_name: Option<String>) -> Result<Url> {
@@ -141,7 +292,7 @@ mod tests {
     #[test]
     fn parse_empty_config() {
         let cfg = Options {
-            database: "postgres://hostname/test".to_string(),
+            database: Some("postgres://hostname/test".to_string()),
             config: "./config.yml".to_string(),
             db_name: "test".to_string(),
             host: "localhost".to_string(),
@@ -208,6 +359,214 @@ fn parse_args() {
         assert_eq!(options.config, "some_config.yml");
         assert_eq!(options.file, Some("some_file.sql".to_string()));
         assert_eq!(options.pg_dump_args, vec!["--no-owner", "--no-acl"]);
+        assert!(options.command.is_none());
+    }
+
+    #[test]
+    fn parse_encrypt_args() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "--encrypt",
+            "age1first",
+            "--encrypt",
+            "age1second",
+            "postgres://user@hostname/test",
+        ];
+        let options = Options::from_iter(cmd);
+
+        assert_eq!(
+            options.database_url().unwrap().as_str(),
+            "postgres://user@hostname/test"
+        );
+        assert_eq!(options.encrypt_recipients, vec!["age1first", "age1second"]);
+
+        // A dump restored directly into the target database is not encrypted
+        let cmd = vec![
+            "pg_datanymizer",
+            "--encrypt",
+            "age1first",
+            "--target-db",
+            "postgres://user@hostname/staging",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+    }
+
+    #[test]
+    fn parse_target_db() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "--target-db",
+            "postgres://user@hostname/staging",
+            "postgres://user@hostname/test",
+        ];
+        let options = Options::from_iter(cmd);
+
+        assert_eq!(
+            options.target_database_url().unwrap().unwrap().as_str(),
+            "postgres://user@hostname/staging"
+        );
+        assert_eq!(
+            options.database_url().unwrap().as_str(),
+            "postgres://user@hostname/test"
+        );
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--target-db",
+            "postgres://user@hostname/staging",
+            "-f",
+            "dump.sql",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+
+        let options = Options {
+            target_db: Some("mysql://user@hostname/staging".to_string()),
+            ..Default::default()
+        };
+        assert!(options.target_database_url().is_err());
+    }
+
+    #[test]
+    fn parse_resume() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "--resume",
+            "-f",
+            "dump.sql",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter(cmd).resume);
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--resume",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--resume",
+            "-f",
+            "dump.sql",
+            "--encrypt",
+            "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+    }
+
+    #[test]
+    fn parse_anonymize_in_place() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "--anonymize-in-place",
+            "--batch-size",
+            "500",
+            "--disable-triggers",
+            "postgres://user@hostname/test",
+        ];
+        let options = Options::from_iter(cmd);
+
+        assert!(options.anonymize_in_place);
+        assert_eq!(options.batch_size, Some(500));
+        assert!(options.disable_triggers);
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--anonymize-in-place",
+            "-f",
+            "dump.sql",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--disable-triggers",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+    }
+
+    #[test]
+    fn parse_parquet_dir() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "--parquet-dir",
+            "/tmp/export",
+            "postgres://user@hostname/test",
+        ];
+        let options = Options::from_iter(cmd);
+        assert_eq!(options.parquet_dir, Some("/tmp/export".to_string()));
+
+        let cmd = vec![
+            "pg_datanymizer",
+            "--parquet-dir",
+            "/tmp/export",
+            "-f",
+            "dump.sql",
+            "postgres://user@hostname/test",
+        ];
+        assert!(Options::from_iter_safe(cmd).is_err());
+    }
+
+    #[test]
+    fn parse_decrypt_command() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "decrypt",
+            "-i",
+            "key.txt",
+            "-f",
+            "dump.sql",
+            "dump.sql.age",
+        ];
+        let options = Options::from_iter(cmd);
+
+        match options.command {
+            Some(Command::Decrypt(o)) => {
+                assert_eq!(o.identity, "key.txt");
+                assert_eq!(o.file, Some("dump.sql".to_string()));
+                assert_eq!(o.input, Some("dump.sql.age".to_string()));
+            }
+            _ => panic!("No decrypt command"),
+        }
+    }
+
+    #[test]
+    fn parse_transform_dump_command() {
+        let cmd = vec![
+            "pg_datanymizer",
+            "transform-dump",
+            "-c",
+            "some_config.yml",
+            "-f",
+            "anonymized.sql",
+            "dump.sql",
+        ];
+        let options = Options::from_iter(cmd);
+
+        match options.command {
+            Some(Command::TransformDump(o)) => {
+                assert_eq!(o.config, "some_config.yml");
+                assert_eq!(o.file, Some("anonymized.sql".to_string()));
+                assert_eq!(o.input, Some("dump.sql".to_string()));
+            }
+            _ => panic!("No transform-dump command"),
+        }
+
+        let options = Options::from_iter(vec!["pg_datanymizer", "transform-dump"]);
+        match options.command {
+            Some(Command::TransformDump(o)) => {
+                assert_eq!(o.config, "./config.yml");
+                assert!(o.file.is_none());
+                assert!(o.input.is_none());
+            }
+            _ => panic!("No transform-dump command"),
+        }
     }
 
     #[test]
@@ -216,11 +575,11 @@ fn support_multiple_schemes() {
         let scheme2 = "postgresql://user@hostname/test";
 
         let opts1 = Options {
-            database: scheme1.to_string(),
+            database: Some(scheme1.to_string()),
             ..Default::default()
         };
         let opts2 = Options {
-            database: scheme2.to_string(),
+            database: Some(scheme2.to_string()),
             ..Default::default()
         };
 
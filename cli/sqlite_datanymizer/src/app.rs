@@ -0,0 +1,59 @@
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use crate::options::Options;
+
+use datanymizer_dumper::{
+    indicator::{ConsoleIndicator, Indicator, SilentIndicator},
+    sqlite::{connector::Connector, dumper::SqliteDumper, loader::SqliteLoader},
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+pub struct App {
+    options: Options,
+}
+
+impl App {
+    pub fn from_options(options: Options) -> Result<Self> {
+        Ok(App { options })
+    }
+
+    pub fn run(&self) -> Result<()> {
+        if let Some(target) = &self.options.target_db {
+            self.dump(SqliteLoader::create(target)?, ConsoleIndicator::new())?
+                .finish()?;
+            return Ok(());
+        }
+
+        match &self.options.file {
+            Some(filename) => {
+                self.dump(File::create(filename)?, ConsoleIndicator::new())?;
+            }
+            None => {
+                self.dump(io::stdout(), SilentIndicator)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn dump<W, I>(&self, writer: W, indicator: I) -> Result<W>
+    where
+        W: 'static + Write + Send,
+        I: 'static + Indicator + Send,
+    {
+        let mut connection = Connector::new(&self.options.database).connect()?;
+        let mut dumper = SqliteDumper::new(self.engine()?, writer, indicator)?;
+        dumper.dump(&mut connection)?;
+
+        Ok(dumper.into_writer())
+    }
+
+    fn engine(&self) -> Result<Engine> {
+        let settings = Settings::new(self.options.config.clone())?;
+        Ok(Engine::new(settings))
+    }
+}
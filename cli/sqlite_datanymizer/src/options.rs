@@ -0,0 +1,67 @@
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, Clone, Default)]
+#[structopt(name = "sqlite_datanymizer")]
+pub struct Options {
+    #[structopt(name = "DATABASE", help = "Path to the source database file")]
+    pub database: String,
+
+    #[structopt(
+        short,
+        long,
+        help = "Path to config file",
+        default_value = "./config.yml"
+    )]
+    pub config: String,
+
+    #[structopt(
+        short,
+        long,
+        name = "FILE",
+        help = "Path to dump file, example: /tmp/dump.sql"
+    )]
+    pub file: Option<String>,
+
+    #[structopt(
+        long = "target-db",
+        name = "TARGET_DB",
+        conflicts_with = "FILE",
+        help = "Path to a new database file. The anonymized copy is written into it instead of SQL text"
+    )]
+    pub target_db: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let options = Options::from_iter(vec![
+            "sqlite_datanymizer",
+            "-c",
+            "some_config.yml",
+            "--target-db",
+            "/tmp/copy.sqlite",
+            "app.sqlite",
+        ]);
+
+        assert_eq!(options.database, "app.sqlite");
+        assert_eq!(options.config, "some_config.yml");
+        assert_eq!(options.target_db, Some("/tmp/copy.sqlite".to_string()));
+        assert_eq!(options.file, None);
+    }
+
+    #[test]
+    fn conflicting_args() {
+        let result = Options::from_iter_safe(vec![
+            "sqlite_datanymizer",
+            "-f",
+            "dump.sql",
+            "--target-db",
+            "copy.sqlite",
+            "app.sqlite",
+        ]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use datanymizer_dumper::postgres::escaper::replace_chars;
+
+fn values() -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "plain",
+            "Some plain value without special characters".repeat(4),
+        ),
+        ("multiline", "First line\r\nSecond\tline\n".repeat(8)),
+        ("multibyte", "Строка с\tсимволами\n".repeat(8)),
+        ("bytea", format!("\\x{}", "89504e470d0a1a0a".repeat(64))),
+    ]
+}
+
+fn escaper(c: &mut Criterion) {
+    let mut group = c.benchmark_group("replace_chars");
+
+    for (name, value) in values() {
+        group.throughput(Throughput::Bytes(value.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &value, |b, value| {
+            b.iter(|| {
+                let mut s = value.clone();
+                replace_chars(black_box(&mut s));
+                s
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, escaper);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use datanymizer_dumper::{
+    postgres::{column::PgColumn, row::RowTransformer, table::PgTable},
+    Table,
+};
+use datanymizer_engine::{Engine, Settings};
+
+const COLUMNS: usize = 80;
+
+fn table() -> PgTable {
+    let mut table = PgTable::new("wide".to_string(), "public".to_string());
+    table.set_columns(
+        (0..COLUMNS)
+            .map(|i| PgColumn {
+                position: i as i32 + 1,
+                name: format!("column_{}", i),
+                data_type: String::new(),
+                inner_type: Some(25),
+                not_null: false,
+            })
+            .collect(),
+    );
+    table
+}
+
+fn engine(transformed: usize) -> Engine {
+    let rules: String = (0..transformed)
+        .map(|i| format!("      column_{}:\n        capitalize: ~\n", i * 7))
+        .collect();
+    let config = format!("tables:\n  - name: wide\n    rules:\n{}", rules);
+    Engine::new(Settings::from_yaml(&config).unwrap())
+}
+
+fn line() -> String {
+    (0..COLUMNS)
+        .map(|i| format!("value number {} with some text", i))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+// The previous row path: the whole line is split into strings and joined back
+fn split_and_join(engine: &Engine, table: &PgTable, line: &str) -> String {
+    let values: Vec<_> = line.split('\t').collect();
+    engine
+        .process_row("wide", table.get_column_indexes(), &values)
+        .unwrap()
+        .join("\t")
+}
+
+fn rows(c: &mut Criterion) {
+    let table = table();
+    let line = line();
+    let mut group = c.benchmark_group("row");
+    group.throughput(Throughput::Bytes(line.len() as u64));
+
+    for transformed in [1, 10] {
+        let engine = engine(transformed);
+
+        group.bench_with_input(
+            BenchmarkId::new("row_transformer", transformed),
+            &line,
+            |b, line| {
+                let mut transformer = RowTransformer::new(&engine, &table, "wide");
+                let mut buf = Vec::with_capacity(line.len() * 2);
+                b.iter(|| {
+                    buf.clear();
+                    transformer.write_line(black_box(line), &mut buf).unwrap();
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("split_and_join", transformed),
+            &line,
+            |b, line| b.iter(|| split_and_join(&engine, &table, black_box(line))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, rows);
+criterion_main!(benches);
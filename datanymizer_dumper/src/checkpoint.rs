@@ -0,0 +1,208 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/// The checkpoint name for the schema (pre-data) stage
+pub const PRE_DATA: &str = "pre-data";
+
+/// The checkpoint name for large objects
+pub const LARGE_OBJECTS: &str = "large-objects";
+
+const EXTENSION: &str = "checkpoint";
+
+/// Checkpoint file for resumable dumps.
+/// It records which stages (the schema and every table) are completely written to the dump
+/// and the dump size (byte offset) at that moment, one `name<TAB>offset` line per stage.
+/// Since tables are dumped in a deterministic order, an interrupted dump can be truncated
+/// to the last offset and continued from the next table.
+pub struct Checkpoint {
+    path: PathBuf,
+    entries: Vec<(String, u64)>,
+}
+
+impl Checkpoint {
+    /// Checkpoint path for the dump file (`<dump file>.checkpoint`)
+    pub fn path_for<P: AsRef<Path>>(dump_path: P) -> PathBuf {
+        let mut path = dump_path.as_ref().as_os_str().to_owned();
+        path.push(".");
+        path.push(EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// Starts a new (empty) checkpoint file, an existing one is overwritten
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        File::create(path.as_ref())?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            entries: vec![],
+        })
+    }
+
+    /// Reads an existing checkpoint file or starts a new one if there is no such file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = match fs::read_to_string(path.as_ref()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Self::create(path),
+            Err(e) => return Err(e.into()),
+        };
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(parse_entry)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Is the stage (the schema or a table) already written?
+    pub fn is_done(&self, name: &str) -> bool {
+        self.entries.iter().any(|(n, _)| n == name)
+    }
+
+    /// Dump size at the last checkpoint (the dump should be truncated to it before resuming)
+    pub fn offset(&self) -> u64 {
+        self.entries.last().map_or(0, |(_, offset)| *offset)
+    }
+
+    /// Marks the stage as written. `offset` is the dump size after the stage.
+    pub fn record(&mut self, name: &str, offset: u64) -> Result<()> {
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(file, "{}\t{}", name, offset)?;
+        file.sync_data()?;
+
+        self.entries.push((name.to_string(), offset));
+        Ok(())
+    }
+
+    /// Removes the checkpoint file (when the dump is completed)
+    pub fn remove(self) -> Result<()> {
+        fs::remove_file(&self.path).map_err(|e| e.into())
+    }
+}
+
+fn parse_entry(line: &str) -> Result<(String, u64)> {
+    let (name, offset) = line
+        .rsplit_once('\t')
+        .ok_or_else(|| anyhow!("Invalid checkpoint entry: {}", line))?;
+    let offset = offset
+        .parse()
+        .map_err(|_| anyhow!("Invalid checkpoint offset: {}", line))?;
+
+    Ok((name.to_string(), offset))
+}
+
+/// Writer that counts written bytes (to know checkpoint offsets)
+pub struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sets the initial number of bytes (e.g., when continuing an existing dump)
+    pub fn set_count(&mut self, count: u64) {
+        self.count = count;
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!(
+            "datanymizer_{}_{}.sql.checkpoint",
+            name,
+            process::id()
+        ))
+    }
+
+    #[test]
+    fn path_for() {
+        assert_eq!(
+            Checkpoint::path_for("/tmp/dump.sql"),
+            PathBuf::from("/tmp/dump.sql.checkpoint")
+        );
+    }
+
+    #[test]
+    fn record_and_open() {
+        let path = checkpoint_path("record");
+        let mut checkpoint = Checkpoint::create(&path).unwrap();
+        assert_eq!(checkpoint.offset(), 0);
+
+        checkpoint.record(PRE_DATA, 100).unwrap();
+        checkpoint.record("public.users", 250).unwrap();
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert!(checkpoint.is_done(PRE_DATA));
+        assert!(checkpoint.is_done("public.users"));
+        assert!(!checkpoint.is_done("public.orders"));
+        assert_eq!(checkpoint.offset(), 250);
+
+        checkpoint.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn open_missing() {
+        let path = checkpoint_path("missing");
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert!(!checkpoint.is_done(PRE_DATA));
+        assert_eq!(checkpoint.offset(), 0);
+        assert!(path.exists());
+
+        checkpoint.remove().unwrap();
+    }
+
+    #[test]
+    fn open_invalid() {
+        let path = checkpoint_path("invalid");
+        fs::write(&path, "public.users\tabc\n").unwrap();
+        assert!(Checkpoint::open(&path).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn counting_writer() {
+        let mut writer = CountingWriter::new(vec![]);
+        writer.set_count(10);
+        writer.write_all(b"abc").unwrap();
+        writer.write_all(b"de").unwrap();
+
+        assert_eq!(writer.count(), 15);
+        assert_eq!(writer.into_inner(), b"abcde");
+    }
+}
@@ -0,0 +1,159 @@
+use age::{stream::StreamWriter, x25519, Decryptor, Encryptor, IdentityFile, Recipient};
+use anyhow::{anyhow, Result};
+use std::io::{self, Read, Write};
+
+/// Writer for encrypting the dump with age (X25519) public keys, so only holders of
+/// a matching private key can restore it.
+/// It is intended to be the last link in the writer chain (right before the file or stdout).
+/// The encryption stream must be finalized with `finish` (a dump that is not finished,
+/// e.g. after an error, can't be decrypted).
+pub struct EncryptedWriter<W: Write> {
+    inner: StreamWriter<W>,
+}
+
+impl<W: Write> EncryptedWriter<W> {
+    /// Wraps the `output` writer. Recipients are age public keys (`age1...`).
+    pub fn new<T: AsRef<str>>(output: W, recipients: &[T]) -> Result<Self> {
+        let recipients = parse_recipients(recipients)?;
+        let encryptor = Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn Recipient))?;
+
+        Ok(Self {
+            inner: encryptor.wrap_output(output)?,
+        })
+    }
+
+    /// Finalizes the encryption stream and returns the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        self.inner.finish()
+    }
+}
+
+impl<W: Write> Write for EncryptedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts the dump from `input` to `output` with private keys from the age identity file.
+/// Returns the number of written (decrypted) bytes.
+pub fn decrypt<R: Read, W: Write>(input: R, output: &mut W, identity_file: &str) -> Result<u64> {
+    let identities = IdentityFile::from_file(identity_file.to_string())?.into_identities()?;
+    let mut reader = Decryptor::new(input)?.decrypt(identities.iter().map(|i| i.as_ref()))?;
+
+    Ok(io::copy(&mut reader, output)?)
+}
+
+fn parse_recipients<T: AsRef<str>>(recipients: &[T]) -> Result<Vec<x25519::Recipient>> {
+    if recipients.is_empty() {
+        return Err(anyhow!("No recipients for encryption"));
+    }
+
+    recipients
+        .iter()
+        .map(|r| {
+            r.as_ref()
+                .parse()
+                .map_err(|e| anyhow!("Invalid recipient {}: {}", r.as_ref(), e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::secrecy::ExposeSecret;
+    use std::{env, fs, process};
+
+    fn identity_file(identity: &x25519::Identity, name: &str) -> String {
+        let path = env::temp_dir().join(format!("datanymizer_{}_{}.key", name, process::id()));
+        fs::write(&path, identity.to_string().expose_secret()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn encrypt(data: &[u8], recipients: &[String]) -> Vec<u8> {
+        let mut writer = EncryptedWriter::new(vec![], recipients).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let identity = x25519::Identity::generate();
+        let key_path = identity_file(&identity, "encrypt_and_decrypt");
+
+        let dump = b"COPY public.users(id, name) FROM STDIN;\n1\tJohn\n\\.\n";
+        let encrypted = encrypt(dump, &[identity.to_public().to_string()]);
+        assert_ne!(encrypted.as_slice(), &dump[..]);
+
+        let mut decrypted = vec![];
+        let len = decrypt(encrypted.as_slice(), &mut decrypted, &key_path).unwrap();
+        assert_eq!(len, dump.len() as u64);
+        assert_eq!(decrypted.as_slice(), &dump[..]);
+
+        fs::remove_file(key_path).unwrap();
+    }
+
+    #[test]
+    fn several_recipients() {
+        let identity1 = x25519::Identity::generate();
+        let identity2 = x25519::Identity::generate();
+        let key_path = identity_file(&identity2, "several_recipients");
+
+        let encrypted = encrypt(
+            b"dump",
+            &[
+                identity1.to_public().to_string(),
+                identity2.to_public().to_string(),
+            ],
+        );
+
+        let mut decrypted = vec![];
+        decrypt(encrypted.as_slice(), &mut decrypted, &key_path).unwrap();
+        assert_eq!(decrypted.as_slice(), b"dump");
+
+        fs::remove_file(key_path).unwrap();
+    }
+
+    #[test]
+    fn wrong_identity() {
+        let identity = x25519::Identity::generate();
+        let other_identity = x25519::Identity::generate();
+        let key_path = identity_file(&other_identity, "wrong_identity");
+
+        let encrypted = encrypt(b"dump", &[identity.to_public().to_string()]);
+
+        let mut decrypted = vec![];
+        assert!(decrypt(encrypted.as_slice(), &mut decrypted, &key_path).is_err());
+
+        fs::remove_file(key_path).unwrap();
+    }
+
+    #[test]
+    fn invalid_recipient() {
+        assert!(EncryptedWriter::new(vec![], &["invalid"]).is_err());
+        assert!(EncryptedWriter::new(vec![], &Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn not_finished() {
+        let identity = x25519::Identity::generate();
+        let key_path = identity_file(&identity, "not_finished");
+
+        let mut encrypted = vec![];
+        {
+            let mut writer =
+                EncryptedWriter::new(&mut encrypted, &[identity.to_public().to_string()]).unwrap();
+            writer.write_all(b"dump").unwrap();
+        }
+
+        // The stream is not finalized on drop (e.g., after a failed dump)
+        let mut decrypted = vec![];
+        assert!(decrypt(encrypted.as_slice(), &mut decrypted, &key_path).is_err());
+
+        fs::remove_file(key_path).unwrap();
+    }
+}
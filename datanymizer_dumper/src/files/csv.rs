@@ -0,0 +1,168 @@
+//! CSV (and TSV) files with headers. Column names are taken from headers.
+//! There are no NULLs in CSV: empty values are passed to transformers as is,
+//! and `\N` from transformers (and values of excluded columns) are written as empty values.
+
+use super::{check_settings, Columns};
+use crate::indicator::Indicator;
+use anyhow::Result;
+use datanymizer_engine::Engine;
+use std::{
+    borrow::Cow,
+    io::{Read, Write},
+};
+
+const NULL: &str = "\\N";
+
+pub struct CsvTransformer<I: Indicator> {
+    engine: Engine,
+    indicator: I,
+    delimiter: u8,
+}
+
+impl<I: Indicator> CsvTransformer<I> {
+    pub fn new(engine: Engine, indicator: I) -> Result<Self> {
+        check_settings(&engine.settings)?;
+        Ok(Self {
+            engine,
+            indicator,
+            delimiter: b',',
+        })
+    }
+
+    /// The field delimiter of both input and output (`,` by default)
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    /// Transforms a file with the rules of the table with one of `table_names`
+    /// (see `files::table_names`)
+    pub fn transform<T, R, W>(&self, table_names: &[T], reader: R, writer: W) -> Result<()>
+    where
+        T: AsRef<str>,
+        R: Read,
+        W: Write,
+    {
+        let cfg = self.engine.settings.find_table(table_names);
+        if let Some(name) = table_names.first() {
+            self.indicator
+                .debug_msg(&format!("Transform file: {}", name.as_ref()));
+        }
+
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .from_reader(reader);
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer);
+
+        let headers = reader.headers()?.clone();
+        let names: Vec<&str> = headers.iter().collect();
+        let columns = Columns::new(&names, cfg)?;
+        writer.write_record(
+            names
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| columns.is_kept(*i))
+                .map(|(_, name)| name),
+        )?;
+
+        let mut record = ::csv::StringRecord::new();
+        while reader.read_record(&mut record)? {
+            let values: Vec<&str> = record.iter().collect();
+            let transformed = match cfg {
+                Some(cfg) => self
+                    .engine
+                    .process_row(&cfg.name, &columns.indexes, &values)?,
+                None => values.iter().map(|&v| Cow::Borrowed(v)).collect(),
+            };
+
+            writer.write_record(
+                transformed
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| columns.is_kept(*i))
+                    .map(|(i, value)| {
+                        if value == NULL || columns.is_excluded(i) {
+                            ""
+                        } else {
+                            value.as_ref()
+                        }
+                    }),
+            )?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::SilentIndicator;
+    use datanymizer_engine::Settings;
+
+    const CONFIG: &str = r#"
+      tables:
+        - name: users
+          rules:
+            email:
+              template:
+                format: "user-{{ _0 }}"
+            phone:
+              template:
+                format: '\N'
+          exclude_columns:
+            - password
+          drop_columns:
+            - token
+    "#;
+
+    fn transform(names: &[&str], delimiter: u8, input: &str) -> Result<String> {
+        let engine = Engine::new(Settings::from_yaml(CONFIG).unwrap());
+        let mut transformer = CsvTransformer::new(engine, SilentIndicator)?;
+        transformer.set_delimiter(delimiter);
+        let mut out = vec![];
+        transformer.transform(names, input.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn csv() {
+        let input = "id,email,phone,password,token\n\
+                     1,a@b.c,123,secret,abc\n\
+                     2,\"x, \"\"y\"\"\",,,\n";
+        assert_eq!(
+            transform(&["users.csv", "users"], b',', input).unwrap(),
+            "id,email,phone,password\n\
+             1,user-a@b.c,,\n\
+             2,\"user-x, \"\"y\"\"\",,\n"
+        );
+    }
+
+    #[test]
+    fn tsv() {
+        let input = "token\temail\tid\tphone\tpassword\nabc\ta@b.c\t1\t123\tsecret\n";
+        assert_eq!(
+            transform(&["users"], b'\t', input).unwrap(),
+            "email\tid\tphone\tpassword\nuser-a@b.c\t1\t\t\n"
+        );
+    }
+
+    #[test]
+    fn without_rules() {
+        let input = "id,email\n1,a@b.c\n";
+        assert_eq!(
+            transform(&["other.csv", "other"], b',', input).unwrap(),
+            input
+        );
+    }
+
+    #[test]
+    fn invalid_files() {
+        // An unknown column for rules
+        assert!(transform(&["users"], b',', "id,phone,password,token\n1,2,3,4\n").is_err());
+        // A wrong number of values
+        assert!(transform(&["other"], b',', "id,email\n1\n").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Anonymization of data files (without a database).
+//! Rules are matched by file names: the file `users.csv` matches the tables `users.csv` and `users`.
+
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Settings, Table as TableCfg, Transformers};
+use std::{collections::HashMap, path::Path};
+
+pub mod csv;
+pub mod ndjson;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Names of the table config for the file (the file name and the file name without the extension)
+pub fn table_names<P: AsRef<Path>>(path: P) -> Vec<String> {
+    let path = path.as_ref();
+    let mut names = vec![];
+    if let Some(name) = path.file_name() {
+        names.push(name.to_string_lossy().to_string());
+    }
+    if let Some(stem) = path.file_stem() {
+        let stem = stem.to_string_lossy().to_string();
+        if !names.contains(&stem) {
+            names.push(stem);
+        }
+    }
+    names
+}
+
+/// Columns of a file with `exclude_columns` and `drop_columns` of the table config
+pub struct Columns {
+    pub indexes: HashMap<String, usize>,
+    /// Indexes of excluded columns (their values are cleared)
+    pub excluded: Vec<usize>,
+    /// Indexes of dropped columns (they are omitted from the output)
+    pub dropped: Vec<usize>,
+}
+
+impl Columns {
+    pub fn new<T: AsRef<str>>(names: &[T], cfg: Option<&TableCfg>) -> Result<Self> {
+        let indexes: HashMap<String, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_ref().to_string(), i))
+            .collect();
+        let mut columns = Self {
+            indexes,
+            excluded: vec![],
+            dropped: vec![],
+        };
+
+        if let Some(cfg) = cfg {
+            columns.excluded = columns.find(&cfg.exclude_columns, cfg)?;
+            columns.dropped = columns.find(&cfg.drop_columns, cfg)?;
+        }
+
+        Ok(columns)
+    }
+
+    pub fn is_kept(&self, index: usize) -> bool {
+        !self.dropped.contains(&index)
+    }
+
+    pub fn is_excluded(&self, index: usize) -> bool {
+        self.excluded.contains(&index)
+    }
+
+    fn find(&self, names: &[String], cfg: &TableCfg) -> Result<Vec<usize>> {
+        names
+            .iter()
+            .map(|name| {
+                if cfg.rules.contains_key(name) {
+                    return Err(anyhow!(
+                        "Column {} in the table {} can't be excluded (or dropped) and transformed at the same time",
+                        name,
+                        cfg.name
+                    ));
+                }
+                self.indexes
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| anyhow!("Unknown column {} in the table {}", name, cfg.name))
+            })
+            .collect()
+    }
+}
+
+// Features that require a database
+fn check_settings(settings: &Settings) -> Result<()> {
+    if settings.subset.is_some() {
+        return Err(anyhow!("Subsets are not supported for data files"));
+    }
+    if settings.large_objects.is_some() {
+        return Err(anyhow!("Large objects are not supported for data files"));
+    }
+    for table in settings.tables.iter() {
+        if table.query.is_some() {
+            return Err(anyhow!(
+                "Queries are not supported for data files (table {})",
+                table.name
+            ));
+        }
+        if let Some(column) = table
+            .rules
+            .iter()
+            .find_map(|(name, rule)| matches!(rule, Transformers::Sql(_)).then_some(name))
+        {
+            return Err(anyhow!(
+                "SQL rules are not supported for data files (table {}, column {})",
+                table.name,
+                column
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(table_names("/tmp/users.csv"), vec!["users.csv", "users"]);
+        assert_eq!(table_names("users"), vec!["users"]);
+        assert_eq!(
+            table_names("export/users.2021.tsv"),
+            vec!["users.2021.tsv", "users.2021"]
+        );
+    }
+
+    #[test]
+    fn columns() {
+        let settings = Settings::from_yaml(
+            r#"
+            tables:
+              - name: users
+                rules: {}
+                exclude_columns:
+                  - password
+                drop_columns:
+                  - token
+            "#,
+        )
+        .unwrap();
+        let cfg = settings.find_table(&["users"]);
+        let columns = Columns::new(&["id", "token", "password"], cfg).unwrap();
+
+        assert_eq!(columns.indexes["password"], 2);
+        assert!(columns.is_excluded(2));
+        assert!(!columns.is_kept(1));
+        assert!(columns.is_kept(2));
+
+        assert!(Columns::new(&["id", "password"], cfg).is_err());
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let settings = Settings::from_yaml(
+            r#"
+            tables:
+              - name: users
+                rules:
+                  email:
+                    sql: "md5(email)"
+            "#,
+        )
+        .unwrap();
+        assert!(check_settings(&settings).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! NDJSON (JSON Lines) files: one JSON object per line.
+//! Leaf fields of objects are columns: nested fields are named with dots (`user.email`),
+//! array elements - with indexes (`tags.0`). Records can have different fields:
+//! rules for missing fields are skipped (transformers get `\N` for them).
+//!
+//! Field types are kept: transformed numbers and booleans are written as numbers and booleans
+//! (if they are still valid), nulls stay null, and `\N` from transformers is written as null.
+
+use super::check_settings;
+use crate::indicator::Indicator;
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Engine, Table as TableCfg};
+use serde_json::{Number, Value};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+const NULL: &str = "\\N";
+
+pub struct NdjsonTransformer<I: Indicator> {
+    engine: Engine,
+    indicator: I,
+}
+
+impl<I: Indicator> NdjsonTransformer<I> {
+    pub fn new(engine: Engine, indicator: I) -> Result<Self> {
+        check_settings(&engine.settings)?;
+        Ok(Self { engine, indicator })
+    }
+
+    /// Transforms a file with the rules of the table with one of `table_names`
+    /// (see `files::table_names`)
+    pub fn transform<T, R, W>(&self, table_names: &[T], reader: R, mut writer: W) -> Result<()>
+    where
+        T: AsRef<str>,
+        R: BufRead,
+        W: Write,
+    {
+        let cfg = self.engine.settings.find_table(table_names);
+        if let Some(name) = table_names.first() {
+            self.indicator
+                .debug_msg(&format!("Transform file: {}", name.as_ref()));
+        }
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            match cfg {
+                Some(cfg) if !line.trim().is_empty() => {
+                    let mut record: Value = serde_json::from_str(&line)
+                        .map_err(|e| anyhow!("Invalid JSON in the line {}: {}", i + 1, e))?;
+                    if !record.is_object() {
+                        return Err(anyhow!("The line {} is not a JSON object", i + 1));
+                    }
+                    self.transform_record(cfg, &mut record)?;
+                    serde_json::to_writer(&mut writer, &record)?;
+                }
+                _ => writer.write_all(line.as_bytes())?,
+            }
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn transform_record(&self, cfg: &TableCfg, record: &mut Value) -> Result<()> {
+        for name in cfg.drop_columns.iter() {
+            remove_field(record, name);
+        }
+
+        let mut names = vec![];
+        let mut fields = vec![];
+        leaves(record, String::new(), &mut names, &mut fields);
+
+        let mut values: Vec<String> = fields.iter().map(|f| to_string(f)).collect();
+        let mut column_indexes: HashMap<String, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+        for name in cfg.rules.keys() {
+            if !column_indexes.contains_key(name) {
+                column_indexes.insert(name.clone(), values.len());
+                values.push(NULL.to_string());
+            }
+        }
+
+        let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+        let transformed = self
+            .engine
+            .process_row(&cfg.name, &column_indexes, &values)?;
+        for (field, value) in fields.iter_mut().zip(transformed) {
+            if let Cow::Owned(value) = value {
+                if !field.is_null() {
+                    **field = from_string(field, value);
+                }
+            }
+        }
+
+        for name in cfg.exclude_columns.iter() {
+            if let Some(field) = record.pointer_mut(&pointer(name)) {
+                *field = Value::Null;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Collects names and values of leaf fields (empty objects and arrays are not columns)
+fn leaves<'a>(
+    value: &'a mut Value,
+    name: String,
+    names: &mut Vec<String>,
+    fields: &mut Vec<&'a mut Value>,
+) {
+    let prefix = |key: &str| {
+        if name.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", name, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                leaves(value, prefix(key), names, fields);
+            }
+        }
+        Value::Array(array) => {
+            for (i, value) in array.iter_mut().enumerate() {
+                leaves(value, prefix(&i.to_string()), names, fields);
+            }
+        }
+        _ => {
+            names.push(name);
+            fields.push(value);
+        }
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => NULL.to_string(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+// The value of the original type (if it is possible)
+fn from_string(original: &Value, value: String) -> Value {
+    if value == NULL {
+        return Value::Null;
+    }
+
+    match original {
+        Value::Number(_) => match serde_json::from_str::<Number>(&value) {
+            Ok(number) => Value::Number(number),
+            Err(_) => Value::String(value),
+        },
+        Value::Bool(_) => match value.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(value),
+        },
+        _ => Value::String(value),
+    }
+}
+
+/// A JSON pointer for the dotted field name
+fn pointer(name: &str) -> String {
+    name.split('.')
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+fn remove_field(record: &mut Value, name: &str) {
+    let (parent, key) = match name.rsplit_once('.') {
+        Some((parent, key)) => (record.pointer_mut(&pointer(parent)), key),
+        None => (Some(record), name),
+    };
+    match parent {
+        Some(Value::Object(map)) => {
+            map.remove(key);
+        }
+        Some(Value::Array(array)) => {
+            if let Ok(i) = key.parse::<usize>() {
+                if i < array.len() {
+                    array.remove(i);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::SilentIndicator;
+    use datanymizer_engine::Settings;
+
+    const CONFIG: &str = r#"
+      tables:
+        - name: events
+          rules:
+            user.email:
+              template:
+                format: "user-{{ _0 }}"
+            user.age:
+              template:
+                format: "42"
+            user.phone:
+              template:
+                format: '\N'
+            active:
+              template:
+                format: "false"
+            score:
+              template:
+                format: "high"
+            tags.1:
+              template:
+                format: "tag"
+            note:
+              template:
+                format: "filled"
+          exclude_columns:
+            - user.address
+          drop_columns:
+            - token
+            - user.ip
+    "#;
+
+    fn transform(names: &[&str], input: &str) -> Result<String> {
+        let engine = Engine::new(Settings::from_yaml(CONFIG).unwrap());
+        let transformer = NdjsonTransformer::new(engine, SilentIndicator)?;
+        let mut out = vec![];
+        transformer.transform(names, input.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn records() {
+        let input = r#"{"id":1,"user":{"email":"a@b.c","age":30,"phone":"123","ip":"1.1.1.1","address":{"city":"X"}},"active":true,"score":5,"tags":["a","b"],"note":null,"token":"t"}
+{"id":2,"user":{"email":"d@e.f"},"extra":[]}
+"#;
+        assert_eq!(
+            transform(&["events.ndjson", "events"], input).unwrap(),
+            r#"{"id":1,"user":{"email":"user-a@b.c","age":42,"phone":null,"address":null},"active":false,"score":"high","tags":["a","tag"],"note":null}
+{"id":2,"user":{"email":"user-d@e.f"},"extra":[]}
+"#
+        );
+    }
+
+    #[test]
+    fn without_rules() {
+        let input = "{\"b\": 1, \"a\": [1, 2]}\n\nnot json\n";
+        assert_eq!(transform(&["other"], input).unwrap(), input);
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert!(transform(&["events"], "{\"id\": 1\n").is_err());
+        assert!(transform(&["events"], "[1, 2]\n").is_err());
+    }
+
+    #[test]
+    fn pointers() {
+        assert_eq!(pointer("user.email"), "/user/email");
+        assert_eq!(pointer("a/b~c"), "/a~1b~0c");
+    }
+}
@@ -0,0 +1,389 @@
+//! Parquet files. Columns of rules are top-level columns of the file.
+//! Values are passed to transformers as strings: nulls are `\N`, binary values are hex strings
+//! with the `\x` prefix (as PostgreSQL `bytea`), other values are formatted as Arrow does it
+//! (e.g., timestamps in the ISO 8601 format). Transformed values are parsed back to column types.
+
+use super::{check_settings, Columns};
+use crate::indicator::Indicator;
+use anyhow::{anyhow, Result};
+use arrow_array::{new_null_array, Array, ArrayRef, BinaryArray, RecordBatch, StringArray};
+use arrow_cast::{
+    cast_with_options,
+    display::{ArrayFormatter, FormatOptions},
+    CastOptions,
+};
+use arrow_schema::{DataType, Field, Schema};
+use datanymizer_engine::{Engine, Table as TableCfg};
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    basic::Compression,
+    file::{properties::WriterProperties, reader::ChunkReader},
+};
+use std::{io::Write, sync::Arc, time::Instant};
+
+const NULL: &str = "\\N";
+
+pub struct ParquetTransformer<I: Indicator> {
+    engine: Engine,
+    indicator: I,
+}
+
+impl<I: Indicator> ParquetTransformer<I> {
+    pub fn new(engine: Engine, indicator: I) -> Result<Self> {
+        check_settings(&engine.settings)?;
+        Ok(Self { engine, indicator })
+    }
+
+    /// Transforms a file with the rules of the table with one of `table_names`
+    /// (see `files::table_names`)
+    pub fn transform<T, R, W>(&self, table_names: &[T], reader: R, writer: W) -> Result<()>
+    where
+        T: AsRef<str>,
+        R: ChunkReader + 'static,
+        W: Write + Send,
+    {
+        let cfg = self.engine.settings.find_table(table_names);
+        let name = table_names.first().map(|n| n.as_ref()).unwrap_or_default();
+        self.indicator
+            .debug_msg(&format!("Transform file: {}", name));
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?;
+        let size = builder.metadata().file_metadata().num_rows() as u64;
+        let schema = builder.schema().clone();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        let columns = Columns::new(&names, cfg)?;
+
+        // Transformed and excluded columns can contain nulls
+        let fields: Vec<Field> = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| columns.is_kept(*i))
+            .map(|(i, field)| {
+                let changed = columns.is_excluded(i)
+                    || cfg.is_some_and(|c| c.rules.contains_key(field.name()));
+                field
+                    .as_ref()
+                    .clone()
+                    .with_nullable(field.is_nullable() || changed)
+            })
+            .collect();
+        let output_schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+        let started = Instant::now();
+        let mut writer = ArrowWriter::try_new(writer, output_schema.clone(), Some(properties()))?;
+        self.indicator.start_pb(size, name);
+        for batch in builder.build()? {
+            let batch = batch?;
+            let batch = match cfg {
+                Some(cfg) => self.transform_batch(cfg, &columns, &batch, &output_schema)?,
+                None => batch,
+            };
+            writer.write(&batch)?;
+            self.indicator.inc_pb(batch.num_rows() as u64);
+        }
+        writer.close()?;
+        self.indicator.finish_pb(name, started.elapsed());
+
+        Ok(())
+    }
+
+    fn transform_batch(
+        &self,
+        cfg: &TableCfg,
+        columns: &Columns,
+        batch: &RecordBatch,
+        output_schema: &Arc<Schema>,
+    ) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let transformed_columns: Vec<bool> = schema
+            .fields()
+            .iter()
+            .map(|f| cfg.rules.contains_key(f.name()))
+            .collect();
+
+        let input: Vec<Vec<Option<String>>> = batch
+            .columns()
+            .iter()
+            .map(|c| array_values(c.as_ref()))
+            .collect::<Result<_>>()?;
+        let mut output: Vec<Vec<Option<String>>> = vec![vec![]; input.len()];
+        for row in 0..batch.num_rows() {
+            let values: Vec<&str> = input
+                .iter()
+                .map(|column| column[row].as_deref().unwrap_or(NULL))
+                .collect();
+            let transformed = self
+                .engine
+                .process_row(&cfg.name, &columns.indexes, &values)?;
+            for (i, value) in transformed.into_iter().enumerate() {
+                if transformed_columns[i] {
+                    output[i].push(Some(value.into_owned()).filter(|v| v != NULL));
+                }
+            }
+        }
+
+        let mut arrays = vec![];
+        for (i, field) in schema.fields().iter().enumerate() {
+            if !columns.is_kept(i) {
+                continue;
+            }
+            let array = if columns.is_excluded(i) {
+                new_null_array(field.data_type(), batch.num_rows())
+            } else if transformed_columns[i] {
+                typed_array(&output[i], field.data_type()).map_err(|e| {
+                    anyhow!(
+                        "Invalid transformed value of the column {}: {}",
+                        field.name(),
+                        e
+                    )
+                })?
+            } else {
+                batch.column(i).clone()
+            };
+            arrays.push(array);
+        }
+
+        Ok(RecordBatch::try_new(output_schema.clone(), arrays)?)
+    }
+}
+
+pub(crate) fn properties() -> WriterProperties {
+    WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build()
+}
+
+/// Values of the array as strings (`None` for nulls)
+pub(crate) fn array_values(array: &dyn Array) -> Result<Vec<Option<String>>> {
+    let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+    let binary = is_binary(array.data_type());
+    Ok((0..array.len())
+        .map(|i| {
+            if array.is_null(i) {
+                None
+            } else if binary {
+                Some(format!("\\x{}", formatter.value(i)))
+            } else {
+                Some(formatter.value(i).to_string())
+            }
+        })
+        .collect())
+}
+
+/// An array of the given type from strings (`None` for nulls).
+/// Binary values are decoded from hex strings with the `\x` prefix (other strings are stored as is).
+pub(crate) fn typed_array<S: AsRef<str>>(
+    values: &[Option<S>],
+    data_type: &DataType,
+) -> Result<ArrayRef> {
+    let array: ArrayRef = if is_binary(data_type) {
+        Arc::new(
+            values
+                .iter()
+                .map(|v| v.as_ref().map(|v| decode_binary(v.as_ref())))
+                .collect::<BinaryArray>(),
+        )
+    } else {
+        Arc::new(
+            values
+                .iter()
+                .map(|v| v.as_ref().map(|v| v.as_ref()))
+                .collect::<StringArray>(),
+        )
+    };
+
+    if array.data_type() == data_type {
+        return Ok(array);
+    }
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    Ok(cast_with_options(&array, data_type, &options)?)
+}
+
+fn is_binary(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_)
+    )
+}
+
+fn decode_binary(value: &str) -> Vec<u8> {
+    let hex = value
+        .strip_prefix("\\\\x")
+        .or_else(|| value.strip_prefix("\\x"))
+        .filter(|h| h.len() % 2 == 0 && h.bytes().all(|b| b.is_ascii_hexdigit()));
+    match hex {
+        Some(hex) => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default())
+            .collect(),
+        None => value.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::SilentIndicator;
+    use arrow_array::{
+        cast::AsArray,
+        types::{Float64Type, Int32Type, TimestampMicrosecondType},
+        Float64Array, Int32Array, TimestampMicrosecondArray,
+    };
+    use arrow_schema::TimeUnit;
+    use datanymizer_engine::Settings;
+    use std::{fs::File, path::PathBuf};
+
+    const CONFIG: &str = r#"
+      tables:
+        - name: users
+          rules:
+            email:
+              template:
+                format: "user-{{ _0 }}"
+            score:
+              template:
+                format: "{% if _0 == '\\N' %}\\N{% else %}1.5{% endif %}"
+            data:
+              template:
+                format: '\x0a0b'
+            created_at:
+              template:
+                format: "2020-01-02T03:04:05Z"
+          exclude_columns:
+            - password
+          drop_columns:
+            - token
+    "#;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("datanymizer_{}.parquet", name))
+    }
+
+    fn write_source(path: &PathBuf) {
+        let utc = Some("UTC".into());
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("email", DataType::Utf8, false),
+            Field::new("score", DataType::Float64, true),
+            Field::new("data", DataType::Binary, true),
+            Field::new(
+                "created_at",
+                DataType::Timestamp(TimeUnit::Microsecond, utc.clone()),
+                false,
+            ),
+            Field::new("password", DataType::Utf8, false),
+            Field::new("token", DataType::Utf8, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["a@b.c", "d@e.f"])),
+            Arc::new(Float64Array::from(vec![Some(10.0), None])),
+            Arc::new(BinaryArray::from(vec![Some(&b"\x01"[..]), None])),
+            Arc::new(TimestampMicrosecondArray::from(vec![0, 1]).with_timezone_opt(utc)),
+            Arc::new(StringArray::from(vec!["secret", "secret"])),
+            Arc::new(StringArray::from(vec![Some("t1"), None])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+
+        let mut writer = ArrowWriter::try_new(File::create(path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    fn transform(config: &str, names: &[&str], src: &PathBuf, dst: &PathBuf) -> Result<()> {
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+        let transformer = ParquetTransformer::new(engine, SilentIndicator)?;
+        transformer.transform(names, File::open(src)?, File::create(dst)?)
+    }
+
+    fn read(path: &PathBuf) -> RecordBatch {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        batches[0].clone()
+    }
+
+    #[test]
+    fn transform_file() {
+        let (src, dst) = (path("users_src"), path("users_dst"));
+        write_source(&src);
+        transform(CONFIG, &["users.parquet", "users"], &src, &dst).unwrap();
+
+        let batch = read(&dst);
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["id", "email", "score", "data", "created_at", "password"]
+        );
+        assert!(schema.field_with_name("password").unwrap().is_nullable());
+        assert!(!schema.field_with_name("id").unwrap().is_nullable());
+
+        let ids = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ids.values(), &[1, 2]);
+        let emails = batch.column(1).as_string::<i32>();
+        assert_eq!(emails.value(0), "user-a@b.c");
+        assert_eq!(emails.value(1), "user-d@e.f");
+        let scores = batch.column(2).as_primitive::<Float64Type>();
+        assert_eq!(scores.value(0), 1.5);
+        assert!(scores.is_null(1));
+        let data = batch.column(3).as_binary::<i32>();
+        assert_eq!(data.value(0), b"\x0a\x0b");
+        assert_eq!(data.value(1), b"\x0a\x0b");
+        let created = batch.column(4).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(created.value(0), 1_577_934_245_000_000);
+        assert_eq!(created.timezone(), Some("UTC"));
+        assert_eq!(batch.column(5).null_count(), 2);
+
+        std::fs::remove_file(src).unwrap();
+        std::fs::remove_file(dst).unwrap();
+    }
+
+    #[test]
+    fn invalid_values() {
+        let config = r#"
+          tables:
+            - name: ids
+              rules:
+                id:
+                  template:
+                    format: "not a number"
+        "#;
+        let (src, dst) = (path("ids_src"), path("ids_dst"));
+        write_source(&src);
+        assert!(transform(config, &["ids"], &src, &dst).is_err());
+
+        std::fs::remove_file(src).unwrap();
+        let _ = std::fs::remove_file(dst);
+    }
+
+    #[test]
+    fn values() {
+        let binary = BinaryArray::from(vec![Some(&b"\x00\xff"[..]), None]);
+        assert_eq!(
+            array_values(&binary).unwrap(),
+            vec![Some("\\x00ff".to_string()), None]
+        );
+
+        let array = typed_array(&[Some("\\x00ff"), Some("raw"), None], &DataType::Binary).unwrap();
+        let array = array.as_binary::<i32>();
+        assert_eq!(array.value(0), b"\x00\xff");
+        assert_eq!(array.value(1), b"raw");
+        assert!(array.is_null(2));
+
+        let array = typed_array(&[Some("t"), Some("false")], &DataType::Boolean).unwrap();
+        assert_eq!(
+            array_values(&array).unwrap(),
+            vec![Some("true".into()), Some("false".into())]
+        );
+
+        assert!(typed_array(&[Some("x")], &DataType::Int64).is_err());
+    }
+}
@@ -1,12 +1,19 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use core::iter::Iterator;
-use datanymizer_engine::{Filter, Settings};
+use datanymizer_engine::{Filter, Settings, Table as TableCfg, TablePattern};
 use indicatif::HumanDuration;
 use solvent::DepGraph;
 use std::{collections::HashMap, hash::Hash, time::Instant};
 
+pub mod checkpoint;
+pub mod encryption;
+pub mod files;
 pub mod indicator;
+#[cfg(feature = "mysql")]
+pub mod mysql;
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 // Dumper makes dump with same stages
 pub trait Dumper: 'static + Sized + Send {
@@ -34,9 +41,10 @@ fn dump(&mut self, connection: &mut Self::Connection) -> Result<()> {
     /// This stage makes dump foreign keys, indices and other...
     fn post_data(&mut self, _connection: &mut Self::Connection) -> Result<()>;
 
-    fn filter_table(&mut self, table: String, filter: &Option<Filter>) -> bool {
+    /// Filters a table by its names (e.g., a partition is also filtered by the names of its parents)
+    fn filter_table<T: AsRef<str>>(&mut self, names: &[T], filter: &Option<Filter>) -> bool {
         if let Some(f) = filter {
-            f.filter_schema(&table) && f.filter_data(&table)
+            f.filter_names(names)
         } else {
             true
         }
@@ -72,29 +80,13 @@ fn get_dependencies(
     ) -> Result<Vec<Self::Table>>;
 
     fn ordered_tables(&self, connection: &mut Self::Connection) -> Vec<(Self::Table, i32)> {
-        let mut res: HashMap<Self::Table, i32> = HashMap::new();
-        let mut depgraph: DepGraph<Self::Table> = DepGraph::new();
         if let Ok(tables) = self.get_tables(connection) {
-            for table in tables.iter() {
-                let deps: Vec<Self::Table> = self
-                    .get_dependencies(connection, table)
-                    .unwrap_or_default()
-                    .into_iter()
-                    .collect();
-                depgraph.register_dependencies(table.clone(), deps);
-            }
-
-            for table in tables.iter() {
-                let _ = res.entry(table.clone()).or_insert(0);
-                if let Ok(nodes) = depgraph.dependencies_of(table) {
-                    for node in nodes.flatten() {
-                        let counter = res.entry(node.clone()).or_insert(0);
-                        *counter += 1;
-                    }
-                }
-            }
+            weigh_by_dependencies(&tables, |table| {
+                self.get_dependencies(connection, table).unwrap_or_default()
+            })
+        } else {
+            vec![]
         }
-        res.iter().map(|(k, b)| (k.clone(), *b)).collect()
     }
 
     /// Get columns for table
@@ -105,6 +97,93 @@ fn get_columns(
     ) -> Result<Vec<Self::Column>>;
 }
 
+/// Weights tables by dependencies: the more tables depend on a table (directly or not),
+/// the more its weight is (so it should be dumped earlier)
+pub fn weigh_by_dependencies<T, F>(tables: &[T], mut dependencies: F) -> Vec<(T, i32)>
+where
+    T: Clone + Eq + Hash,
+    F: FnMut(&T) -> Vec<T>,
+{
+    let mut res: HashMap<T, i32> = HashMap::new();
+    let mut depgraph: DepGraph<T> = DepGraph::new();
+    for table in tables.iter() {
+        depgraph.register_dependencies(table.clone(), dependencies(table));
+    }
+
+    // Inspected tables are inserted first, so they are the keys (not tables from dependencies)
+    for table in tables.iter() {
+        res.insert(table.clone(), 0);
+    }
+    for table in tables.iter() {
+        if let Ok(nodes) = depgraph.dependencies_of(table) {
+            for node in nodes.flatten() {
+                let counter = res.entry(node.clone()).or_insert(0);
+                *counter += 1;
+            }
+        }
+    }
+    res.iter().map(|(k, b)| (k.clone(), *b)).collect()
+}
+
+/// Sorts tables by the table order from the config, then by weights (descending), then by names.
+/// The order must be deterministic (tables with the same weight are sorted by name),
+/// because resumed dumps rely on it. A table is placed by the first matching name or pattern in the order.
+pub fn sort_tables<T, U>(tables: &mut [(T, i32)], order: &[String])
+where
+    T: Table<U>,
+{
+    let patterns: Vec<TablePattern> = order
+        .iter()
+        .filter_map(|i| TablePattern::parse(i).ok())
+        .collect();
+    tables.sort_by_cached_key(|(tbl, weight)| {
+        let names = tbl.get_names();
+        let position = patterns
+            .iter()
+            .position(|p| names.iter().any(|n| p.matches(n)));
+        (position, -weight, tbl.get_full_name())
+    });
+}
+
+/// Checks `exclude_columns` and `drop_columns` from the table config and returns the excluded
+/// and dropped columns of the table. Configs with name patterns may match tables without
+/// some of these columns, so missing columns are skipped for them.
+pub fn column_exclusions<T, U>(table: &T, cfg: &TableCfg) -> Result<(Vec<String>, Vec<String>)>
+where
+    T: Table<U>,
+{
+    let column_indexes = table.get_column_indexes();
+    let is_pattern = TablePattern::is_glob(&cfg.name) || TablePattern::is_regex(&cfg.name);
+    let existing = |names: &Vec<String>| -> Vec<String> {
+        names
+            .iter()
+            .filter(|name| !is_pattern || column_indexes.contains_key(*name))
+            .cloned()
+            .collect()
+    };
+    let excluded = existing(&cfg.exclude_columns);
+    let dropped = existing(&cfg.drop_columns);
+
+    for name in excluded.iter().chain(dropped.iter()) {
+        if !column_indexes.contains_key(name) {
+            return Err(anyhow!(
+                "Unknown column {} in the table {}",
+                name,
+                table.get_full_name()
+            ));
+        }
+        if cfg.rules.contains_key(name) {
+            return Err(anyhow!(
+                "Column {} in the table {} can't be excluded (or dropped) and transformed at the same time",
+                name,
+                table.get_full_name()
+            ));
+        }
+    }
+
+    Ok((excluded, dropped))
+}
+
 /// Table trait for all databases
 pub trait Table<T>: Sized + Send + Clone + Eq + Hash {
     type Column: ColumnData<T>;
@@ -0,0 +1,100 @@
+use crate::ColumnData;
+
+/// Data types with binary values (they are passed to transformers as `\x`-prefixed hex strings)
+const BINARY_TYPES: [&str; 16] = [
+    "binary",
+    "varbinary",
+    "tinyblob",
+    "blob",
+    "mediumblob",
+    "longblob",
+    "bit",
+    "geometry",
+    "point",
+    "linestring",
+    "polygon",
+    "multipoint",
+    "multilinestring",
+    "multipolygon",
+    "geometrycollection",
+    "geomcollection",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MyColumn {
+    /// Position of column in the dumped column list (starting from 1)
+    pub position: usize,
+    /// Column name
+    pub name: String,
+    /// Column data type (`DATA_TYPE` from `information_schema.COLUMNS`)
+    pub data_type: String,
+}
+
+impl MyColumn {
+    pub fn new(position: usize, name: String, data_type: String) -> Self {
+        Self {
+            position,
+            name,
+            data_type,
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        BINARY_TYPES.contains(&self.data_type.to_lowercase().as_str())
+    }
+
+    pub fn quoted_name(&self) -> String {
+        quote_identifier(&self.name)
+    }
+}
+
+impl ColumnData<String> for MyColumn {
+    fn position(&self) -> usize {
+        self.position - 1
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    fn inner_kind(&self) -> Option<String> {
+        Some(self.data_type.clone())
+    }
+}
+
+/// Quotes an identifier with backticks (backticks inside are doubled)
+pub fn quote_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(data_type: &str) -> MyColumn {
+        MyColumn::new(1, "col".to_string(), data_type.to_string())
+    }
+
+    #[test]
+    fn binary() {
+        for t in [
+            "varbinary",
+            "BLOB",
+            "longblob",
+            "bit",
+            "point",
+            "multipolygon",
+        ] {
+            assert!(column(t).is_binary(), "{}", t);
+        }
+        for t in ["varchar", "text", "int", "json", "datetime"] {
+            assert!(!column(t).is_binary(), "{}", t);
+        }
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote_identifier("name"), "`name`");
+        assert_eq!(quote_identifier("na`me"), "`na``me`");
+    }
+}
@@ -0,0 +1,451 @@
+use super::{
+    column::MyColumn, connector, literal, schema_inspector::MySchemaInspector, table::MyTable,
+};
+use crate::{indicator::Indicator, sort_tables, Dumper, SchemaInspector, Table};
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Engine, Filter, Settings};
+use mysql::{prelude::Queryable, AccessMode, TxOpts, Value};
+use std::{
+    borrow::Cow,
+    io::{self, prelude::*},
+    process::{self, Command},
+    time::Instant,
+};
+
+/// Maximum size of an `INSERT` statement (rows are added while the statement is smaller)
+const MAX_INSERT_SIZE: usize = 1024 * 1024;
+
+// The same session settings as in `mysqldump` output (it restores previous settings at its end)
+const DATA_HEADER: &str = "SET NAMES utf8mb4;
+SET @OLD_TIME_ZONE=@@TIME_ZONE, TIME_ZONE='+00:00';
+SET @OLD_FOREIGN_KEY_CHECKS=@@FOREIGN_KEY_CHECKS, FOREIGN_KEY_CHECKS=0;
+SET @OLD_UNIQUE_CHECKS=@@UNIQUE_CHECKS, UNIQUE_CHECKS=0;
+SET @OLD_SQL_MODE=@@SQL_MODE, SQL_MODE='NO_AUTO_VALUE_ON_ZERO';
+";
+
+const DATA_FOOTER: &str = "SET SQL_MODE=@OLD_SQL_MODE;
+SET UNIQUE_CHECKS=@OLD_UNIQUE_CHECKS;
+SET FOREIGN_KEY_CHECKS=@OLD_FOREIGN_KEY_CHECKS;
+SET TIME_ZONE=@OLD_TIME_ZONE;
+";
+
+pub struct MyDumper<W: Write + Send, I: Indicator + Send> {
+    schema_inspector: MySchemaInspector,
+    engine: Engine,
+    dump_writer: W,
+    indicator: I,
+    mysqldump_location: String,
+    mysqldump_args: Vec<String>,
+}
+
+impl<W: 'static + Write + Send, I: 'static + Indicator + Send> MyDumper<W, I> {
+    pub fn new(
+        engine: Engine,
+        mysqldump_location: String,
+        dump_writer: W,
+        indicator: I,
+        mysqldump_args: Vec<String>,
+    ) -> Result<Self> {
+        check_settings(&engine.settings)?;
+
+        Ok(Self {
+            engine,
+            dump_writer,
+            indicator,
+            mysqldump_location,
+            schema_inspector: MySchemaInspector {},
+            mysqldump_args,
+        })
+    }
+
+    fn run_mysqldump(
+        &mut self,
+        section_args: &[&str],
+        connection: &mut connector::Connection,
+    ) -> Result<()> {
+        let opts = connection.opts()?;
+        let tables = self.schema_inspector.get_tables(connection)?;
+        let table_args = table_args(&tables, &self.engine.settings.filter);
+
+        let program = &self.mysqldump_location;
+        let mut args = connector::mysqldump_args(&opts);
+        args.extend(section_args.iter().map(|s| s.to_string()));
+        args.extend(self.mysqldump_args.iter().cloned());
+        args.extend(table_args);
+        args.push(opts.get_db_name().unwrap_or_default().to_string());
+
+        let mut command = Command::new(program);
+        if let Some(pass) = opts.get_pass() {
+            command.env("MYSQL_PWD", pass);
+        }
+        let dump_output = command.args(&args).output()?;
+        if !dump_output.status.success() {
+            eprintln!(
+                "mysqldump error. Command:\n{} {}\nOutput:",
+                program,
+                args.join(" ")
+            );
+
+            io::stderr().write_all(&dump_output.stderr)?;
+            process::exit(1);
+        }
+
+        self.dump_writer
+            .write_all(&dump_output.stdout)
+            .map_err(|e| e.into())
+    }
+
+    fn dump_table<Q: Queryable>(&mut self, table: &MyTable, conn: &mut Q) -> Result<()> {
+        let settings = self.settings();
+        let started = Instant::now();
+
+        self.write_log(format!("Dump table: {}", &table.get_full_name()))?;
+
+        let cfg = settings.find_table(&table.get_names());
+
+        self.indicator
+            .start_pb(table.count_of_query_to(cfg), &table.get_full_name());
+
+        let mut writer = InsertWriter::new(table);
+        let mut count: u64 = 0;
+        if let Some(cfg) = cfg {
+            if let Some(transformed_query) = table.transformed_query_to(Some(cfg)) {
+                for row in conn.query_iter(transformed_query)? {
+                    let values = row_values(&row?, &table.columns)?;
+                    let input: Vec<&str> = values
+                        .iter()
+                        .map(|v| v.as_deref().unwrap_or(literal::NULL))
+                        .collect();
+                    let transformed =
+                        self.engine
+                            .process_row(&cfg.name, table.get_column_indexes(), &input)?;
+                    let transformed: Vec<Option<&str>> = transformed
+                        .iter()
+                        .zip(values.iter())
+                        .map(|(t, v)| {
+                            let changed = matches!(t, Cow::Owned(_));
+                            (!literal::is_null(t, changed, v.is_none())).then_some(t.as_ref())
+                        })
+                        .collect();
+                    writer.write_row(&transformed, &mut self.dump_writer)?;
+
+                    self.indicator.inc_pb(1);
+                    count += 1;
+                }
+            }
+        }
+
+        if let Some(untransformed_query) = table.untransformed_query_to(cfg, count) {
+            for row in conn.query_iter(untransformed_query)? {
+                let values = row_values(&row?, &table.columns)?;
+                writer.write_row(&values, &mut self.dump_writer)?;
+
+                self.indicator.inc_pb(1);
+            }
+        }
+
+        writer.finish(&mut self.dump_writer)?;
+
+        let finished = started.elapsed();
+        self.indicator
+            .finish_pb(table.get_full_name().as_str(), finished);
+
+        Ok(())
+    }
+}
+
+impl<W: 'static + Write + Send, I: 'static + Indicator + Send> Dumper for MyDumper<W, I> {
+    type Table = MyTable;
+    type Connection = connector::Connection;
+    type SchemaInspector = MySchemaInspector;
+
+    // Tables (without triggers, they are created after data)
+    fn pre_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        self.debug("Prepare data scheme...".into());
+        self.run_mysqldump(&["--no-data", "--skip-triggers"], connection)
+    }
+
+    // This stage makes dump data only (in a consistent snapshot)
+    fn data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        let settings = self.settings();
+        self.write_log("Start dumping data".into())?;
+        self.dump_writer.write_all(DATA_HEADER.as_bytes())?;
+        self.debug("Fetch tables metadata...".into());
+
+        let mut tables = self.schema_inspector().ordered_tables(&mut *connection);
+        for (table, _) in tables.iter_mut() {
+            if let Some(cfg) = settings.find_table(&table.get_names()) {
+                table.check_query(cfg)?;
+                table.apply_column_exclusions(cfg)?;
+            }
+        }
+        sort_tables(
+            &mut tables,
+            settings.table_order.as_ref().unwrap_or(&vec![]),
+        );
+
+        let all_tables_count = tables.len();
+
+        connection.conn.query_drop("SET time_zone = '+00:00'")?;
+        let mut tx = connection.conn.start_transaction(
+            TxOpts::default()
+                .set_with_consistent_snapshot(true)
+                .set_access_mode(Some(AccessMode::ReadOnly)),
+        )?;
+        for (ind, (table, _weight)) in tables.iter().enumerate() {
+            self.debug(format!(
+                "[{} / {}] Prepare to dump table: {}",
+                ind + 1,
+                all_tables_count,
+                table.get_full_name(),
+            ));
+
+            if self.filter_table(&[table.get_full_name()], &settings.filter) {
+                self.dump_table(table, &mut tx)?;
+            } else {
+                self.debug(format!("[Dumping: {}] --- SKIP ---", table.get_full_name()));
+            }
+        }
+        tx.commit()?;
+
+        self.dump_writer.write_all(DATA_FOOTER.as_bytes())?;
+        self.write_log("End dumping data".into())?;
+        Ok(())
+    }
+
+    // Triggers only
+    fn post_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        self.debug("Finishing with triggers...".into());
+        self.run_mysqldump(
+            &[
+                "--no-data",
+                "--no-create-info",
+                "--no-create-db",
+                "--triggers",
+            ],
+            connection,
+        )
+    }
+
+    fn schema_inspector(&self) -> Self::SchemaInspector {
+        self.schema_inspector.clone()
+    }
+
+    fn settings(&mut self) -> Settings {
+        self.engine.settings.clone()
+    }
+
+    fn write_log(&mut self, message: String) -> Result<()> {
+        self.dump_writer
+            .write_all(format!("\n--\n-- {}\n--\n", message).as_bytes())
+            .map_err(|e| e.into())
+    }
+
+    fn debug(&self, message: String) {
+        self.indicator.debug_msg(message.as_str());
+    }
+}
+
+/// Builds extended `INSERT` statements (up to `MAX_INSERT_SIZE`)
+struct InsertWriter<'a> {
+    table: &'a MyTable,
+    prefix: String,
+    statement: String,
+}
+
+impl<'a> InsertWriter<'a> {
+    fn new(table: &'a MyTable) -> Self {
+        Self {
+            table,
+            prefix: table.insert_prefix(),
+            statement: String::new(),
+        }
+    }
+
+    // `None` values are NULL
+    fn write_row<T: AsRef<str>, W: Write>(
+        &mut self,
+        values: &[Option<T>],
+        writer: &mut W,
+    ) -> Result<()> {
+        let mut row = String::from("(");
+        for (i, (value, column)) in values.iter().zip(self.table.columns.iter()).enumerate() {
+            if i > 0 {
+                row.push(',');
+            }
+            literal::push(
+                &mut row,
+                value.as_ref().map(|v| v.as_ref()),
+                column.is_binary(),
+            );
+        }
+        row.push(')');
+
+        if !self.statement.is_empty() && self.statement.len() + row.len() + 1 > MAX_INSERT_SIZE {
+            self.finish(writer)?;
+        }
+        if self.statement.is_empty() {
+            self.statement.push_str(&self.prefix);
+        } else {
+            self.statement.push(',');
+        }
+        self.statement.push_str(&row);
+
+        Ok(())
+    }
+
+    fn finish<W: Write>(&mut self, writer: &mut W) -> Result<()> {
+        if !self.statement.is_empty() {
+            writer.write_all(self.statement.as_bytes())?;
+            writer.write_all(b";\n")?;
+            self.statement.clear();
+        }
+        Ok(())
+    }
+}
+
+// Values are fetched with the text protocol, so they are bytes (or NULL)
+fn row_values(row: &mysql::Row, columns: &[MyColumn]) -> Result<Vec<Option<String>>> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let bytes: Cow<[u8]> = match row.as_ref(i) {
+                Some(Value::NULL) => return Ok(None),
+                Some(Value::Bytes(bytes)) => Cow::Borrowed(bytes),
+                Some(value) => Cow::Owned(value.as_sql(true).into_bytes()),
+                None => return Err(anyhow!("No value of the column {}", column.name)),
+            };
+            literal::from_bytes(&bytes, column.is_binary())
+                .map(Some)
+                .map_err(|e| anyhow!("Invalid UTF-8 in the column {}: {}", column.name, e))
+        })
+        .collect()
+}
+
+// Subsets, samples and large objects are specific for PostgreSQL
+fn check_settings(settings: &Settings) -> Result<()> {
+    if settings.subset.is_some() {
+        return Err(anyhow!("Subsets are not supported for MySQL"));
+    }
+    if settings.large_objects.is_some() {
+        return Err(anyhow!("Large objects are not supported for MySQL"));
+    }
+    for table in settings.tables.iter() {
+        if table.query.as_ref().is_some_and(|q| q.sample.is_some()) {
+            return Err(anyhow!(
+                "Samples are not supported for MySQL (table {})",
+                table.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Tables excluded from the schema by the filter are ignored by mysqldump
+fn table_args(tables: &[MyTable], filter: &Option<Filter>) -> Vec<String> {
+    match filter {
+        Some(f) => tables
+            .iter()
+            .filter(|t| !f.filter_schema(&t.get_full_name()))
+            .map(|t| format!("--ignore-table={}", t.get_full_name()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> MyTable {
+        let mut table = MyTable::new("files".to_string(), "app".to_string());
+        table.set_columns(vec![
+            MyColumn::new(1, "name".to_string(), "varchar".to_string()),
+            MyColumn::new(2, "content".to_string(), "blob".to_string()),
+        ]);
+        table
+    }
+
+    #[test]
+    fn insert_writer() {
+        let table = table();
+        let mut writer = InsertWriter::new(&table);
+        let mut out = vec![];
+        writer
+            .write_row(&[Some("a'b"), Some("\\x0102")], &mut out)
+            .unwrap();
+        writer
+            .write_row::<&str, _>(&[None, None], &mut out)
+            .unwrap();
+        // A fetched text `\N` is not NULL
+        writer.write_row(&[Some("\\N"), None], &mut out).unwrap();
+        writer.finish(&mut out).unwrap();
+        writer.finish(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "INSERT INTO `files` (`name`, `content`) VALUES ('a\\'b',X'0102'),(NULL,NULL),('\\\\N',NULL);\n"
+        );
+    }
+
+    #[test]
+    fn insert_writer_splits_statements() {
+        let table = table();
+        let mut writer = InsertWriter::new(&table);
+        let mut out = vec![];
+        let name = "x".repeat(MAX_INSERT_SIZE / 2);
+        for _ in 0..3 {
+            writer
+                .write_row(&[Some(name.as_str()), Some("\\x")], &mut out)
+                .unwrap();
+        }
+        writer.finish(&mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("INSERT INTO").count(), 3);
+        assert!(out.lines().all(|l| l.len() <= MAX_INSERT_SIZE + 1));
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let settings = Settings::from_yaml(
+            r#"
+            tables:
+              - name: users
+                rules: {}
+                query:
+                  sample:
+                    percent: 10
+            "#,
+        )
+        .unwrap();
+        assert!(check_settings(&settings).is_err());
+
+        let settings = Settings::from_yaml("tables: []").unwrap();
+        assert!(check_settings(&settings).is_ok());
+    }
+
+    #[test]
+    fn ignored_tables() {
+        let tables = vec![
+            MyTable::new("users".to_string(), "app".to_string()),
+            MyTable::new("logs".to_string(), "app".to_string()),
+        ];
+        let settings = Settings::from_yaml(
+            r#"
+            tables: []
+            filter:
+              schema:
+                except:
+                  - app.logs
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            table_args(&tables, &settings.filter),
+            vec!["--ignore-table=app.logs"]
+        );
+        assert!(table_args(&tables, &None).is_empty());
+    }
+}
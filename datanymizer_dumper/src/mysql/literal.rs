@@ -0,0 +1,128 @@
+//! Conversion of column values to MySQL literals (for `INSERT` statements).
+//! Values are passed to transformers as strings: NULL is `\N` (as in PostgreSQL dumps),
+//! and binary values are hex strings prefixed with `\x` (as PostgreSQL `bytea`),
+//! so the same transformers work for both databases.
+//! A text value can be `\N` too, so NULLs are tracked separately (see `is_null`).
+
+use std::fmt::Write;
+
+pub const NULL: &str = "\\N";
+
+/// Converts a fetched (not NULL) value to a string for transformers
+pub fn from_bytes(bytes: &[u8], binary: bool) -> Result<String, std::str::Utf8Error> {
+    if binary {
+        let mut s = String::with_capacity(bytes.len() * 2 + 2);
+        s.push_str("\\x");
+        for byte in bytes {
+            let _ = write!(s, "{:02x}", byte);
+        }
+        Ok(s)
+    } else {
+        std::str::from_utf8(bytes).map(|s| s.to_string())
+    }
+}
+
+/// Is the (possibly transformed) value NULL? `\N` means NULL only if the fetched value was NULL
+/// (and it is not changed) or if a transformer returned it.
+pub fn is_null(value: &str, transformed: bool, fetched_null: bool) -> bool {
+    if transformed {
+        value == NULL
+    } else {
+        fetched_null
+    }
+}
+
+/// Appends the value as an SQL literal (`None` is NULL). Binary values are written
+/// as hex literals (if they are still hex strings after transformation).
+pub fn push(out: &mut String, value: Option<&str>, binary: bool) {
+    let value = match value {
+        Some(value) => value,
+        None => {
+            out.push_str("NULL");
+            return;
+        }
+    };
+
+    if binary {
+        let hex = value
+            .strip_prefix("\\\\x")
+            .or_else(|| value.strip_prefix("\\x"));
+        if let Some(hex) =
+            hex.filter(|h| h.len() % 2 == 0 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+        {
+            out.push_str("X'");
+            out.push_str(hex);
+            out.push('\'');
+            return;
+        }
+    }
+
+    out.push('\'');
+    for c in value.chars() {
+        match c {
+            '\0' => out.push_str("\\0"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x1a' => out.push_str("\\Z"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(value: &str, binary: bool) -> String {
+        let mut s = String::new();
+        push(&mut s, Some(value), binary);
+        s
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(from_bytes(b"text", false).unwrap(), "text");
+        assert_eq!(from_bytes(&[0, 15, 255], true).unwrap(), "\\x000fff");
+        assert_eq!(from_bytes(b"", true).unwrap(), "\\x");
+        assert!(from_bytes(&[0xff], false).is_err());
+    }
+
+    #[test]
+    fn literals() {
+        let mut null = String::new();
+        push(&mut null, None, false);
+        assert_eq!(null, "NULL");
+        // A text value
+        assert_eq!(literal("\\N", false), "'\\\\N'");
+        assert_eq!(literal("42", false), "'42'");
+        assert_eq!(
+            literal("it's \"a\"\n\\path\r\0\x1a", false),
+            "'it\\'s \\\"a\\\"\\n\\\\path\\r\\0\\Z'"
+        );
+        assert_eq!(literal("юникод", false), "'юникод'");
+    }
+
+    #[test]
+    fn nulls() {
+        assert!(is_null("\\N", false, true));
+        // The original text value
+        assert!(!is_null("\\N", false, false));
+        // Transformed values
+        assert!(is_null("\\N", true, false));
+        assert!(!is_null("value", true, true));
+    }
+
+    #[test]
+    fn binary_literals() {
+        assert_eq!(literal("\\x000fff", true), "X'000fff'");
+        assert_eq!(literal("\\\\x0a", true), "X'0a'");
+        assert_eq!(literal("\\x", true), "X''");
+        assert_eq!(literal("\\x0", true), "'\\\\x0'");
+        assert_eq!(literal("plain", true), "'plain'");
+        assert_eq!(literal("\\x00", false), "'\\\\x00'");
+    }
+}
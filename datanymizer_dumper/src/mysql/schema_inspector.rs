@@ -0,0 +1,103 @@
+use super::{column::MyColumn, connector, table::MyTable, SchemaInspector};
+use anyhow::Result;
+use mysql::prelude::Queryable;
+
+const TABLES_QUERY: &str = "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_ROWS
+                            FROM information_schema.TABLES
+                            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE'
+                            ORDER BY TABLE_NAME";
+
+// Generated columns are skipped (they can't be inserted)
+const COLUMNS_QUERY: &str = "SELECT COLUMN_NAME, ORDINAL_POSITION, DATA_TYPE
+                             FROM information_schema.COLUMNS
+                             WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+                             AND EXTRA NOT LIKE '%VIRTUAL GENERATED%'
+                             AND EXTRA NOT LIKE '%STORED GENERATED%'
+                             AND EXTRA NOT LIKE '%PERSISTENT GENERATED%'
+                             ORDER BY ORDINAL_POSITION";
+
+const PRIMARY_KEY_QUERY: &str = "SELECT COLUMN_NAME
+                                 FROM information_schema.KEY_COLUMN_USAGE
+                                 WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+                                 AND CONSTRAINT_NAME = 'PRIMARY'
+                                 ORDER BY ORDINAL_POSITION";
+
+const TABLE_SIZE_QUERY: &str = "SELECT TABLE_ROWS
+                                FROM information_schema.TABLES
+                                WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?";
+
+// Self-references are skipped (they don't affect the order of tables)
+const DEPENDENCIES_QUERY: &str = "SELECT DISTINCT REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME
+                                  FROM information_schema.KEY_COLUMN_USAGE
+                                  WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+                                  AND REFERENCED_TABLE_NAME IS NOT NULL
+                                  AND NOT (REFERENCED_TABLE_SCHEMA = TABLE_SCHEMA
+                                      AND REFERENCED_TABLE_NAME = TABLE_NAME)";
+
+#[derive(Clone)]
+pub struct MySchemaInspector;
+
+impl SchemaInspector for MySchemaInspector {
+    type Type = String;
+    type Connection = connector::Connection;
+    type Table = MyTable;
+    type Column = MyColumn;
+
+    fn get_tables(&self, connection: &mut Self::Connection) -> Result<Vec<Self::Table>> {
+        let rows: Vec<(String, String, Option<u64>)> = connection.conn.query(TABLES_QUERY)?;
+        let mut tables = Vec::with_capacity(rows.len());
+        for (schemaname, tablename, size) in rows {
+            let mut table = MyTable::new(tablename, schemaname);
+            let columns = self.get_columns(connection, &table)?;
+            table.set_columns(columns);
+            let primary_key = connection
+                .conn
+                .exec(PRIMARY_KEY_QUERY, (&table.schemaname, &table.tablename))?;
+            table.set_primary_key(primary_key);
+            table.size = size.unwrap_or(0) as i64;
+            tables.push(table);
+        }
+
+        Ok(tables)
+    }
+
+    // `TABLE_ROWS` is an estimate for InnoDB tables (it is used for progress bars only)
+    fn get_table_size(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<i64> {
+        let size: Option<Option<u64>> = connection
+            .conn
+            .exec_first(TABLE_SIZE_QUERY, (&table.schemaname, &table.tablename))?;
+        Ok(size.flatten().unwrap_or(0) as i64)
+    }
+
+    fn get_dependencies(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<Vec<Self::Table>> {
+        let rows: Vec<(String, String)> = connection
+            .conn
+            .exec(DEPENDENCIES_QUERY, (&table.schemaname, &table.tablename))?;
+        Ok(rows
+            .into_iter()
+            .map(|(schemaname, tablename)| MyTable::new(tablename, schemaname))
+            .collect())
+    }
+
+    fn get_columns(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<Vec<Self::Column>> {
+        let rows: Vec<(String, u64, String)> = connection
+            .conn
+            .exec(COLUMNS_QUERY, (&table.schemaname, &table.tablename))?;
+        Ok(rows
+            .into_iter()
+            .map(|(name, position, data_type)| MyColumn::new(position as usize, name, data_type))
+            .collect())
+    }
+}
@@ -0,0 +1,486 @@
+use super::column::{quote_identifier, MyColumn};
+use crate::{column_exclusions, Table};
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Query as QueryCfg, Table as TableCfg, Transformers};
+use mysql::Row as MysqlRow;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+#[derive(Debug, Clone, Eq)]
+pub struct MyTable {
+    pub tablename: String,
+    /// Database name (MySQL schemas are databases)
+    pub schemaname: String,
+    pub columns: Vec<MyColumn>,
+    pub primary_key: Vec<String>,
+    /// Columns dumped as NULL
+    pub excluded_columns: Vec<String>,
+    /// Columns omitted from the dump (they are already removed from `columns`)
+    pub dropped_columns: Vec<String>,
+    column_indexes: HashMap<String, usize>,
+    pub size: i64,
+}
+
+impl PartialEq for MyTable {
+    fn eq(&self, other: &MyTable) -> bool {
+        self.get_full_name() == other.get_full_name()
+    }
+}
+
+impl Hash for MyTable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tablename.hash(state);
+        self.schemaname.hash(state);
+    }
+}
+
+impl Table<String> for MyTable {
+    type Column = MyColumn;
+    type Row = MysqlRow;
+
+    fn get_name(&self) -> String {
+        self.tablename.clone()
+    }
+
+    fn get_full_name(&self) -> String {
+        format!("{}.{}", self.schemaname, self.tablename)
+    }
+
+    fn get_names(&self) -> Vec<String> {
+        vec![self.get_full_name(), self.get_name()]
+    }
+
+    fn get_columns(&self) -> Vec<Self::Column> {
+        self.columns.clone()
+    }
+
+    fn get_columns_names(&self) -> Vec<String> {
+        self.get_columns().into_iter().map(|c| c.name).collect()
+    }
+
+    fn get_size(&self) -> i64 {
+        self.size
+    }
+
+    fn get_column_indexes(&self) -> &HashMap<String, usize> {
+        &self.column_indexes
+    }
+}
+
+impl MyTable {
+    pub fn new(tablename: String, schemaname: String) -> Self {
+        Self {
+            tablename,
+            schemaname,
+            columns: vec![],
+            primary_key: vec![],
+            excluded_columns: vec![],
+            dropped_columns: vec![],
+            column_indexes: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    pub fn quoted_full_name(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&self.schemaname),
+            quote_identifier(&self.tablename)
+        )
+    }
+
+    /// Columns are renumbered in the order of their positions
+    pub fn set_columns(&mut self, mut columns: Vec<MyColumn>) {
+        columns.sort_by_key(|c| c.position);
+        let mut map: HashMap<String, usize> = HashMap::with_capacity(columns.len());
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.position = i + 1;
+            map.insert(column.name.clone(), i);
+        }
+
+        self.column_indexes = map;
+        self.columns = columns;
+    }
+
+    pub fn set_primary_key(&mut self, primary_key: Vec<String>) {
+        self.primary_key = primary_key;
+    }
+
+    /// Limited rows split by `transform_condition` are selected by both queries,
+    /// so they must be ordered (by `order_by` or by the primary key)
+    pub fn check_query(&self, cfg: &TableCfg) -> Result<()> {
+        match &cfg.query {
+            Some(q)
+                if q.limit.is_some()
+                    && q.transform_condition.is_some()
+                    && self.order_by(q).is_none() =>
+            {
+                Err(anyhow!(
+                    "The table {} has no primary key, so `order_by` is required to use `limit` with `transform_condition`",
+                    self.get_full_name()
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies `exclude_columns` and `drop_columns` from the table config (see `column_exclusions`).
+    /// Dropped columns are removed from the table columns (and column indexes).
+    pub fn apply_column_exclusions(&mut self, cfg: &TableCfg) -> Result<()> {
+        let (excluded, dropped) = column_exclusions(self, cfg)?;
+        if !dropped.is_empty() {
+            let columns = self
+                .columns
+                .iter()
+                .filter(|c| !dropped.contains(&c.name))
+                .cloned()
+                .collect();
+            self.set_columns(columns);
+        }
+        self.excluded_columns = excluded;
+        self.dropped_columns = dropped;
+
+        Ok(())
+    }
+
+    pub fn transformed_query_to(&self, cfg: Option<&TableCfg>) -> Option<String> {
+        cfg.map(|c| match &c.query {
+            Some(q) => self.query(q, Some(c), |s| format!("({})", s)),
+            None => self.query_with_select(self.select_list(Some(c)), vec![], None, None),
+        })
+    }
+
+    /// `already_dumped` is the number of rows dumped by the transformed query
+    /// (nothing is left if it reaches the limit)
+    pub fn untransformed_query_to(
+        &self,
+        cfg: Option<&TableCfg>,
+        already_dumped: u64,
+    ) -> Option<String> {
+        match cfg {
+            Some(c) => c.query.as_ref().and_then(|q| {
+                if q.transform_condition.is_none()
+                    || q.limit.is_some_and(|limit| limit as u64 <= already_dumped)
+                {
+                    None
+                } else {
+                    Some(self.query(q, None, |s| format!("NOT ({})", s)))
+                }
+            }),
+            None => Some(self.query_with_select(self.select_list(None), vec![], None, None)),
+        }
+    }
+
+    pub fn count_of_query_to(&self, cfg: Option<&TableCfg>) -> u64 {
+        let number = self.get_size() as u64;
+        cfg.and_then(|c| c.query.as_ref())
+            .and_then(|q| q.limit)
+            .map_or(number, |limit| number.min(limit as u64))
+    }
+
+    /// The beginning of `INSERT` statements (before row values)
+    pub fn insert_prefix(&self) -> String {
+        let columns: Vec<String> = self.columns.iter().map(|c| c.quoted_name()).collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES ",
+            quote_identifier(&self.tablename),
+            columns.join(", ")
+        )
+    }
+
+    // With a limit, rows are split by the transform condition after limiting,
+    // so the transformed and untransformed queries split the same (limited) set of rows
+    fn query(
+        &self,
+        q: &QueryCfg,
+        transformed: Option<&TableCfg>,
+        tr_fmt: fn(s: &String) -> String,
+    ) -> String {
+        let dump_condition = q.dump_condition.as_ref().map(|c| format!("({})", c));
+        let limit = q.limit.map(|limit| limit as u64);
+        match q.transform_condition.as_ref().map(tr_fmt) {
+            Some(split_condition) if limit.is_some() => format!(
+                "SELECT {} FROM ({}) AS {} WHERE {}",
+                self.select_list(transformed),
+                self.query_with_select(
+                    "*".to_string(),
+                    vec![dump_condition],
+                    self.order_by(q),
+                    limit
+                ),
+                quote_identifier(&self.tablename),
+                split_condition
+            ),
+            split_condition => self.query_with_select(
+                self.select_list(transformed),
+                vec![dump_condition, split_condition],
+                self.order_by(q),
+                limit,
+            ),
+        }
+    }
+
+    // Without the order, `limit` selects arbitrary rows, so we use the primary key by default
+    fn order_by(&self, q: &QueryCfg) -> Option<String> {
+        q.order_by.clone().or_else(|| {
+            if q.limit.is_some() && !self.primary_key.is_empty() {
+                let columns: Vec<String> = self
+                    .primary_key
+                    .iter()
+                    .map(|c| quote_identifier(c))
+                    .collect();
+                Some(columns.join(", "))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn query_with_select(
+        &self,
+        select_list: String,
+        cs: Vec<Option<String>>,
+        order_by: Option<String>,
+        limit: Option<u64>,
+    ) -> String {
+        let conditions: Vec<String> = cs.into_iter().flatten().collect();
+        let conditions = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        format!(
+            "SELECT {} FROM {}{}{}{}",
+            select_list,
+            self.quoted_full_name(),
+            conditions,
+            order_by.map_or(String::new(), |o| format!(" ORDER BY {}", o)),
+            limit.map_or(String::new(), |l| format!(" LIMIT {}", l)),
+        )
+    }
+
+    /// SQL expressions of `sql` rules (column name -> expression)
+    pub fn sql_expressions(cfg: Option<&TableCfg>) -> HashMap<&str, &str> {
+        cfg.map(|c| {
+            c.rules
+                .iter()
+                .filter_map(|(name, rule)| match rule {
+                    Transformers::Sql(t) => Some((name.as_str(), t.expression.as_str())),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    // Columns are always listed, because generated columns are not dumped.
+    // Excluded columns are selected as NULL (their values are not read at all).
+    // Columns with `sql` rules are selected as their expressions (only for transformed queries).
+    fn select_list(&self, transformed: Option<&TableCfg>) -> String {
+        let expressions = Self::sql_expressions(transformed);
+        self.columns
+            .iter()
+            .map(|c| {
+                if self.excluded_columns.contains(&c.name) {
+                    format!("NULL AS {}", c.quoted_name())
+                } else if let Some(expression) = expressions.get(c.name.as_str()) {
+                    format!("({}) AS {}", expression, c.quoted_name())
+                } else {
+                    c.quoted_name()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datanymizer_engine::Settings;
+
+    fn table() -> MyTable {
+        let mut table = MyTable::new("users".to_string(), "app".to_string());
+        table.set_columns(vec![
+            MyColumn::new(3, "email".to_string(), "varchar".to_string()),
+            MyColumn::new(1, "id".to_string(), "int".to_string()),
+            MyColumn::new(5, "password".to_string(), "varchar".to_string()),
+        ]);
+        table
+    }
+
+    fn settings(config: &str) -> Settings {
+        Settings::from_yaml(config).unwrap()
+    }
+
+    #[test]
+    fn set_columns() {
+        let table = table();
+        assert_eq!(table.get_columns_names(), vec!["id", "email", "password"]);
+        assert_eq!(table.get_column_indexes()["password"], 2);
+        assert_eq!(table.columns[2].position, 3);
+    }
+
+    #[test]
+    fn insert_prefix() {
+        assert_eq!(
+            table().insert_prefix(),
+            "INSERT INTO `users` (`id`, `email`, `password`) VALUES "
+        );
+    }
+
+    #[test]
+    fn queries_without_config() {
+        let table = table();
+        assert_eq!(table.transformed_query_to(None), None);
+        assert_eq!(
+            table.untransformed_query_to(None, 0).unwrap(),
+            "SELECT `id`, `email`, `password` FROM `app`.`users`"
+        );
+    }
+
+    #[test]
+    fn queries_with_rules() {
+        let settings = settings(
+            r#"
+            tables:
+              - name: users
+                rules:
+                  email:
+                    sql: "CONCAT('user', id, '@example.com')"
+                exclude_columns:
+                  - password
+            "#,
+        );
+        let mut table = table();
+        let cfg = settings.find_table(&table.get_names());
+        table.apply_column_exclusions(cfg.unwrap()).unwrap();
+
+        assert_eq!(
+            table.transformed_query_to(cfg).unwrap(),
+            "SELECT `id`, (CONCAT('user', id, '@example.com')) AS `email`, NULL AS `password` \
+             FROM `app`.`users`"
+        );
+        assert_eq!(table.untransformed_query_to(cfg, 0), None);
+    }
+
+    #[test]
+    fn queries_with_conditions() {
+        let settings = settings(
+            r#"
+            tables:
+              - name: users
+                rules:
+                  email:
+                    email: {}
+                query:
+                  dump_condition: "id > 10"
+                  transform_condition: "email NOT LIKE '%@example.com'"
+                  order_by: "id DESC"
+                  limit: 100
+                drop_columns:
+                  - password
+            "#,
+        );
+        let mut table = table();
+        let cfg = settings.find_table(&table.get_names());
+        table.apply_column_exclusions(cfg.unwrap()).unwrap();
+
+        // The limited rows are split by the transform condition
+        assert_eq!(
+            table.transformed_query_to(cfg).unwrap(),
+            "SELECT `id`, `email` FROM (SELECT * FROM `app`.`users` \
+             WHERE (id > 10) ORDER BY id DESC LIMIT 100) AS `users` \
+             WHERE (email NOT LIKE '%@example.com')"
+        );
+        assert_eq!(
+            table.untransformed_query_to(cfg, 40).unwrap(),
+            "SELECT `id`, `email` FROM (SELECT * FROM `app`.`users` \
+             WHERE (id > 10) ORDER BY id DESC LIMIT 100) AS `users` \
+             WHERE NOT (email NOT LIKE '%@example.com')"
+        );
+        assert_eq!(table.untransformed_query_to(cfg, 100), None);
+        assert_eq!(
+            table.insert_prefix(),
+            "INSERT INTO `users` (`id`, `email`) VALUES "
+        );
+    }
+
+    #[test]
+    fn exclusions_for_patterns() {
+        let settings = settings(
+            r#"
+            tables:
+              - name: "app.*"
+                rules: {}
+                exclude_columns:
+                  - email
+                  - phone
+                drop_columns:
+                  - token
+            "#,
+        );
+        let mut table = table();
+        let cfg = settings.find_table(&table.get_names()).unwrap();
+        // Missing columns are skipped for patterns
+        table.apply_column_exclusions(cfg).unwrap();
+        assert_eq!(table.excluded_columns, vec!["email"]);
+        assert!(table.dropped_columns.is_empty());
+
+        let mut cfg = cfg.clone();
+        cfg.name = "users".to_string();
+        assert!(table.apply_column_exclusions(&cfg).is_err());
+    }
+
+    #[test]
+    fn limit_order() {
+        let settings = settings(
+            r#"
+            tables:
+              - name: users
+                rules:
+                  email:
+                    email: {}
+                query:
+                  transform_condition: "id > 10"
+                  limit: 100
+            "#,
+        );
+        let cfg = settings.find_table(&["users".to_string()]).unwrap();
+
+        // Without the primary key, the limited rows can't be split consistently
+        let mut table = table();
+        assert!(table.check_query(cfg).is_err());
+
+        table.set_primary_key(vec!["id".to_string()]);
+        table.check_query(cfg).unwrap();
+        assert_eq!(
+            table.transformed_query_to(Some(cfg)).unwrap(),
+            "SELECT `id`, `email`, `password` FROM (SELECT * FROM `app`.`users` \
+             ORDER BY `id` LIMIT 100) AS `users` WHERE (id > 10)"
+        );
+    }
+
+    #[test]
+    fn count_of_query() {
+        let mut table = table();
+        table.size = 1000;
+        let settings = settings(
+            r#"
+            tables:
+              - name: users
+                rules: {}
+                query:
+                  limit: 100
+            "#,
+        );
+        assert_eq!(table.count_of_query_to(None), 1000);
+        assert_eq!(
+            table.count_of_query_to(settings.find_table(&["users".to_string()])),
+            100
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use super::{
+    column::quote_identifier, connector::Connection, schema_inspector::PgSchemaInspector,
+    table::PgTable,
+};
+use crate::{indicator::Indicator, SchemaInspector, Table};
+use anyhow::Result;
+use datanymizer_engine::{Engine, Table as TableCfg};
+use indicatif::HumanDuration;
+use postgres::{types::ToSql, Transaction};
+use std::{borrow::Cow, collections::HashMap, time::Instant};
+
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+// The maximum number of parameters in one query (PostgreSQL protocol limitation)
+const MAX_PARAMS: usize = 65535;
+const NULL_VALUE: &str = "\\N";
+const CTID: &str = "ctid";
+const TABLEOID: &str = "tableoid";
+
+const TABLE_COLUMN_TYPES_QUERY: &str =
+    "SELECT attname::text AS column_name, format_type(atttypid, atttypmod) AS column_type
+     FROM pg_catalog.pg_attribute
+     WHERE attrelid = $1::text::regclass AND attnum > 0 AND NOT attisdropped";
+
+/// Anonymizes an existing database in place (e.g., a raw backup restored into an isolated environment).
+/// It uses the same rules as the dumper, and rewrites rows with batched `UPDATE`s.
+/// Rows are identified by the primary key or by `tableoid` and `ctid` (if there is no primary key,
+/// `ctid` alone isn't unique for tables with inheritance children).
+pub struct PgAnonymizer<I: Indicator> {
+    schema_inspector: PgSchemaInspector,
+    engine: Engine,
+    indicator: I,
+    batch_size: usize,
+    disable_triggers: bool,
+}
+
+impl<I: Indicator> PgAnonymizer<I> {
+    pub fn new(engine: Engine, indicator: I, batch_size: usize, disable_triggers: bool) -> Self {
+        Self {
+            schema_inspector: PgSchemaInspector {},
+            engine,
+            indicator,
+            batch_size: batch_size.max(1),
+            disable_triggers,
+        }
+    }
+
+    pub fn anonymize(&mut self, connection: &mut Connection) -> Result<()> {
+        let started = Instant::now();
+        let settings = self.engine.settings.clone();
+
+        let tables = self.schema_inspector.get_tables(connection)?;
+        for table in tables.iter() {
+            // Rows of partitioned tables are updated in their partitions
+            if table.is_partitioned {
+                continue;
+            }
+            let cfg = match settings.find_table(&table.get_names()) {
+                Some(cfg) if !cfg.rules.is_empty() => cfg,
+                _ => continue,
+            };
+
+            if settings
+                .filter
+                .as_ref()
+                .is_none_or(|f| f.filter_data_names(&table.full_names()))
+            {
+                self.anonymize_table(connection, table, cfg)?;
+            } else {
+                self.debug(format!(
+                    "[Anonymizing: {}] --- SKIP ---",
+                    table.get_full_name()
+                ));
+            }
+        }
+
+        self.debug(format!(
+            "Anonymization finished in {}",
+            HumanDuration(started.elapsed())
+        ));
+        Ok(())
+    }
+
+    fn anonymize_table(
+        &self,
+        connection: &mut Connection,
+        table: &PgTable,
+        cfg: &TableCfg,
+    ) -> Result<()> {
+        let started = Instant::now();
+        self.debug(format!("Anonymize table: {}", table.get_full_name()));
+
+        let column_types = self.column_types(connection, table)?;
+        let query = UpdateQuery::new(table, cfg, &column_types);
+
+        let mut tr = connection.client.transaction()?;
+        if self.disable_triggers {
+            tr.batch_execute(&triggers_query(table, "DISABLE"))?;
+        }
+
+        self.indicator
+            .start_pb(table.get_size() as u64, &table.get_full_name());
+
+        let portal = tr.bind(query.select.as_str(), &[])?;
+        loop {
+            let rows = tr.query_portal(&portal, self.batch_size as i32)?;
+            if rows.is_empty() {
+                break;
+            }
+
+            let mut params: Vec<Option<String>> = Vec::with_capacity(rows.len() * query.width());
+            for row in rows.iter() {
+                let keys: Vec<Option<String>> = (0..query.keys.len()).map(|i| row.get(i)).collect();
+                let values: Vec<Option<String>> =
+                    (query.keys.len()..row.len()).map(|i| row.get(i)).collect();
+                let input: Vec<&str> = values
+                    .iter()
+                    .map(|v| v.as_deref().unwrap_or(NULL_VALUE))
+                    .collect();
+
+                let transformed =
+                    self.engine
+                        .process_row(&cfg.name, table.get_column_indexes(), &input)?;
+
+                params.extend(keys);
+                for column in query.columns.iter() {
+                    let i = table.get_column_indexes()[column];
+                    // `\N` is NULL only if a transformer returns it (a fetched text can be `\N` too)
+                    params.push(match &transformed[i] {
+                        Cow::Owned(value) if value == NULL_VALUE => None,
+                        Cow::Owned(value) => Some(value.clone()),
+                        Cow::Borrowed(_) => values[i].clone(),
+                    });
+                }
+            }
+
+            query.execute(&mut tr, &params)?;
+            self.indicator.inc_pb(rows.len() as u64);
+        }
+        // The portal must be closed before re-enabling triggers
+        drop(portal);
+
+        if self.disable_triggers {
+            tr.batch_execute(&triggers_query(table, "ENABLE"))?;
+        }
+        tr.commit()?;
+
+        self.indicator
+            .finish_pb(&table.get_full_name(), started.elapsed());
+        Ok(())
+    }
+
+    fn column_types(
+        &self,
+        connection: &mut Connection,
+        table: &PgTable,
+    ) -> Result<HashMap<String, String>> {
+        let types = connection
+            .client
+            .query(TABLE_COLUMN_TYPES_QUERY, &[&table.quoted_full_name()])?
+            .into_iter()
+            .map(|row| (row.get("column_name"), row.get("column_type")))
+            .collect();
+        Ok(types)
+    }
+
+    fn debug(&self, message: String) {
+        self.indicator.debug_msg(message.as_str());
+    }
+}
+
+fn triggers_query(table: &PgTable, action: &str) -> String {
+    format!(
+        "ALTER TABLE {} {} TRIGGER ALL;",
+        table.quoted_full_name(),
+        action
+    )
+}
+
+/// Queries for selecting rows and updating them with transformed values
+struct UpdateQuery {
+    /// Query for selecting keys and all column values (as text)
+    select: String,
+    /// Key columns (primary key or `tableoid` and `ctid`) with their types
+    keys: Vec<(String, String)>,
+    /// Transformed columns
+    columns: Vec<String>,
+    /// Types of transformed columns
+    column_types: Vec<String>,
+    table_name: String,
+}
+
+impl UpdateQuery {
+    fn new(table: &PgTable, cfg: &TableCfg, types: &HashMap<String, String>) -> Self {
+        let keys: Vec<(String, String)> = if table.primary_key.is_empty() {
+            vec![
+                (TABLEOID.to_string(), "oid".to_string()),
+                (CTID.to_string(), "tid".to_string()),
+            ]
+        } else {
+            table
+                .primary_key
+                .iter()
+                .map(|k| (k.clone(), Self::column_type(types, k)))
+                .collect()
+        };
+
+        let mut column_refs: Vec<_> = table.columns.iter().collect();
+        column_refs.sort_by_key(|c| c.position);
+        // Columns with `sql` rules are computed by the server (as in dumps)
+        let expressions = PgTable::sql_expressions(Some(cfg));
+        let select_list: Vec<String> = keys
+            .iter()
+            .map(|(k, _)| quote(k))
+            .chain(column_refs.iter().map(|c| {
+                expressions
+                    .get(c.name.as_str())
+                    .map_or_else(|| quote(&c.name), |e| format!("({})", e))
+            }))
+            .map(|c| format!("{}::text", c))
+            .collect();
+        let condition = cfg
+            .query
+            .as_ref()
+            .and_then(|q| q.transform_condition.as_ref())
+            .map_or(String::new(), |c| format!(" WHERE ({})", c));
+        let select = format!(
+            "SELECT {} FROM {}{}",
+            select_list.join(", "),
+            table.quoted_full_name(),
+            condition
+        );
+
+        let mut columns: Vec<String> = cfg.rules.keys().cloned().collect();
+        columns.sort();
+        let column_types = columns
+            .iter()
+            .map(|c| Self::column_type(types, c))
+            .collect();
+
+        Self {
+            select,
+            keys,
+            columns,
+            column_types,
+            table_name: table.quoted_full_name(),
+        }
+    }
+
+    /// Number of parameters per row
+    fn width(&self) -> usize {
+        self.keys.len() + self.columns.len()
+    }
+
+    fn execute(&self, tr: &mut Transaction, params: &[Option<String>]) -> Result<()> {
+        let rows_per_query = (MAX_PARAMS / self.width()).max(1);
+        for chunk in params.chunks(rows_per_query * self.width()) {
+            let params: Vec<&(dyn ToSql + Sync)> =
+                chunk.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+            tr.execute(
+                self.update_sql(chunk.len() / self.width()).as_str(),
+                &params,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn update_sql(&self, row_count: usize) -> String {
+        let width = self.width();
+        let values: Vec<String> = (0..row_count)
+            .map(|row| {
+                let params: Vec<String> = (1..=width)
+                    .map(|i| format!("${}::text", row * width + i))
+                    .collect();
+                format!("({})", params.join(", "))
+            })
+            .collect();
+
+        let key_aliases: Vec<String> = (1..=self.keys.len()).map(|i| format!("k{}", i)).collect();
+        let column_aliases: Vec<String> = (1..=self.columns.len())
+            .map(|i| format!("c{}", i))
+            .collect();
+
+        let assignments: Vec<String> = self
+            .columns
+            .iter()
+            .zip(self.column_types.iter())
+            .zip(column_aliases.iter())
+            .map(|((c, t), a)| format!("{} = v.{}::{}", quote(c), a, t))
+            .collect();
+        let conditions: Vec<String> = self
+            .keys
+            .iter()
+            .zip(key_aliases.iter())
+            .map(|((k, t), a)| format!("t.{} = v.{}::{}", quote(k), a, t))
+            .collect();
+
+        format!(
+            "UPDATE {} AS t SET {} FROM (VALUES {}) AS v({}) WHERE {}",
+            self.table_name,
+            assignments.join(", "),
+            values.join(", "),
+            key_aliases
+                .into_iter()
+                .chain(column_aliases.into_iter())
+                .collect::<Vec<_>>()
+                .join(", "),
+            conditions.join(" AND ")
+        )
+    }
+
+    fn column_type(types: &HashMap<String, String>, column: &str) -> String {
+        types
+            .get(column)
+            .cloned()
+            .unwrap_or_else(|| "text".to_string())
+    }
+}
+
+// System columns are not quoted
+fn quote(column: &str) -> String {
+    if column == CTID || column == TABLEOID {
+        column.to_string()
+    } else {
+        quote_identifier(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres::column::PgColumn;
+    use datanymizer_engine::{Query as QueryCfg, Settings};
+
+    fn table(primary_key: Vec<&str>) -> PgTable {
+        let mut table = PgTable::new("users".to_string(), "public".to_string());
+        let columns = ["id", "name", "email"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| PgColumn {
+                position: i as i32 + 1,
+                name: name.to_string(),
+                data_type: String::new(),
+                inner_type: Some(0),
+                not_null: false,
+            })
+            .collect();
+        table.set_columns(columns);
+        table.set_primary_key(primary_key.into_iter().map(String::from).collect());
+        table
+    }
+
+    fn cfg(transform_condition: Option<&str>) -> TableCfg {
+        let config = r#"
+          tables:
+            - name: users
+              rules:
+                name:
+                  first_name: {}
+                email:
+                  email: {}
+        "#;
+        let mut cfg = Settings::from_yaml(config).unwrap().tables[0].clone();
+        cfg.query = Some(QueryCfg {
+            limit: None,
+            sample: None,
+            order_by: None,
+            dump_condition: None,
+            transform_condition: transform_condition.map(String::from),
+        });
+        cfg
+    }
+
+    fn types() -> HashMap<String, String> {
+        let mut types = HashMap::new();
+        types.insert("id".to_string(), "integer".to_string());
+        types.insert("name".to_string(), "character varying(255)".to_string());
+        types.insert("email".to_string(), "text".to_string());
+        types
+    }
+
+    #[test]
+    fn with_primary_key() {
+        let query = UpdateQuery::new(&table(vec!["id"]), &cfg(None), &types());
+
+        assert_eq!(
+            query.select,
+            "SELECT \"id\"::text, \"id\"::text, \"name\"::text, \"email\"::text FROM \"public\".\"users\""
+        );
+        assert_eq!(query.width(), 3);
+        assert_eq!(
+            query.update_sql(2),
+            "UPDATE \"public\".\"users\" AS t \
+            SET \"email\" = v.c1::text, \"name\" = v.c2::character varying(255) \
+            FROM (VALUES ($1::text, $2::text, $3::text), ($4::text, $5::text, $6::text)) AS v(k1, c1, c2) \
+            WHERE t.\"id\" = v.k1::integer"
+        );
+    }
+
+    #[test]
+    fn without_primary_key() {
+        let query = UpdateQuery::new(&table(vec![]), &cfg(Some("id > 10")), &types());
+
+        assert_eq!(
+            query.select,
+            "SELECT tableoid::text, ctid::text, \"id\"::text, \"name\"::text, \"email\"::text \
+            FROM \"public\".\"users\" WHERE (id > 10)"
+        );
+        assert_eq!(
+            query.update_sql(1),
+            "UPDATE \"public\".\"users\" AS t \
+            SET \"email\" = v.c1::text, \"name\" = v.c2::character varying(255) \
+            FROM (VALUES ($1::text, $2::text, $3::text, $4::text)) AS v(k1, k2, c1, c2) \
+            WHERE t.tableoid = v.k1::oid AND t.ctid = v.k2::tid"
+        );
+    }
+
+    #[test]
+    fn composite_primary_key() {
+        let query = UpdateQuery::new(&table(vec!["id", "email"]), &cfg(None), &types());
+
+        assert_eq!(query.width(), 4);
+        assert_eq!(
+            query.update_sql(1),
+            "UPDATE \"public\".\"users\" AS t \
+            SET \"email\" = v.c1::text, \"name\" = v.c2::character varying(255) \
+            FROM (VALUES ($1::text, $2::text, $3::text, $4::text)) AS v(k1, k2, c1, c2) \
+            WHERE t.\"id\" = v.k1::integer AND t.\"email\" = v.k2::text"
+        );
+    }
+
+    #[test]
+    fn sql_rule() {
+        let config = r#"
+          tables:
+            - name: users
+              rules:
+                email:
+                  sql: "md5(email) || '@example.com'"
+        "#;
+        let cfg = Settings::from_yaml(config).unwrap().tables[0].clone();
+        let query = UpdateQuery::new(&table(vec!["id"]), &cfg, &types());
+
+        assert_eq!(
+            query.select,
+            "SELECT \"id\"::text, \"id\"::text, \"name\"::text, \
+            (md5(email) || '@example.com')::text FROM \"public\".\"users\""
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("na\"me"), "\"na\"\"me\"");
+        assert_eq!(quote(CTID), "ctid");
+    }
+
+    #[test]
+    fn disable_triggers() {
+        let table = table(vec![]);
+        assert_eq!(
+            triggers_query(&table, "DISABLE"),
+            "ALTER TABLE \"public\".\"users\" DISABLE TRIGGER ALL;"
+        );
+    }
+}
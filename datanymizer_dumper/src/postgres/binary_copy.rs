@@ -0,0 +1,648 @@
+use super::{column::quote_identifier, table::PgTable};
+use anyhow::{anyhow, Result};
+use datanymizer_engine::Table as TableCfg;
+use postgres::types::Type;
+use std::{borrow::Cow, fmt::Write as _, io::BufRead};
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+const NULL_VALUE: &str = "\\N";
+
+// 2000-01-01 (the PostgreSQL epoch) in days since 1970-01-01
+const PG_EPOCH_DAYS: i64 = 10_957;
+const USECS_PER_DAY: i64 = 86_400_000_000;
+
+/// The format of `COPY ... TO STDOUT` for tables with transformation rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CopyFormat {
+    /// Rows are split by tabs
+    #[default]
+    Text,
+    /// Columns with transformation rules are decoded from the binary format, other columns
+    /// (and columns of unsupported types) are read as text (falls back to the text format
+    /// for tables with `sql` rules)
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decoder {
+    /// A value cast to text in the query (it is only escaped)
+    Raw,
+    Bool,
+    Bytea,
+    Text,
+    Jsonb,
+    Int2,
+    Int4,
+    Int8,
+    Oid,
+    Float4,
+    Float8,
+    Numeric,
+    Uuid,
+    Date,
+    Time,
+    Timestamp,
+    Timestamptz,
+}
+
+impl Decoder {
+    fn for_type(ty: &Type) -> Option<Self> {
+        let decoder = match *ty {
+            Type::BOOL => Self::Bool,
+            Type::BYTEA => Self::Bytea,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::JSON => Self::Text,
+            Type::JSONB => Self::Jsonb,
+            Type::INT2 => Self::Int2,
+            Type::INT4 => Self::Int4,
+            Type::INT8 => Self::Int8,
+            Type::OID => Self::Oid,
+            Type::FLOAT4 => Self::Float4,
+            Type::FLOAT8 => Self::Float8,
+            Type::NUMERIC => Self::Numeric,
+            Type::UUID => Self::Uuid,
+            Type::DATE => Self::Date,
+            Type::TIME => Self::Time,
+            Type::TIMESTAMP => Self::Timestamp,
+            Type::TIMESTAMPTZ => Self::Timestamptz,
+            _ => return None,
+        };
+        Some(decoder)
+    }
+
+    // Values are returned in the text COPY format (as transformers get them in the text mode)
+    fn decode(self, buf: &[u8]) -> Result<String> {
+        let value = match self {
+            Self::Raw => escape(std::str::from_utf8(buf)?).into_owned(),
+            Self::Bool => match read_bytes::<1>(buf)? {
+                [0] => "f".to_string(),
+                _ => "t".to_string(),
+            },
+            Self::Bytea => {
+                let mut s = String::with_capacity(buf.len() * 2 + 3);
+                s.push_str("\\\\x");
+                for byte in buf {
+                    let _ = write!(s, "{:02x}", byte);
+                }
+                s
+            }
+            Self::Text => escape(std::str::from_utf8(buf)?).into_owned(),
+            Self::Jsonb => match buf.split_first() {
+                Some((1, json)) => escape(std::str::from_utf8(json)?).into_owned(),
+                _ => return Err(anyhow!("Unsupported jsonb version")),
+            },
+            Self::Int2 => i16::from_be_bytes(read_bytes(buf)?).to_string(),
+            Self::Int4 => i32::from_be_bytes(read_bytes(buf)?).to_string(),
+            Self::Int8 => i64::from_be_bytes(read_bytes(buf)?).to_string(),
+            Self::Oid => u32::from_be_bytes(read_bytes(buf)?).to_string(),
+            Self::Float4 => {
+                let value = f32::from_be_bytes(read_bytes(buf)?);
+                float(value.into(), || value.to_string())
+            }
+            Self::Float8 => {
+                let value = f64::from_be_bytes(read_bytes(buf)?);
+                float(value, || value.to_string())
+            }
+            Self::Numeric => numeric(buf)?,
+            Self::Uuid => {
+                let b: [u8; 16] = read_bytes(buf)?;
+                let mut s = String::with_capacity(36);
+                for (i, byte) in b.iter().enumerate() {
+                    if matches!(i, 4 | 6 | 8 | 10) {
+                        s.push('-');
+                    }
+                    let _ = write!(s, "{:02x}", byte);
+                }
+                s
+            }
+            Self::Date => match i32::from_be_bytes(read_bytes(buf)?) {
+                i32::MAX => "infinity".to_string(),
+                i32::MIN => "-infinity".to_string(),
+                days => {
+                    let (date, bc) = date(days.into());
+                    format!("{}{}", date, bc)
+                }
+            },
+            Self::Time => time(i64::from_be_bytes(read_bytes(buf)?)),
+            Self::Timestamp | Self::Timestamptz => match i64::from_be_bytes(read_bytes(buf)?) {
+                i64::MAX => "infinity".to_string(),
+                i64::MIN => "-infinity".to_string(),
+                usecs => {
+                    let (date, bc) = date(usecs.div_euclid(USECS_PER_DAY));
+                    let tz = if self == Self::Timestamptz { "+00" } else { "" };
+                    format!(
+                        "{} {}{}{}",
+                        date,
+                        time(usecs.rem_euclid(USECS_PER_DAY)),
+                        tz,
+                        bc
+                    )
+                }
+            },
+        };
+
+        Ok(value)
+    }
+}
+
+/// Reads rows of the binary COPY format and converts them to values of the text COPY format
+pub struct BinaryCopyReader<R: BufRead> {
+    reader: R,
+    decoders: Vec<Decoder>,
+    started: bool,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> BinaryCopyReader<R> {
+    /// Returns `None` if the binary format isn't supported for the table (there are `sql` rules)
+    pub fn new(reader: R, table: &PgTable, cfg: Option<&TableCfg>) -> Option<Self> {
+        decoders(table, cfg).map(|decoders| Self {
+            reader,
+            decoders,
+            started: false,
+            buf: vec![],
+        })
+    }
+
+    /// Reads the next row into `values`. Returns `false` at the end of data.
+    pub fn read_row(&mut self, values: &mut Vec<String>) -> Result<bool> {
+        if !self.started {
+            self.read_header()?;
+            self.started = true;
+        }
+
+        let count = i16::from_be_bytes(self.read_array()?);
+        if count == -1 {
+            return Ok(false);
+        }
+        if count as usize != self.decoders.len() {
+            return Err(anyhow!(
+                "Unexpected number of fields: {} (expected {})",
+                count,
+                self.decoders.len()
+            ));
+        }
+
+        // Strings of values are reused between rows
+        values.resize(self.decoders.len(), String::new());
+        for (i, value) in values.iter_mut().enumerate() {
+            value.clear();
+            let len = i32::from_be_bytes(self.read_array()?);
+            if len < 0 {
+                value.push_str(NULL_VALUE);
+                continue;
+            }
+
+            self.buf.resize(len as usize, 0);
+            self.reader.read_exact(&mut self.buf)?;
+            match self.decoders[i] {
+                Decoder::Raw => value.push_str(&escape(std::str::from_utf8(&self.buf)?)),
+                decoder => value.push_str(&decoder.decode(&self.buf)?),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn read_header(&mut self) -> Result<()> {
+        let mut signature = [0; SIGNATURE.len()];
+        self.reader.read_exact(&mut signature)?;
+        if signature != SIGNATURE {
+            return Err(anyhow!("Invalid binary COPY signature"));
+        }
+
+        let _flags: [u8; 4] = self.read_array()?;
+        let extension_len = u32::from_be_bytes(self.read_array()?);
+        self.buf.resize(extension_len as usize, 0);
+        self.reader.read_exact(&mut self.buf)?;
+
+        Ok(())
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Is the binary format supported for the table?
+pub fn is_supported(table: &PgTable, cfg: Option<&TableCfg>) -> bool {
+    decoders(table, cfg).is_some()
+}
+
+// Decoders are in the same order as values in the text format.
+// Only columns with rules are decoded (if their types are supported),
+// other columns are cast to text in the query.
+fn decoders(table: &PgTable, cfg: Option<&TableCfg>) -> Option<Vec<Decoder>> {
+    if !PgTable::sql_expressions(cfg).is_empty() {
+        return None;
+    }
+
+    let mut columns: Vec<_> = table.columns.iter().collect();
+    columns.sort_by_key(|c| c.position);
+    let decoders = columns
+        .into_iter()
+        .map(|c| {
+            cfg.filter(|cfg| cfg.rules.contains_key(&c.name))
+                .and(c.inner_type)
+                .and_then(Type::from_oid)
+                .and_then(|ty| Decoder::for_type(&ty))
+                .unwrap_or(Decoder::Raw)
+        })
+        .collect();
+    Some(decoders)
+}
+
+/// Converts a text COPY query (of the transformed rows) to the binary one.
+/// Columns that aren't decoded are cast to text, so their values are sent
+/// in the text format (and written as is).
+pub fn binary_query(query: &str, table: &PgTable, cfg: Option<&TableCfg>) -> String {
+    let decoders = decoders(table, cfg).unwrap_or_default();
+    if !decoders.contains(&Decoder::Raw) {
+        return format!("{} (FORMAT binary)", query);
+    }
+
+    let mut columns: Vec<_> = table.columns.iter().collect();
+    columns.sort_by_key(|c| c.position);
+    let select_list: Vec<String> = columns
+        .iter()
+        .zip(decoders)
+        .map(|(c, decoder)| match decoder {
+            Decoder::Raw => format!("{}::text", c.quoted_name()),
+            _ => c.quoted_name(),
+        })
+        .collect();
+    let names: Vec<String> = columns.iter().map(|c| c.quoted_name()).collect();
+    // Queries are either `COPY (SELECT ...) TO STDOUT` or `COPY table(columns) TO STDOUT`
+    let select = match query
+        .strip_prefix("COPY (")
+        .and_then(|q| q.strip_suffix(") TO STDOUT"))
+    {
+        Some(select) => select.to_string(),
+        None => format!(
+            "SELECT {} FROM {}",
+            names.join(", "),
+            table.quoted_full_name()
+        ),
+    };
+
+    format!(
+        "COPY (SELECT {} FROM ({}) AS {}) TO STDOUT (FORMAT binary)",
+        select_list.join(", "),
+        select,
+        quote_identifier(&table.tablename)
+    )
+}
+
+fn read_bytes<const N: usize>(buf: &[u8]) -> Result<[u8; N]> {
+    buf.get(..N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("Unexpected value length: {}", buf.len()))
+}
+
+// The escaping of the text COPY format (unlike the escaper for transformed values,
+// `\N` is not special here)
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.bytes().any(|b| {
+        matches!(
+            b,
+            b'\\' | b'\x08' | b'\x0C' | b'\n' | b'\r' | b'\t' | b'\x0B'
+        )
+    }) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut escaped = String::with_capacity(s.len() + 1);
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\x08' => escaped.push_str("\\b"),
+            '\x0C' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\x0B' => escaped.push_str("\\v"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn float(value: f64, fmt: impl Fn() -> String) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        (if value > 0.0 { "Infinity" } else { "-Infinity" }).to_string()
+    } else {
+        fmt()
+    }
+}
+
+fn numeric(buf: &[u8]) -> Result<String> {
+    let header = |i: usize| read_bytes::<2>(&buf[(i * 2).min(buf.len())..]);
+    let ndigits = i16::from_be_bytes(header(0)?) as usize;
+    let weight = i16::from_be_bytes(header(1)?) as i64;
+    let sign = u16::from_be_bytes(header(2)?);
+    let dscale = u16::from_be_bytes(header(3)?) as usize;
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let digits = (0..ndigits)
+        .map(|i| header(4 + i).map(i16::from_be_bytes))
+        .collect::<Result<Vec<_>>>()?;
+    let digit = |i: i64| {
+        if i < 0 {
+            0
+        } else {
+            digits.get(i as usize).cloned().unwrap_or_default()
+        }
+    };
+
+    let mut s = String::new();
+    if sign == 0x4000 {
+        s.push('-');
+    }
+    if weight < 0 {
+        s.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                let _ = write!(s, "{}", digit(i));
+            } else {
+                let _ = write!(s, "{:04}", digit(i));
+            }
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            let _ = write!(fraction, "{:04}", digit(i));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        s.push('.');
+        s.push_str(&fraction);
+    }
+
+    Ok(s)
+}
+
+// Returns the date and the era suffix (` BC` or an empty string)
+fn date(days_since_pg_epoch: i64) -> (String, &'static str) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days_since_pg_epoch + PG_EPOCH_DAYS + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    if year > 0 {
+        (format!("{:04}-{:02}-{:02}", year, month, day), "")
+    } else {
+        (format!("{:04}-{:02}-{:02}", 1 - year, month, day), " BC")
+    }
+}
+
+fn time(usecs: i64) -> String {
+    let secs = usecs / 1_000_000;
+    let mut s = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    let fraction = usecs % 1_000_000;
+    if fraction > 0 {
+        let _ = write!(s, ".{:06}", fraction);
+        s.truncate(s.trim_end_matches('0').len());
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres::column::PgColumn;
+    use datanymizer_engine::Settings;
+
+    fn decode(decoder: Decoder, buf: &[u8]) -> String {
+        decoder.decode(buf).unwrap()
+    }
+
+    fn column(position: i32, oid: u32) -> PgColumn {
+        PgColumn {
+            position,
+            name: format!("c{}", position),
+            data_type: String::new(),
+            inner_type: Some(oid),
+            not_null: false,
+        }
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(decode(Decoder::Bool, &[1]), "t");
+        assert_eq!(decode(Decoder::Bool, &[0]), "f");
+        assert_eq!(decode(Decoder::Int2, &(-5i16).to_be_bytes()), "-5");
+        assert_eq!(decode(Decoder::Int4, &42i32.to_be_bytes()), "42");
+        assert_eq!(
+            decode(Decoder::Int8, &i64::MAX.to_be_bytes()),
+            i64::MAX.to_string()
+        );
+        assert_eq!(decode(Decoder::Oid, &16400u32.to_be_bytes()), "16400");
+        assert_eq!(decode(Decoder::Float4, &1.5f32.to_be_bytes()), "1.5");
+        assert_eq!(decode(Decoder::Float8, &0.1f64.to_be_bytes()), "0.1");
+        assert_eq!(decode(Decoder::Float8, &f64::NAN.to_be_bytes()), "NaN");
+        assert_eq!(
+            decode(Decoder::Float4, &f32::NEG_INFINITY.to_be_bytes()),
+            "-Infinity"
+        );
+        assert!(Decoder::Int4.decode(&[0, 1]).is_err());
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            decode(Decoder::Text, "multi\nline\t\\N ё".as_bytes()),
+            "multi\\nline\\t\\\\N ё"
+        );
+        assert_eq!(decode(Decoder::Jsonb, b"\x01{\"a\": 1}"), "{\"a\": 1}");
+        assert!(Decoder::Text.decode(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn bytea() {
+        assert_eq!(decode(Decoder::Bytea, &[0x0a, 0xff]), "\\\\x0aff");
+        assert_eq!(decode(Decoder::Bytea, &[]), "\\\\x");
+    }
+
+    #[test]
+    fn uuid() {
+        let bytes: Vec<u8> = (0..16).collect();
+        assert_eq!(
+            decode(Decoder::Uuid, &bytes),
+            "00010203-0405-0607-0809-0a0b0c0d0e0f"
+        );
+    }
+
+    #[test]
+    fn numeric() {
+        // ndigits, weight, sign, dscale, digits...
+        let num =
+            |parts: &[i16]| -> Vec<u8> { parts.iter().flat_map(|p| p.to_be_bytes()).collect() };
+        assert_eq!(decode(Decoder::Numeric, &num(&[0, 0, 0, 0])), "0");
+        assert_eq!(
+            decode(Decoder::Numeric, &num(&[2, 1, 0, 2, 1, 2345,])),
+            "12345.00"
+        );
+        assert_eq!(
+            decode(Decoder::Numeric, &num(&[2, 0, 0x4000, 5, 12, 3400])),
+            "-12.34000"
+        );
+        assert_eq!(
+            decode(Decoder::Numeric, &num(&[1, -2, 0, 8, 15])),
+            "0.00000015"
+        );
+        assert_eq!(decode(Decoder::Numeric, &num(&[1, 1, 0, 0, 1])), "10000");
+        assert_eq!(
+            decode(Decoder::Numeric, &num(&[0, 0, 0xC000u16 as i16, 0])),
+            "NaN"
+        );
+    }
+
+    #[test]
+    fn dates_and_times() {
+        assert_eq!(decode(Decoder::Date, &0i32.to_be_bytes()), "2000-01-01");
+        assert_eq!(decode(Decoder::Date, &(-1i32).to_be_bytes()), "1999-12-31");
+        assert_eq!(decode(Decoder::Date, &8_459i32.to_be_bytes()), "2023-02-28");
+        assert_eq!(
+            decode(Decoder::Date, &(-730_119i32).to_be_bytes()),
+            "0001-01-01"
+        );
+        assert_eq!(
+            decode(Decoder::Date, &(-730_120i32).to_be_bytes()),
+            "0001-12-31 BC"
+        );
+        assert_eq!(decode(Decoder::Date, &i32::MAX.to_be_bytes()), "infinity");
+
+        assert_eq!(
+            decode(Decoder::Time, &45_296_500_000i64.to_be_bytes()),
+            "12:34:56.5"
+        );
+        let ts = 8_459 * USECS_PER_DAY + 45_296_000_001;
+        assert_eq!(
+            decode(Decoder::Timestamp, &ts.to_be_bytes()),
+            "2023-02-28 12:34:56.000001"
+        );
+        assert_eq!(
+            decode(Decoder::Timestamptz, &(-1i64).to_be_bytes()),
+            "1999-12-31 23:59:59.999999+00"
+        );
+        assert_eq!(
+            decode(Decoder::Timestamp, &i64::MIN.to_be_bytes()),
+            "-infinity"
+        );
+    }
+
+    fn cfg(rules: &str) -> TableCfg {
+        let settings =
+            Settings::from_yaml(&format!("tables:\n  - name: t\n    rules:\n{}", rules)).unwrap();
+        settings.tables[0].clone()
+    }
+
+    #[test]
+    fn read_rows() {
+        let mut data = SIGNATURE.to_vec();
+        data.extend(0i32.to_be_bytes());
+        data.extend(0i32.to_be_bytes());
+        // a row: (7, 'a\tb', NULL), only the first column is decoded
+        data.extend(3i16.to_be_bytes());
+        data.extend(4i32.to_be_bytes());
+        data.extend(7i32.to_be_bytes());
+        data.extend(3i32.to_be_bytes());
+        data.extend(b"a\tb");
+        data.extend((-1i32).to_be_bytes());
+        data.extend((-1i16).to_be_bytes());
+
+        let mut table = PgTable::new("t".to_string(), "public".to_string());
+        table.set_columns(vec![column(2, 25), column(1, 23), column(3, 1007)]);
+        let cfg = cfg("      c1:\n        capitalize: ~\n");
+        let mut reader = BinaryCopyReader::new(data.as_slice(), &table, Some(&cfg)).unwrap();
+
+        let mut values = vec![];
+        assert!(reader.read_row(&mut values).unwrap());
+        assert_eq!(values, vec!["7", "a\\tb", "\\N"]);
+        assert!(!reader.read_row(&mut values).unwrap());
+    }
+
+    #[test]
+    fn supported() {
+        let mut table = PgTable::new("t".to_string(), "public".to_string());
+        // int4 and int4[] (read as text)
+        table.set_columns(vec![column(1, 23), column(2, 1007)]);
+        assert!(is_supported(&table, None));
+        assert!(is_supported(
+            &table,
+            Some(&cfg("      c2:\n        capitalize: ~\n"))
+        ));
+        assert!(!is_supported(
+            &table,
+            Some(&cfg("      c1:\n        sql: c1 + 1\n"))
+        ));
+    }
+
+    #[test]
+    fn query() {
+        let mut table = PgTable::new("t".to_string(), "public".to_string());
+        table.set_columns(vec![column(1, 23), column(2, 1007)]);
+        let cfg = cfg("      c1:\n        capitalize: ~\n");
+
+        let mut all_decoded = table.clone();
+        all_decoded.set_columns(vec![column(1, 23)]);
+        assert_eq!(
+            binary_query(
+                "COPY \"public\".\"t\"(\"c1\") TO STDOUT",
+                &all_decoded,
+                Some(&cfg)
+            ),
+            "COPY \"public\".\"t\"(\"c1\") TO STDOUT (FORMAT binary)"
+        );
+        assert_eq!(
+            binary_query(
+                "COPY \"public\".\"t\"(\"c1\", \"c2\") TO STDOUT",
+                &table,
+                Some(&cfg)
+            ),
+            "COPY (SELECT \"c1\", \"c2\"::text FROM (SELECT \"c1\", \"c2\" FROM \"public\".\"t\") \
+             AS \"t\") TO STDOUT (FORMAT binary)"
+        );
+        assert_eq!(
+            binary_query(
+                "COPY (SELECT * FROM \"public\".\"t\" LIMIT 10) TO STDOUT",
+                &table,
+                Some(&cfg)
+            ),
+            "COPY (SELECT \"c1\", \"c2\"::text FROM (SELECT * FROM \"public\".\"t\" LIMIT 10) \
+             AS \"t\") TO STDOUT (FORMAT binary)"
+        );
+
+        // Quotes in identifiers
+        let mut table = PgTable::new("t\"x".to_string(), "public".to_string());
+        let mut columns = vec![column(1, 23), column(2, 1007)];
+        columns[1].name = "c\"2".to_string();
+        table.set_columns(columns);
+        assert_eq!(
+            binary_query(
+                "COPY (SELECT * FROM \"public\".\"t\"\"x\") TO STDOUT",
+                &table,
+                Some(&cfg)
+            ),
+            "COPY (SELECT \"c1\", \"c\"\"2\"::text FROM (SELECT * FROM \"public\".\"t\"\"x\") \
+             AS \"t\"\"x\") TO STDOUT (FORMAT binary)"
+        );
+    }
+}
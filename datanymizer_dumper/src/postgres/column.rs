@@ -13,6 +13,8 @@ pub struct PgColumn {
 
     /// Inner postgres type (oid)
     pub inner_type: Option<u32>,
+    /// Has the column a NOT NULL constraint?
+    pub not_null: bool,
 }
 
 impl PartialEq for PgColumn {
@@ -33,6 +35,12 @@ fn cmp(&self, other: &Self) -> Ordering {
     }
 }
 
+impl PgColumn {
+    pub fn quoted_name(&self) -> String {
+        quote_identifier(&self.name)
+    }
+}
+
 impl From<PostgresRow> for PgColumn {
     fn from(row: PostgresRow) -> Self {
         let oid: u32 = row.get("oid");
@@ -42,6 +50,7 @@ fn from(row: PostgresRow) -> Self {
             name: row.get("column_name"),
             data_type: row.get("data_type"),
             inner_type: Some(oid),
+            not_null: row.get("not_null"),
         }
     }
 }
@@ -63,6 +72,11 @@ fn inner_kind(&self) -> Option<Type> {
     }
 }
 
+/// Quotes an identifier with double quotes (double quotes inside are doubled)
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,12 +89,14 @@ fn ordering_test() {
             name: String::from("Column1"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col2 = &PgColumn {
             position: 2,
             name: String::from("Column2"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
 
         let col3 = &PgColumn {
@@ -88,10 +104,17 @@ fn ordering_test() {
             name: String::from("Column1"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
 
         assert_eq!(col1, col3);
         assert_eq!(col1.cmp(col2), Ordering::Less);
         assert_eq!(col1.cmp(col3), Ordering::Equal);
     }
+
+    #[test]
+    fn quoted_identifiers() {
+        assert_eq!(quote_identifier("name"), "\"name\"");
+        assert_eq!(quote_identifier("na\"me"), "\"na\"\"me\"");
+    }
 }
@@ -0,0 +1,865 @@
+//! Anonymization of existing plain-text `pg_dump` files (without a database connection).
+//! Data of `COPY ... FROM stdin;` blocks and `INSERT` statements (`--inserts`, `--column-inserts`
+//! and `--rows-per-insert` dumps) is transformed, all other statements are written as is.
+//! Columns of `INSERT` statements without column lists are taken from the `CREATE TABLE`
+//! statements of the same dump.
+
+use super::row::RowTransformer;
+use crate::indicator::Indicator;
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Engine, Settings, Table as TableCfg, Transformers};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{BufRead, Write},
+    ops::Range,
+};
+
+const NULL: &str = "\\N";
+const COPY_END: &str = "\\.";
+const CUSTOM_FORMAT_MAGIC: &[u8] = b"PGDMP";
+
+pub struct DumpTransformer<I: Indicator> {
+    engine: Engine,
+    indicator: I,
+}
+
+enum State {
+    Sql,
+    /// Column names of the table being created
+    CreateTable(String, Vec<String>),
+    Copy(CopyBlock),
+    /// Accumulated lines of an unfinished `INSERT` statement
+    Insert(Statement),
+    /// The tag of an unfinished dollar-quoted string (e.g. a function body)
+    DollarQuoted(String),
+}
+
+enum CopyBlock {
+    Transformed {
+        cfg_tbl_name: String,
+        column_indexes: HashMap<String, usize>,
+    },
+    Untouched,
+    Skipped,
+}
+
+impl<I: Indicator> DumpTransformer<I> {
+    pub fn new(engine: Engine, indicator: I) -> Result<Self> {
+        check_settings(&engine.settings)?;
+        Ok(Self { engine, indicator })
+    }
+
+    /// Reads a plain-text dump and writes the transformed one
+    pub fn transform<R: BufRead, W: Write>(&self, mut reader: R, writer: &mut W) -> Result<()> {
+        if reader.fill_buf()?.starts_with(CUSTOM_FORMAT_MAGIC) {
+            return Err(anyhow!(
+                "Only plain-text dumps are supported (convert the dump with `pg_restore -f`)"
+            ));
+        }
+
+        let mut table_columns: HashMap<String, Vec<String>> = HashMap::new();
+        let mut state = State::Sql;
+        let mut buf = vec![];
+        let mut line_number = 0;
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            line_number += 1;
+            let line = std::str::from_utf8(&buf)
+                .map_err(|e| anyhow!("Invalid UTF-8 in the line {}: {}", line_number, e))?;
+            let (content, eol) = match line.strip_suffix('\n') {
+                Some(content) => (content, "\n"),
+                None => (line, ""),
+            };
+
+            state = match state {
+                State::Sql => self.sql_line(line, content, writer)?,
+                State::CreateTable(name, mut columns) => {
+                    writer.write_all(line.as_bytes())?;
+                    let trimmed = content.trim_start();
+                    if trimmed.starts_with(')') {
+                        table_columns.insert(name, columns);
+                        State::Sql
+                    } else {
+                        if !trimmed.starts_with("CONSTRAINT ") {
+                            if let Some(column) = Scanner::new(trimmed).identifier() {
+                                columns.push(column);
+                            }
+                        }
+                        State::CreateTable(name, columns)
+                    }
+                }
+                State::Copy(block) => {
+                    if content == COPY_END {
+                        writer.write_all(line.as_bytes())?;
+                        State::Sql
+                    } else {
+                        self.copy_line(&block, content, eol, writer)?;
+                        State::Copy(block)
+                    }
+                }
+                State::Insert(mut statement) => {
+                    statement.push(line);
+                    State::Insert(statement)
+                }
+                State::DollarQuoted(tag) => {
+                    writer.write_all(line.as_bytes())?;
+                    match open_dollar_tag(content, Some(tag)) {
+                        Some(tag) => State::DollarQuoted(tag),
+                        None => State::Sql,
+                    }
+                }
+            };
+
+            if let State::Insert(statement) = &state {
+                if statement.is_complete() {
+                    self.insert(&statement.text, &table_columns, writer)?;
+                    state = State::Sql;
+                }
+            }
+        }
+
+        match state {
+            State::Sql | State::CreateTable(_, _) | State::DollarQuoted(_) => Ok(()),
+            State::Copy(_) => Err(anyhow!("Unexpected end of the dump in a COPY block")),
+            State::Insert(_) => Err(anyhow!("Unexpected end of the dump in an INSERT statement")),
+        }
+    }
+
+    fn sql_line<W: Write>(&self, line: &str, content: &str, writer: &mut W) -> Result<State> {
+        if content.starts_with("INSERT INTO ") {
+            let mut statement = Statement::default();
+            statement.push(line);
+            return Ok(State::Insert(statement));
+        }
+
+        writer.write_all(line.as_bytes())?;
+        if let Some(header) = content.strip_prefix("COPY ") {
+            if content.ends_with(" FROM stdin;") {
+                return self.copy_block(header).map(State::Copy);
+            }
+        } else if let Some(name) = content
+            .strip_prefix("CREATE TABLE ")
+            .or_else(|| content.strip_prefix("CREATE UNLOGGED TABLE "))
+        {
+            if let Some(name) = name.strip_suffix(" (") {
+                if let Some(names) = Scanner::new(name).qualified_name() {
+                    return Ok(State::CreateTable(names.join("."), vec![]));
+                }
+            }
+        }
+
+        // Lines of function bodies are not statements (even if they look like `INSERT` or `COPY`)
+        Ok(match open_dollar_tag(content, None) {
+            Some(tag) => State::DollarQuoted(tag),
+            None => State::Sql,
+        })
+    }
+
+    fn copy_block(&self, header: &str) -> Result<CopyBlock> {
+        let (names, columns) = match parse_copy_header(header) {
+            Some(header) => header,
+            None => return Ok(CopyBlock::Untouched),
+        };
+        let full_name = names.join(".");
+        if !self.filter_table(&full_name) {
+            self.indicator
+                .debug_msg(&format!("[Transforming: {}] --- SKIP ---", full_name));
+            return Ok(CopyBlock::Skipped);
+        }
+
+        match self.find_table(&names) {
+            Some(cfg) => {
+                let columns = columns
+                    .ok_or_else(|| anyhow!("No column list in COPY of the table {}", full_name))?;
+                self.indicator
+                    .debug_msg(&format!("Transform table: {}", full_name));
+                Ok(CopyBlock::Transformed {
+                    cfg_tbl_name: cfg.name.clone(),
+                    column_indexes: column_indexes(&columns),
+                })
+            }
+            None => Ok(CopyBlock::Untouched),
+        }
+    }
+
+    fn copy_line<W: Write>(
+        &self,
+        block: &CopyBlock,
+        line: &str,
+        eol: &str,
+        writer: &mut W,
+    ) -> Result<()> {
+        match block {
+            CopyBlock::Transformed {
+                cfg_tbl_name,
+                column_indexes,
+            } => RowTransformer::with_column_indexes(&self.engine, column_indexes, cfg_tbl_name)
+                .write_line(line, writer)?,
+            CopyBlock::Untouched => writer.write_all(line.as_bytes())?,
+            CopyBlock::Skipped => return Ok(()),
+        }
+        writer.write_all(eol.as_bytes())?;
+        Ok(())
+    }
+
+    fn insert<W: Write>(
+        &self,
+        statement: &str,
+        table_columns: &HashMap<String, Vec<String>>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let names = Scanner::new(&statement["INSERT INTO ".len()..])
+            .qualified_name()
+            .ok_or_else(|| anyhow!("Invalid INSERT statement: {}", statement))?;
+        let full_name = names.join(".");
+        if !self.filter_table(&full_name) {
+            return Ok(());
+        }
+        let cfg = match self.find_table(&names) {
+            Some(cfg) => cfg,
+            None => return Ok(writer.write_all(statement.as_bytes())?),
+        };
+
+        let insert = parse_insert(statement).ok_or_else(|| {
+            anyhow!(
+                "Can't parse the INSERT statement of the table {}: {}",
+                full_name,
+                statement
+            )
+        })?;
+        let columns = insert
+            .columns
+            .as_ref()
+            .or_else(|| table_columns.get(&full_name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown columns of the table {} (there are no column lists in INSERT statements \
+                     and no CREATE TABLE statement before them)",
+                    full_name
+                )
+            })?;
+        let column_indexes = column_indexes(columns);
+
+        let mut written = 0;
+        for row in insert.rows.iter() {
+            if row.len() != columns.len() {
+                return Err(anyhow!(
+                    "The INSERT statement of the table {} has {} values instead of {}",
+                    full_name,
+                    row.len(),
+                    columns.len()
+                ));
+            }
+
+            let values: Vec<&str> = row
+                .iter()
+                .map(|v| match &v.kind {
+                    ValueKind::Null => NULL,
+                    ValueKind::Quoted(s) => s.as_str(),
+                    ValueKind::Bare => &statement[v.range.clone()],
+                })
+                .collect();
+            let transformed = self
+                .engine
+                .process_row(&cfg.name, &column_indexes, &values)?;
+
+            for (value, original) in transformed.into_iter().zip(row.iter()) {
+                if let Cow::Owned(value) = value {
+                    writer.write_all(&statement.as_bytes()[written..original.range.start])?;
+                    writer.write_all(literal(&value).as_bytes())?;
+                    written = original.range.end;
+                }
+            }
+        }
+        writer.write_all(&statement.as_bytes()[written..])?;
+
+        Ok(())
+    }
+
+    fn find_table(&self, names: &[String]) -> Option<&TableCfg> {
+        let full_name = names.join(".");
+        match names.last() {
+            Some(name) if names.len() > 1 => self.engine.settings.find_table(&[&full_name, name]),
+            _ => self.engine.settings.find_table(&[&full_name]),
+        }
+    }
+
+    // Schema objects of filtered tables are kept (as in the dump), only data is skipped
+    fn filter_table(&self, table: &str) -> bool {
+        self.engine
+            .settings
+            .filter
+            .as_ref()
+            .is_none_or(|f| f.filter_schema(table) && f.filter_data(table))
+    }
+}
+
+/// Lines of an SQL statement (complete when it ends with `;` outside of quotes)
+#[derive(Default)]
+struct Statement {
+    text: String,
+    quote: Option<char>,
+}
+
+impl Statement {
+    fn push(&mut self, line: &str) {
+        for c in line.chars() {
+            match self.quote {
+                // Doubled quotes close and open the quoted string again
+                Some(q) if c == q => self.quote = None,
+                Some(_) => {}
+                None if c == '\'' || c == '"' => self.quote = Some(c),
+                None => {}
+            }
+        }
+        self.text.push_str(line);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.quote.is_none() && self.text.trim_end().ends_with(';')
+    }
+}
+
+/// The tag of a dollar-quoted string that is still open at the end of the line
+/// (`open` is the tag of the string that is open at the start of the line)
+fn open_dollar_tag(line: &str, mut open: Option<String>) -> Option<String> {
+    let mut quote = None;
+    let mut after_identifier = false;
+    let mut pos = 0;
+    while pos < line.len() {
+        let rest = &line[pos..];
+        if let Some(tag) = &open {
+            match rest.find(tag.as_str()) {
+                Some(end) => pos += end + tag.len(),
+                None => break,
+            }
+            open = None;
+            after_identifier = false;
+            continue;
+        }
+
+        let c = rest.chars().next()?;
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if rest.starts_with("--") => break,
+            // `$` inside identifiers (e.g. `a$b`) doesn't start a string
+            None if c == '$' && !after_identifier => {
+                if let Some(tag) = dollar_tag(rest) {
+                    pos += tag.len();
+                    open = Some(tag.to_string());
+                    continue;
+                }
+            }
+            None => {}
+        }
+        after_identifier = c.is_alphanumeric() || c == '_' || c == '$';
+        pos += c.len_utf8();
+    }
+    open
+}
+
+/// The dollar quote (e.g. `$$` or `$body$`) at the start of the string
+fn dollar_tag(s: &str) -> Option<&str> {
+    let len = s[1..].find('$')? + 2;
+    let name = &s[1..len - 1];
+    let valid = name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    valid.then(|| &s[..len])
+}
+
+struct Insert {
+    columns: Option<Vec<String>>,
+    rows: Vec<Vec<Value>>,
+}
+
+struct Value {
+    /// The byte range of the value literal in the statement
+    range: Range<usize>,
+    kind: ValueKind,
+}
+
+enum ValueKind {
+    Null,
+    /// A string constant (the value is unescaped)
+    Quoted(String),
+    /// Numbers, booleans and other expressions (as is)
+    Bare,
+}
+
+fn parse_insert(statement: &str) -> Option<Insert> {
+    let mut scanner = Scanner::new(statement);
+    if !scanner.eat("INSERT INTO ") {
+        return None;
+    }
+    scanner.qualified_name()?;
+    scanner.skip_whitespace();
+    let columns = if scanner.rest().starts_with('(') {
+        Some(scanner.column_list()?)
+    } else {
+        None
+    };
+
+    // E.g. `OVERRIDING SYSTEM VALUE VALUES`
+    loop {
+        scanner.skip_whitespace();
+        if scanner.eat("VALUES") {
+            break;
+        }
+        scanner.word()?;
+    }
+
+    let mut rows = vec![];
+    loop {
+        scanner.skip_whitespace();
+        if !scanner.eat("(") {
+            return None;
+        }
+        let mut row = vec![];
+        loop {
+            row.push(scanner.value()?);
+            if scanner.eat(")") {
+                break;
+            }
+            if !scanner.eat(",") {
+                return None;
+            }
+        }
+        rows.push(row);
+
+        scanner.skip_whitespace();
+        if !scanner.eat(",") {
+            break;
+        }
+    }
+
+    Some(Insert { columns, rows })
+}
+
+/// The table name (unquoted parts) and column names from the `COPY` header
+/// (after `COPY `, e.g. `public.users (id, email) FROM stdin;`)
+pub(crate) fn parse_copy_header(header: &str) -> Option<(Vec<String>, Option<Vec<String>>)> {
+    let mut scanner = Scanner::new(header);
+    let names = scanner.qualified_name()?;
+    scanner.skip_whitespace();
+    Some((names, scanner.column_list()))
+}
+
+/// A string constant (`standard_conforming_strings` is on in `pg_dump` files)
+fn literal(value: &str) -> String {
+    if value == NULL {
+        "NULL".to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+fn column_indexes(columns: &[String]) -> HashMap<String, usize> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.clone(), i))
+        .collect()
+}
+
+struct Scanner<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(s: &'a str) -> Self {
+        Self { s, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.rest().starts_with(prefix);
+        if found {
+            self.pos += prefix.len();
+        }
+        found
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        let rest = self.rest();
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.pos += len;
+        Some(&rest[..len]).filter(|w| !w.is_empty())
+    }
+
+    /// A quoted or unquoted identifier (quotes are removed)
+    fn identifier(&mut self) -> Option<String> {
+        let rest = self.rest();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut name = String::new();
+            let mut chars = quoted.char_indices().peekable();
+            while let Some((i, c)) = chars.next() {
+                if c != '"' {
+                    name.push(c);
+                } else if chars.peek().is_some_and(|&(_, next)| next == '"') {
+                    name.push('"');
+                    chars.next();
+                } else {
+                    self.pos += i + 2;
+                    return Some(name);
+                }
+            }
+            None
+        } else {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            self.pos += len;
+            Some(rest[..len].to_string()).filter(|name| !name.is_empty())
+        }
+    }
+
+    fn qualified_name(&mut self) -> Option<Vec<String>> {
+        let mut names = vec![self.identifier()?];
+        while self.eat(".") {
+            names.push(self.identifier()?);
+        }
+        Some(names)
+    }
+
+    fn column_list(&mut self) -> Option<Vec<String>> {
+        if !self.eat("(") {
+            return None;
+        }
+        let mut columns = vec![];
+        loop {
+            self.skip_whitespace();
+            columns.push(self.identifier()?);
+            self.skip_whitespace();
+            if self.eat(")") {
+                return Some(columns);
+            }
+            if !self.eat(",") {
+                return None;
+            }
+        }
+    }
+
+    /// A value of a row (the scanner stops before `,` or `)` after the value)
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.eat("'") {
+            let mut value = String::new();
+            loop {
+                let rest = self.rest();
+                let quote = rest.find('\'')?;
+                value.push_str(&rest[..quote]);
+                self.pos += quote + 1;
+                if self.eat("'") {
+                    value.push('\'');
+                } else {
+                    break;
+                }
+            }
+            let end = self.pos;
+            // The rest of the expression (e.g. a type cast) is kept as is
+            self.skip_expression()?;
+            return Some(Value {
+                range: start..end,
+                kind: ValueKind::Quoted(value),
+            });
+        }
+
+        self.skip_expression()?;
+        let end = start + self.s[start..self.pos].trim_end().len();
+        let kind = match &self.s[start..end] {
+            "" => return None,
+            v if v.eq_ignore_ascii_case("NULL") => ValueKind::Null,
+            _ => ValueKind::Bare,
+        };
+        Some(Value {
+            range: start..end,
+            kind,
+        })
+    }
+
+    // Skips to `,` or `)` outside of quotes and parentheses
+    fn skip_expression(&mut self) -> Option<()> {
+        let mut depth = 0;
+        let mut quote = None;
+        for (i, c) in self.rest().char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '\'' | '"' => quote = Some(c),
+                    '(' | '[' => depth += 1,
+                    ')' | ']' if depth > 0 => depth -= 1,
+                    ',' | ')' if depth == 0 => {
+                        self.pos += i;
+                        return Some(());
+                    }
+                    _ => {}
+                },
+            }
+        }
+        None
+    }
+}
+
+// Features that require a database connection
+fn check_settings(settings: &Settings) -> Result<()> {
+    if settings.subset.is_some() {
+        return Err(anyhow!("Subsets are not supported for dump files"));
+    }
+    if settings.large_objects.is_some() {
+        return Err(anyhow!("Large objects are not supported for dump files"));
+    }
+    for table in settings.tables.iter() {
+        if table.query.is_some() {
+            return Err(anyhow!(
+                "Queries are not supported for dump files (table {})",
+                table.name
+            ));
+        }
+        if !table.exclude_columns.is_empty() || !table.drop_columns.is_empty() {
+            return Err(anyhow!(
+                "Column exclusions are not supported for dump files (table {})",
+                table.name
+            ));
+        }
+        if let Some(column) = table
+            .rules
+            .iter()
+            .find_map(|(name, rule)| matches!(rule, Transformers::Sql(_)).then_some(name))
+        {
+            return Err(anyhow!(
+                "SQL rules are not supported for dump files (table {}, column {})",
+                table.name,
+                column
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::SilentIndicator;
+
+    const CONFIG: &str = r#"
+      filter:
+        data:
+          except:
+            - public.logs
+      tables:
+        - name: users
+          rules:
+            email:
+              template:
+                format: "user-{{ _0 }}"
+            phone:
+              template:
+                format: '\N'
+    "#;
+
+    fn transform_with(config: &str, dump: &str) -> Result<String> {
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+        let transformer = DumpTransformer::new(engine, SilentIndicator)?;
+        let mut out = vec![];
+        transformer.transform(dump.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn transform(dump: &str) -> String {
+        transform_with(CONFIG, dump).unwrap()
+    }
+
+    #[test]
+    fn copy_blocks() {
+        let dump = "SET client_encoding = 'UTF8';\n\
+                    COPY public.users (id, email, phone) FROM stdin;\n\
+                    1\ta@b.c\t123\n\
+                    2\t\\N\t\\N\n\
+                    \\.\n\
+                    COPY public.logs (id, message) FROM stdin;\n\
+                    1\tsecret\n\
+                    \\.\n\
+                    COPY public.other (id, email) FROM stdin;\n\
+                    1\ta@b.c\n\
+                    \\.\n";
+        assert_eq!(
+            transform(dump),
+            "SET client_encoding = 'UTF8';\n\
+             COPY public.users (id, email, phone) FROM stdin;\n\
+             1\tuser-a@b.c\t\\N\n\
+             2\tuser-\\\\N\t\\N\n\
+             \\.\n\
+             COPY public.logs (id, message) FROM stdin;\n\
+             \\.\n\
+             COPY public.other (id, email) FROM stdin;\n\
+             1\ta@b.c\n\
+             \\.\n"
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        let dump = "COPY \"public\".\"users\" (\"id\", \"email\", \"phone\") FROM stdin;\n\
+                    1\ta@b.c\t123\n\
+                    \\.";
+        assert_eq!(
+            transform(dump),
+            "COPY \"public\".\"users\" (\"id\", \"email\", \"phone\") FROM stdin;\n\
+             1\tuser-a@b.c\t\\N\n\
+             \\."
+        );
+    }
+
+    #[test]
+    fn column_inserts() {
+        let dump = "INSERT INTO public.users (id, email, phone) VALUES (1, 'it''s@b.c', '123');\n\
+                    INSERT INTO public.users (id, email, phone) VALUES (2, NULL, NULL);\n\
+                    INSERT INTO public.logs (id, message) VALUES (1, 'secret');\n\
+                    INSERT INTO public.other (id, email) VALUES (1, 'a@b.c');\n";
+        assert_eq!(
+            transform(dump),
+            "INSERT INTO public.users (id, email, phone) VALUES (1, 'user-it''s@b.c', NULL);\n\
+             INSERT INTO public.users (id, email, phone) VALUES (2, 'user-\\N', NULL);\n\
+             INSERT INTO public.other (id, email) VALUES (1, 'a@b.c');\n"
+        );
+    }
+
+    #[test]
+    fn inserts_with_table_columns() {
+        let dump = "CREATE TABLE public.users (\n    \
+                        id integer NOT NULL,\n    \
+                        \"phone\" text,\n    \
+                        email text DEFAULT 'a, b'::text,\n    \
+                        CONSTRAINT users_check CHECK ((id > 0))\n\
+                    );\n\
+                    \n\
+                    INSERT INTO public.users OVERRIDING SYSTEM VALUE VALUES\n\t\
+                        (1, '1''2''3', 'multi\n\
+                    line;'),\n\t\
+                        (2, '(1,2)'::text, 'x'::text);\n";
+        assert_eq!(
+            transform(dump),
+            "CREATE TABLE public.users (\n    \
+                 id integer NOT NULL,\n    \
+                 \"phone\" text,\n    \
+                 email text DEFAULT 'a, b'::text,\n    \
+                 CONSTRAINT users_check CHECK ((id > 0))\n\
+             );\n\
+             \n\
+             INSERT INTO public.users OVERRIDING SYSTEM VALUE VALUES\n\t\
+                 (1, NULL, 'user-multi\n\
+             line;'),\n\t\
+                 (2, NULL::text, 'user-x'::text);\n"
+        );
+    }
+
+    #[test]
+    fn function_bodies() {
+        let dump = "CREATE FUNCTION public.log_user() RETURNS trigger\n    \
+                        LANGUAGE plpgsql\n    \
+                        AS $_$\n\
+                    BEGIN\n\
+                    INSERT INTO public.users (id, email, phone) VALUES (NEW.id, 'a@b.c', '$1');\n\
+                    COPY public.users (id, email, phone) FROM stdin;\n\
+                    RETURN NEW;\n\
+                    END;\n\
+                    $_$;\n\
+                    CREATE FUNCTION public.one() RETURNS integer AS $$ SELECT 1 $$ LANGUAGE sql;\n\
+                    CREATE TRIGGER log_user AFTER INSERT ON public.users \
+                        FOR EACH ROW EXECUTE FUNCTION public.log_user();\n\
+                    INSERT INTO public.users (id, email, phone) VALUES (1, 'a@b.c', '123');\n";
+        assert_eq!(
+            transform(dump),
+            "CREATE FUNCTION public.log_user() RETURNS trigger\n    \
+                 LANGUAGE plpgsql\n    \
+                 AS $_$\n\
+             BEGIN\n\
+             INSERT INTO public.users (id, email, phone) VALUES (NEW.id, 'a@b.c', '$1');\n\
+             COPY public.users (id, email, phone) FROM stdin;\n\
+             RETURN NEW;\n\
+             END;\n\
+             $_$;\n\
+             CREATE FUNCTION public.one() RETURNS integer AS $$ SELECT 1 $$ LANGUAGE sql;\n\
+             CREATE TRIGGER log_user AFTER INSERT ON public.users \
+                 FOR EACH ROW EXECUTE FUNCTION public.log_user();\n\
+             INSERT INTO public.users (id, email, phone) VALUES (1, 'user-a@b.c', NULL);\n"
+        );
+    }
+
+    #[test]
+    fn dollar_tags() {
+        assert_eq!(open_dollar_tag("AS $_$", None), Some("$_$".to_string()));
+        assert_eq!(
+            open_dollar_tag("$_$ $$;", Some("$_$".to_string())),
+            Some("$$".to_string())
+        );
+        assert_eq!(open_dollar_tag("AS $$ SELECT $1 $$;", None), None);
+        assert_eq!(open_dollar_tag("SELECT 'a$$', a$b$ -- $$", None), None);
+        assert_eq!(
+            open_dollar_tag("END;", Some("$x$".to_string())),
+            Some("$x$".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_columns() {
+        let dump = "INSERT INTO public.users VALUES (1, 'a@b.c', '123');\n";
+        assert!(transform_with(CONFIG, dump).is_err());
+
+        let dump = "CREATE TABLE public.users (\n    id integer\n);\n\
+                    INSERT INTO public.users VALUES (1, 'a@b.c', '123');\n";
+        assert!(transform_with(CONFIG, dump).is_err());
+    }
+
+    #[test]
+    fn unfinished_dump() {
+        let dump = "COPY public.users (id, email, phone) FROM stdin;\n1\ta@b.c\t123\n";
+        assert!(transform_with(CONFIG, dump).is_err());
+
+        let dump = "INSERT INTO public.users (id, email, phone) VALUES (1, 'a;\n";
+        assert!(transform_with(CONFIG, dump).is_err());
+    }
+
+    #[test]
+    fn custom_format() {
+        assert!(transform_with(CONFIG, "PGDMP\x01\x0e\x00").is_err());
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let config = r#"
+          tables:
+            - name: users
+              rules:
+                email:
+                  sql: "md5(email)"
+        "#;
+        assert!(transform_with(config, "").is_err());
+
+        let config = r#"
+          tables:
+            - name: users
+              rules: {}
+              query:
+                limit: 10
+        "#;
+        assert!(transform_with(config, "").is_err());
+    }
+}
@@ -1,10 +1,23 @@
 use super::{
-    connector, query_wrapper::QueryWrapper, row::PgRow, schema_inspector::PgSchemaInspector,
+    binary_copy::{self, BinaryCopyReader, CopyFormat},
+    connector, large_objects,
+    query_wrapper::QueryWrapper,
+    relation::{Relation, RelationKind},
+    row::RowTransformer,
+    schema_inspector::PgSchemaInspector,
+    subset::Subset,
     table::PgTable,
 };
-use crate::{indicator::Indicator, Dumper, SchemaInspector, Table};
-use anyhow::Result;
-use datanymizer_engine::{Engine, Filter, Settings, TableList};
+use crate::{
+    checkpoint::{self, Checkpoint, CountingWriter},
+    indicator::Indicator,
+    sort_tables, Dumper, SchemaInspector, Table,
+};
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{
+    Engine, Filter, ForeignTables, LargeObjects, MaterializedViews, Settings, TableList,
+    TablePattern,
+};
 use postgres::IsolationLevel;
 use std::{
     io::{self, prelude::*},
@@ -15,11 +28,13 @@
 pub struct PgDumper<W: Write + Send, I: Indicator + Send> {
     schema_inspector: PgSchemaInspector,
     engine: Engine,
-    dump_writer: W,
+    dump_writer: CountingWriter<W>,
     indicator: I,
     dump_isolation_level: Option<IsolationLevel>,
     pg_dump_location: String,
     pg_dump_args: Vec<String>,
+    checkpoint: Option<Checkpoint>,
+    copy_format: CopyFormat,
 }
 
 impl<W: 'static + Write + Send, I: 'static + Indicator + Send> PgDumper<W, I> {
@@ -33,19 +48,55 @@ pub fn new(
     ) -> Result<Self> {
         Ok(Self {
             engine,
-            dump_writer,
+            dump_writer: CountingWriter::new(dump_writer),
             indicator,
             dump_isolation_level,
             pg_dump_location,
             schema_inspector: PgSchemaInspector {},
             pg_dump_args,
+            checkpoint: None,
+            copy_format: CopyFormat::default(),
         })
     }
 
-    fn run_pg_dump(&mut self, section: &str, db_url: &str) -> Result<()> {
+    /// Sets the COPY format for reading tables with transformation rules
+    pub fn set_copy_format(&mut self, copy_format: CopyFormat) {
+        self.copy_format = copy_format;
+    }
+
+    /// Records completed stages to the checkpoint, and skips stages that are already done
+    /// according to it. The writer should already contain the dump truncated to
+    /// the checkpoint offset.
+    pub fn set_checkpoint(&mut self, checkpoint: Checkpoint) {
+        self.dump_writer.set_count(checkpoint.offset());
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// Consumes the dumper, returning the underlying writer
+    pub fn into_writer(self) -> W {
+        self.dump_writer.into_inner()
+    }
+
+    fn is_done(&self, name: &str) -> bool {
+        self.checkpoint.as_ref().is_some_and(|c| c.is_done(name))
+    }
+
+    fn record_checkpoint(&mut self, name: &str) -> Result<()> {
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            self.dump_writer.flush()?;
+            checkpoint.record(name, self.dump_writer.count())?;
+        }
+        Ok(())
+    }
+
+    fn run_pg_dump(&mut self, section: &str, connection: &mut connector::Connection) -> Result<()> {
+        let mut table_args = table_args(&self.engine.settings.filter)?;
+        table_args.extend(self.partition_args(connection)?);
+        table_args.extend(self.relation_args(section, connection)?);
+
         let program = &self.pg_dump_location;
         let args = vec!["--section", section];
-        let table_args = table_args(&self.engine.settings.filter)?;
+        let db_url = connection.url.as_str();
 
         let dump_output = Command::new(program)
             .args(&self.pg_dump_args)
@@ -73,6 +124,45 @@ fn run_pg_dump(&mut self, section: &str, db_url: &str) -> Result<()> {
             .map_err(|e| e.into())
     }
 
+    // pg_dump filters partitions only by their own names, so partitions of filtered
+    // partitioned tables are passed to it explicitly
+    fn partition_args(&self, connection: &mut connector::Connection) -> Result<Vec<String>> {
+        match self.engine.settings.filter.as_ref() {
+            Some(filter) if filter.schema().is_some() => {
+                let partitions = self.schema_inspector.get_partitions(connection)?;
+                Ok(partition_args(filter, &partitions))
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    // Foreign tables are skipped in all sections, and data of materialized views (`REFRESH`)
+    // is in the post-data section. Dumped materialized views are skipped in all sections
+    // (their rows are dumped into tables in the data section).
+    fn relation_args(
+        &self,
+        section: &str,
+        connection: &mut connector::Connection,
+    ) -> Result<Vec<String>> {
+        let settings = &self.engine.settings;
+        let mut kinds = vec![];
+        if settings.foreign_tables == ForeignTables::Skip {
+            kinds.push(RelationKind::ForeignTable);
+        }
+        let views = settings.materialized_views;
+        if views == MaterializedViews::Dump
+            || (section == "post-data" && views == MaterializedViews::Skip)
+        {
+            kinds.push(RelationKind::MaterializedView);
+        }
+        if kinds.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let relations = self.schema_inspector.get_relations(connection, &kinds)?;
+        Ok(relation_args(&relations, views))
+    }
+
     fn dump_table(&mut self, table: &PgTable, qw: &mut QueryWrapper) -> Result<()> {
         let settings = self.settings();
         let started = Instant::now();
@@ -91,33 +181,143 @@ fn dump_table(&mut self, table: &PgTable, qw: &mut QueryWrapper) -> Result<()> {
         let mut count: u64 = 0;
         if let Some(cfg) = cfg {
             if let Some(transformed_query) = table.transformed_query_to(Some(cfg), count) {
-                let reader = qw.copy_out(transformed_query.as_str())?;
-                for line in reader.lines() {
-                    self.indicator.inc_pb(1);
-
-                    let row = PgRow::from_string_row(line?, table.clone());
-                    let transformed = row.transform(&self.engine, cfg.name.as_str())?;
-                    self.dump_writer.write_all(transformed.as_bytes())?;
-                    self.dump_writer.write_all(b"\n")?;
-
-                    count += 1;
+                let mut transformer = RowTransformer::new(&self.engine, table, cfg.name.as_str());
+                if self.copy_format == CopyFormat::Binary
+                    && binary_copy::is_supported(table, Some(cfg))
+                {
+                    let query = binary_copy::binary_query(&transformed_query, table, Some(cfg));
+                    let reader = qw.copy_out(query.as_str())?;
+                    count += Self::dump_binary_rows(
+                        &mut self.dump_writer,
+                        &self.indicator,
+                        &mut transformer,
+                        BinaryCopyReader::new(reader, table, Some(cfg)).ok_or_else(|| {
+                            anyhow!("Binary COPY is not supported for {}", table.get_full_name())
+                        })?,
+                    )?;
+                } else {
+                    // The line buffer is reused (values are validated as UTF-8 in place)
+                    let mut reader = qw.copy_out(transformed_query.as_str())?;
+                    let mut line = Vec::new();
+                    while reader.read_until(b'\n', &mut line)? > 0 {
+                        self.indicator.inc_pb(1);
+
+                        let bytes = line.strip_suffix(b"\n").unwrap_or(&line);
+                        transformer
+                            .write_line(std::str::from_utf8(bytes)?, &mut self.dump_writer)?;
+                        self.dump_writer.write_all(b"\n")?;
+                        line.clear();
+
+                        count += 1;
+                    }
                 }
             }
         }
 
         if let Some(untransformed_query) = table.untransformed_query_to(cfg, count) {
-            let reader = qw.copy_out(untransformed_query.as_str())?;
-            for line in reader.lines() {
+            // Lines are copied as is (without UTF-8 validation)
+            let mut reader = qw.copy_out(untransformed_query.as_str())?;
+            let mut line = Vec::new();
+            while reader.read_until(b'\n', &mut line)? > 0 {
                 self.indicator.inc_pb(1);
 
-                self.dump_writer.write_all(line?.as_bytes())?;
-                self.dump_writer.write_all(b"\n")?;
+                self.dump_writer.write_all(&line)?;
+                line.clear();
 
                 count += 1;
             }
         }
 
         self.dump_writer.write_all(b"\\.\n")?;
+        self.dump_sequences(table, qw)?;
+
+        let finished = started.elapsed();
+        self.indicator
+            .finish_pb(table.get_full_name().as_str(), finished);
+
+        Ok(())
+    }
+
+    // Rows of materialized views are dumped (with transformations) into tables
+    // with the same names (the views themselves are excluded from the schema)
+    fn dump_materialized_views(
+        &mut self,
+        views: Vec<PgTable>,
+        qw: &mut QueryWrapper,
+    ) -> Result<()> {
+        let settings = self.settings();
+        for mut view in views {
+            let name = view.get_full_name();
+            if self.is_done(&name) {
+                self.debug(format!("[Dumping: {}] --- DONE (checkpoint) ---", name));
+                continue;
+            }
+            if !self.filter_table(&[&name], &settings.filter) {
+                self.debug(format!("[Dumping: {}] --- SKIP ---", name));
+                continue;
+            }
+
+            if let Some(cfg) = settings.find_table(&view.get_names()) {
+                view.apply_column_exclusions(cfg)?;
+            }
+            self.dump_writer.write_all(b"\n")?;
+            self.dump_writer.write_all(view.create_query().as_bytes())?;
+            self.dump_writer.write_all(b"\n")?;
+            self.dump_table(&view, qw)?;
+            self.record_checkpoint(&name)?;
+        }
+
+        Ok(())
+    }
+
+    // An associated function, because the row transformer borrows the engine
+    fn dump_binary_rows<R: BufRead>(
+        writer: &mut CountingWriter<W>,
+        indicator: &I,
+        transformer: &mut RowTransformer,
+        mut reader: BinaryCopyReader<R>,
+    ) -> Result<u64> {
+        let mut values = vec![];
+        let mut count = 0;
+        while reader.read_row(&mut values)? {
+            indicator.inc_pb(1);
+
+            let values: Vec<_> = values.iter().map(|v| v.as_str()).collect();
+            transformer.write_values(&values, writer)?;
+            writer.write_all(b"\n")?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn dump_large_objects(
+        &mut self,
+        cfg: &LargeObjects,
+        created: bool,
+        qw: &mut QueryWrapper,
+    ) -> Result<()> {
+        let started = Instant::now();
+        self.debug("Fetch large objects...".into());
+        let objects = large_objects::fetch(qw, cfg)?;
+        if objects.is_empty() {
+            return Ok(());
+        }
+
+        self.write_log("Large objects".into())?;
+        self.indicator
+            .start_pb(objects.len() as u64, "large objects");
+        for (object, action) in objects.iter() {
+            large_objects::write(&mut self.dump_writer, qw, object, *action, created)?;
+            self.indicator.inc_pb(1);
+        }
+        self.indicator.finish_pb("large objects", started.elapsed());
+
+        Ok(())
+    }
+
+    fn dump_sequences(&mut self, table: &PgTable, qw: &mut QueryWrapper) -> Result<()> {
         for seq in &table.sequences {
             let last_value: i64 = qw.query_one(seq.last_value_query().as_str(), &[])?.get(0);
             self.dump_writer.write_all(b"\n")?;
@@ -125,11 +325,6 @@ fn dump_table(&mut self, table: &PgTable, qw: &mut QueryWrapper) -> Result<()> {
                 .write_all(seq.setval_query(last_value).as_bytes())?;
             self.dump_writer.write_all(b"\n")?;
         }
-
-        let finished = started.elapsed();
-        self.indicator
-            .finish_pb(table.get_full_name().as_str(), finished);
-
         Ok(())
     }
 }
@@ -141,23 +336,73 @@ impl<W: 'static + Write + Send, I: 'static + Indicator + Send> Dumper for PgDump
 
     // Stage before dumping data. It makes dump schema with any options
     fn pre_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        if self.is_done(checkpoint::PRE_DATA) {
+            self.debug("Data scheme is already dumped (checkpoint)".into());
+            return Ok(());
+        }
+
         self.debug("Prepare data scheme...".into());
-        self.run_pg_dump("pre-data", connection.url.as_str())
+        self.run_pg_dump("pre-data", connection)?;
+        self.record_checkpoint(checkpoint::PRE_DATA)
     }
 
     // This stage makes dump data only
     fn data(&mut self, connection: &mut Self::Connection) -> Result<()> {
         let settings = self.settings();
-        self.write_log("Start dumping data".into())?;
+        if self.checkpoint.as_ref().is_some_and(|c| c.offset() > 0) {
+            self.write_log("Resume dumping data".into())?;
+        } else {
+            self.write_log("Start dumping data".into())?;
+        }
         self.debug("Fetch tables metadata...".into());
 
         let mut tables = self.schema_inspector().ordered_tables(connection);
+        for (table, _) in tables.iter_mut() {
+            if let Some(cfg) = settings.find_table(&table.get_names()) {
+                // Each partition is dumped with its own query, so the limit would be applied
+                // to every partition separately
+                if table.is_partitioned && cfg.query.as_ref().is_some_and(|q| q.limit.is_some()) {
+                    return Err(anyhow!(
+                        "Limits are not supported for partitioned tables ({}), set them for partitions",
+                        table.get_full_name()
+                    ));
+                }
+                table.apply_column_exclusions(cfg)?;
+            }
+        }
+        if let Some(subset_cfg) = &settings.subset {
+            self.debug("Prepare subset conditions...".into());
+            let graph = self.schema_inspector().get_foreign_key_graph(connection)?;
+            let all_tables: Vec<PgTable> = tables.iter().map(|(t, _)| t.clone()).collect();
+            let subset = Subset::new(subset_cfg, &all_tables, graph.references())?;
+            // Written to the dump too: the dump may be inconsistent
+            for r in subset.skipped_references.iter() {
+                let message = format!(
+                    "[Subset] Warning: foreign key {}({}) -> {}({}) is a part of a cycle, \
+                     some referenced rows may be missing",
+                    r.table,
+                    r.columns.join(", "),
+                    r.foreign_table,
+                    r.foreign_columns.join(", ")
+                );
+                self.debug(message.clone());
+                self.write_log(message)?;
+            }
+            for (table, _) in tables.iter_mut() {
+                table.set_subset_condition(subset.condition(table).cloned());
+            }
+        }
         sort_tables(
             &mut tables,
             settings.table_order.as_ref().unwrap_or(&vec![]),
         );
 
         let all_tables_count = tables.len();
+        let views = if settings.materialized_views == MaterializedViews::Dump {
+            self.schema_inspector.get_materialized_views(connection)?
+        } else {
+            vec![]
+        };
 
         let mut query_wrapper =
             QueryWrapper::with_isolation_level(&mut connection.client, self.dump_isolation_level)?;
@@ -169,10 +414,40 @@ fn data(&mut self, connection: &mut Self::Connection) -> Result<()> {
                 table.get_full_name(),
             ));
 
-            if self.filter_table(table.get_full_name(), &settings.filter) {
+            if self.is_done(&table.get_full_name()) {
+                self.debug(format!(
+                    "[Dumping: {}] --- DONE (checkpoint) ---",
+                    table.get_full_name()
+                ));
+            } else if !self.filter_table(&table.full_names(), &settings.filter) {
+                self.debug(format!("[Dumping: {}] --- SKIP ---", table.get_full_name()));
+            } else if table.is_partitioned {
+                // Data is dumped from partitions, but sequences may belong to the partitioned table
+                self.debug(format!(
+                    "[Dumping: {}] --- PARTITIONED (data is dumped from partitions) ---",
+                    table.get_full_name()
+                ));
+                self.dump_sequences(table, &mut query_wrapper)?;
+                self.record_checkpoint(&table.get_full_name())?;
+            } else {
                 self.dump_table(table, &mut query_wrapper)?;
+                self.record_checkpoint(&table.get_full_name())?;
+            }
+        }
+
+        self.dump_materialized_views(views, &mut query_wrapper)?;
+
+        if let Some(lo_cfg) = &settings.large_objects {
+            if self.is_done(checkpoint::LARGE_OBJECTS) {
+                self.debug("Large objects are already dumped (checkpoint)".into());
             } else {
-                self.debug(format!("[Dumping: {}] --- SKIP ---", table.get_full_name()));
+                // pg_dump creates (empty) large objects only if the schema is not filtered by `only`
+                let created = !matches!(
+                    settings.filter.as_ref().and_then(|f| f.schema()),
+                    Some(TableList::Only(_))
+                );
+                self.dump_large_objects(lo_cfg, created, &mut query_wrapper)?;
+                self.record_checkpoint(checkpoint::LARGE_OBJECTS)?;
             }
         }
 
@@ -183,7 +458,14 @@ fn data(&mut self, connection: &mut Self::Connection) -> Result<()> {
     // This stage makes dump foreign keys, indices and other...
     fn post_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
         self.debug("Finishing with indexes...".into());
-        self.run_pg_dump("post-data", connection.url.as_str())
+        self.run_pg_dump("post-data", connection)?;
+
+        // The dump is completed, so there is nothing to resume
+        if let Some(checkpoint) = self.checkpoint.take() {
+            self.dump_writer.flush()?;
+            checkpoint.remove()?;
+        }
+        Ok(())
     }
 
     fn schema_inspector(&self) -> Self::SchemaInspector {
@@ -208,14 +490,14 @@ fn debug(&self, message: String) {
 fn table_args(filter: &Option<Filter>) -> Result<Vec<String>> {
     let mut args = vec![];
     if let Some(f) = filter {
-        if let Some(list) = &f.schema {
+        if let Some(list) = f.schema() {
             let flag = match list {
                 TableList::Only(_) => "-t",
                 TableList::Except(_) => "-T",
             };
             for table in list.tables() {
                 args.push(String::from(flag));
-                args.push(PgTable::quote_table_name(table.as_str())?);
+                args.push(table_arg(table.as_str())?);
             }
         }
     }
@@ -223,11 +505,65 @@ fn table_args(filter: &Option<Filter>) -> Result<Vec<String>> {
     Ok(args)
 }
 
-fn sort_tables(tables: &mut Vec<(PgTable, i32)>, order: &[String]) {
-    tables.sort_by_cached_key(|(tbl, weight)| {
-        let position = order.iter().position(|i| tbl.get_names().contains(i));
-        (position, -weight)
-    });
+fn partition_args(filter: &Filter, partitions: &[PgTable]) -> Vec<String> {
+    let flag = match filter.schema() {
+        Some(TableList::Only(_)) => "-t",
+        Some(TableList::Except(_)) => "-T",
+        None => return vec![],
+    };
+    let mut args = vec![];
+    for partition in partitions {
+        if filter.filter_schema(&partition.get_full_name())
+            != filter.filter_schema_names(&partition.full_names())
+        {
+            args.push(String::from(flag));
+            args.push(partition.quoted_full_name());
+        }
+    }
+    args
+}
+
+fn relation_args(relations: &[Relation], views: MaterializedViews) -> Vec<String> {
+    let mut args = vec![];
+    for relation in relations {
+        let flag = match relation.kind {
+            RelationKind::ForeignTable => "-T",
+            RelationKind::MaterializedView if views == MaterializedViews::Dump => "-T",
+            RelationKind::MaterializedView => "--exclude-table-data",
+            _ => continue,
+        };
+        args.push(String::from(flag));
+        args.push(relation.quoted_full_name());
+    }
+    args
+}
+
+// Globs are passed to pg_dump as patterns (only parts with wildcards are unquoted)
+fn table_arg(table: &str) -> Result<String> {
+    if TablePattern::is_regex(table) {
+        return Err(anyhow!(
+            "Regular expressions are not supported in the schema filter ({}), use globs instead",
+            table
+        ));
+    }
+    if !TablePattern::is_glob(table) {
+        return PgTable::quote_table_name(table);
+    }
+
+    let parts: Vec<String> = table
+        .split('.')
+        .map(|p| {
+            if TablePattern::is_glob(p) {
+                p.to_string()
+            } else {
+                format!(r#""{}""#, p)
+            }
+        })
+        .collect();
+    if parts.len() > 2 {
+        return Err(anyhow!("Invalid table name {}", table));
+    }
+    Ok(parts.join("."))
 }
 
 #[cfg(test)]
@@ -239,28 +575,22 @@ fn test_table_args() {
         let empty: Vec<String> = vec![];
         assert_eq!(table_args(&None).unwrap(), empty);
 
-        let filter = Filter {
-            schema: Some(TableList::Except(vec![String::from("table1")])),
-            data: None,
-        };
+        let filter = Filter::new(Some(TableList::Except(vec![String::from("table1")])), None);
         assert_eq!(
             table_args(&Some(filter)).unwrap(),
             vec![String::from("-T"), String::from("\"table1\"")]
         );
 
-        let filter = Filter {
-            schema: None,
-            data: Some(TableList::Except(vec![String::from("table1")])),
-        };
+        let filter = Filter::new(None, Some(TableList::Except(vec![String::from("table1")])));
         assert_eq!(table_args(&Some(filter)).unwrap(), empty);
 
-        let filter = Filter {
-            schema: Some(TableList::Only(vec![
+        let filter = Filter::new(
+            Some(TableList::Only(vec![
                 String::from("table1"),
                 String::from("table2"),
             ])),
-            data: None,
-        };
+            None,
+        );
         assert_eq!(
             table_args(&Some(filter)).unwrap(),
             vec![
@@ -303,4 +633,163 @@ fn test_sort_tables() {
             ]
         )
     }
+
+    #[test]
+    fn test_partition_args() {
+        let partition = |name: &str, parents: &[&str]| {
+            let mut table = PgTable::new(String::from(name), String::from("public"));
+            table.partition_parents = parents.iter().map(|p| p.to_string()).collect();
+            table
+        };
+        let partitions = vec![
+            partition("events_2024", &["public.events"]),
+            partition("events_2024_01", &["public.events_2024", "public.events"]),
+            partition("logs_2024", &["public.logs"]),
+        ];
+
+        let filter = Filter::new(
+            Some(TableList::Except(vec![
+                String::from("public.events"),
+                String::from("public.events_2024"),
+            ])),
+            None,
+        );
+        assert_eq!(
+            partition_args(&filter, &partitions),
+            vec![
+                String::from("-T"),
+                String::from("\"public\".\"events_2024_01\""),
+            ]
+        );
+
+        let filter = Filter::new(
+            Some(TableList::Only(vec![String::from("public.logs")])),
+            None,
+        );
+        assert_eq!(
+            partition_args(&filter, &partitions),
+            vec![String::from("-t"), String::from("\"public\".\"logs_2024\"")]
+        );
+    }
+
+    #[test]
+    fn test_relation_args() {
+        let relation = |name: &str, kind: RelationKind| Relation {
+            schemaname: String::from("public"),
+            name: String::from(name),
+            kind,
+        };
+        let relations = vec![
+            relation("remote_users", RelationKind::ForeignTable),
+            relation("stats", RelationKind::MaterializedView),
+            relation("users", RelationKind::Table),
+        ];
+        assert_eq!(
+            relation_args(&relations, MaterializedViews::Skip),
+            vec![
+                String::from("-T"),
+                String::from("\"public\".\"remote_users\""),
+                String::from("--exclude-table-data"),
+                String::from("\"public\".\"stats\""),
+            ]
+        );
+        assert_eq!(
+            relation_args(&relations, MaterializedViews::Dump),
+            vec![
+                String::from("-T"),
+                String::from("\"public\".\"remote_users\""),
+                String::from("-T"),
+                String::from("\"public\".\"stats\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_args_with_patterns() {
+        let filter = Filter::new(
+            Some(TableList::Except(vec![
+                String::from("public.events_*"),
+                String::from("*.Logs"),
+            ])),
+            None,
+        );
+        assert_eq!(
+            table_args(&Some(filter)).unwrap(),
+            vec![
+                String::from("-T"),
+                String::from("\"public\".events_*"),
+                String::from("-T"),
+                String::from("*.\"Logs\""),
+            ]
+        );
+
+        let filter = Filter::new(
+            Some(TableList::Only(vec![String::from("/^events_\\d+$/")])),
+            None,
+        );
+        assert!(table_args(&Some(filter)).is_err());
+    }
+
+    #[test]
+    fn test_sort_tables_with_patterns() {
+        let order = vec![
+            "public.events_special".to_string(),
+            "public.events_*".to_string(),
+            "/^tenant_\\d+\\.users$/".to_string(),
+        ];
+
+        let mut tables = vec![
+            (PgTable::new("users".to_string(), "tenant_1".to_string()), 0),
+            (
+                PgTable::new("events_2".to_string(), "public".to_string()),
+                0,
+            ),
+            (
+                PgTable::new("events_special".to_string(), "public".to_string()),
+                0,
+            ),
+            (
+                PgTable::new("events_1".to_string(), "public".to_string()),
+                0,
+            ),
+            (PgTable::new("users".to_string(), "public".to_string()), 0),
+        ];
+
+        sort_tables(&mut tables, &order);
+
+        let ordered_names: Vec<_> = tables.iter().map(|(t, _)| t.get_full_name()).collect();
+        assert_eq!(
+            ordered_names,
+            vec![
+                "public.users".to_string(),
+                "public.events_special".to_string(),
+                "public.events_1".to_string(),
+                "public.events_2".to_string(),
+                "tenant_1.users".to_string(),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_sort_tables_with_same_weight() {
+        let mut tables = vec![
+            (PgTable::new("table2".to_string(), "public".to_string()), 0),
+            (PgTable::new("table3".to_string(), "public".to_string()), 1),
+            (PgTable::new("table1".to_string(), "public".to_string()), 0),
+            (PgTable::new("table1".to_string(), "other".to_string()), 0),
+        ];
+
+        sort_tables(&mut tables, &[]);
+
+        let ordered_names: Vec<_> = tables.iter().map(|(t, _)| t.get_full_name()).collect();
+        assert_eq!(
+            ordered_names,
+            vec![
+                "public.table3".to_string(),
+                "other.table1".to_string(),
+                "public.table1".to_string(),
+                "public.table2".to_string(),
+            ]
+        )
+    }
 }
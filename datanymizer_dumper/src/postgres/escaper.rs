@@ -21,7 +21,7 @@ pub fn replace_chars(s: &mut String) {
     let mut beginning = 0;
     let mut slash_count = 0;
 
-    for (i, c) in s.chars().enumerate() {
+    for (i, c) in s.char_indices() {
         if let Some(replacement) = match c {
             '\x08' => Some(r#"\b"#),
             '\x0C' => Some(r#"\f"#),
@@ -43,7 +43,7 @@ pub fn replace_chars(s: &mut String) {
                     new_s.push_str(&s[beginning..i])
                 }
                 new_s.push_str(replacement);
-                beginning = i + 1;
+                beginning = i + c.len_utf8();
             }
         }
     }
@@ -124,6 +124,20 @@ fn all_sequences() {
         assert_eq!(s, r#"\ta\vb\\c\b\f\r\n"#);
     }
 
+    #[test]
+    fn multibyte() {
+        let mut s = String::from("имя\tфамилия\n");
+        replace_chars(&mut s);
+        assert_eq!(s, r#"имя\tфамилия\n"#);
+    }
+
+    #[test]
+    fn bytea_hex() {
+        let mut s = String::from(r#"\x89504e47"#);
+        replace_chars(&mut s);
+        assert_eq!(s, r#"\\x89504e47"#);
+    }
+
     mod null_like_sequences {
         use super::*;
 
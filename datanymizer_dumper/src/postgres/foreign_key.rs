@@ -1,5 +1,7 @@
 use postgres::Row as PostgresRow;
+use std::collections::{BTreeMap, BTreeSet};
 
+/// Foreign key column (one item per column of a constraint)
 #[derive(Debug)]
 pub struct ForeignKey {
     // Source
@@ -31,3 +33,178 @@ fn from(row: PostgresRow) -> Self {
         }
     }
 }
+
+/// Foreign key (possibly composite) between two tables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// Full name of the referencing table
+    pub table: String,
+    pub columns: Vec<String>,
+    /// Full name of the referenced table
+    pub foreign_table: String,
+    pub foreign_columns: Vec<String>,
+}
+
+impl Reference {
+    /// Groups foreign key rows (one row per column) by constraints
+    pub fn from_foreign_keys(fkeys: &[ForeignKey]) -> Vec<Self> {
+        let mut references: BTreeMap<(String, String, String), Self> = BTreeMap::new();
+        for fkey in fkeys {
+            let reference = references
+                .entry((
+                    fkey.table_schema.clone(),
+                    fkey.table_name.clone(),
+                    fkey.constraint_name.clone(),
+                ))
+                .or_insert_with(|| Self {
+                    table: format!("{}.{}", fkey.table_schema, fkey.table_name),
+                    columns: vec![],
+                    foreign_table: format!(
+                        "{}.{}",
+                        fkey.foreign_table_schema, fkey.foreign_table_name
+                    ),
+                    foreign_columns: vec![],
+                });
+
+            if !reference.columns.contains(&fkey.column_name) {
+                reference.columns.push(fkey.column_name.clone());
+            }
+            if !reference
+                .foreign_columns
+                .contains(&fkey.foreign_column_name)
+            {
+                reference
+                    .foreign_columns
+                    .push(fkey.foreign_column_name.clone());
+            }
+        }
+
+        references.into_values().collect()
+    }
+
+    pub fn is_self_reference(&self) -> bool {
+        self.table == self.foreign_table
+    }
+}
+
+/// Graph of foreign keys between tables (by full table names).
+/// It is used for subsetting and for ordering tables by dependencies.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForeignKeyGraph {
+    references: Vec<Reference>,
+}
+
+impl ForeignKeyGraph {
+    pub fn new(references: Vec<Reference>) -> Self {
+        Self { references }
+    }
+
+    pub fn from_foreign_keys(fkeys: &[ForeignKey]) -> Self {
+        Self::new(Reference::from_foreign_keys(fkeys))
+    }
+
+    /// All foreign keys
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    /// Foreign keys of the table (referencing other tables or itself)
+    pub fn references_from<'a>(&'a self, table: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.references.iter().filter(move |r| r.table == table)
+    }
+
+    /// Foreign keys referencing the table (from other tables or itself)
+    pub fn references_to<'a>(&'a self, table: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.references
+            .iter()
+            .filter(move |r| r.foreign_table == table)
+    }
+
+    /// Tables referenced by the table (self-references are skipped)
+    pub fn dependencies(&self, table: &str) -> BTreeSet<&str> {
+        self.references
+            .iter()
+            .filter(|r| r.table == table && !r.is_self_reference())
+            .map(|r| r.foreign_table.as_str())
+            .collect()
+    }
+
+    /// Does the table reference itself (e.g., a tree)?
+    pub fn has_self_reference(&self, table: &str) -> bool {
+        self.references_from(table).any(|r| r.is_self_reference())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_foreign_keys() {
+        let fkey = |constraint: &str, column: &str, foreign_column: &str| ForeignKey {
+            table_schema: "public".to_string(),
+            table_name: "orders".to_string(),
+            constraint_name: constraint.to_string(),
+            column_name: column.to_string(),
+            foreign_table_schema: "public".to_string(),
+            foreign_table_name: "users".to_string(),
+            foreign_column_name: foreign_column.to_string(),
+        };
+
+        let references = Reference::from_foreign_keys(&[
+            fkey("orders_user_fk", "user_id", "id"),
+            fkey("orders_owner_fk", "owner_tenant_id", "tenant_id"),
+            fkey("orders_owner_fk", "owner_tenant_id", "id"),
+            fkey("orders_owner_fk", "owner_id", "tenant_id"),
+            fkey("orders_owner_fk", "owner_id", "id"),
+        ]);
+
+        assert_eq!(
+            references,
+            vec![
+                Reference {
+                    table: "public.orders".to_string(),
+                    columns: vec!["owner_tenant_id".to_string(), "owner_id".to_string()],
+                    foreign_table: "public.users".to_string(),
+                    foreign_columns: vec!["tenant_id".to_string(), "id".to_string()],
+                },
+                Reference {
+                    table: "public.orders".to_string(),
+                    columns: vec!["user_id".to_string()],
+                    foreign_table: "public.users".to_string(),
+                    foreign_columns: vec!["id".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn graph() {
+        let reference = |table: &str, foreign_table: &str| Reference {
+            table: table.to_string(),
+            columns: vec!["ref_id".to_string()],
+            foreign_table: foreign_table.to_string(),
+            foreign_columns: vec!["id".to_string()],
+        };
+        let graph = ForeignKeyGraph::new(vec![
+            reference("public.orders", "public.users"),
+            reference("public.orders", "billing.users"),
+            reference("public.orders", "public.users"),
+            reference("public.users", "public.users"),
+        ]);
+
+        assert_eq!(
+            graph.dependencies("public.orders"),
+            BTreeSet::from(["billing.users", "public.users"])
+        );
+        assert!(graph.dependencies("public.users").is_empty());
+        assert!(graph.dependencies("billing.users").is_empty());
+
+        assert!(graph.has_self_reference("public.users"));
+        assert!(!graph.has_self_reference("public.orders"));
+
+        assert_eq!(graph.references_from("public.orders").count(), 3);
+        assert_eq!(graph.references_to("public.users").count(), 3);
+        assert_eq!(graph.references_to("billing.users").count(), 1);
+    }
+}
@@ -0,0 +1,205 @@
+use super::query_wrapper::QueryWrapper;
+use anyhow::Result;
+use datanymizer_engine::{LargeObjectAction, LargeObjects as LargeObjectsCfg};
+use postgres::types::Oid;
+use std::{collections::HashSet, fmt::Write as _, io::Write};
+
+/// Size of chunks for reading and writing content
+const CHUNK_SIZE: i32 = 1024 * 1024;
+
+const INV_WRITE: i32 = 0x20000;
+
+const INV_READ: i32 = 0x40000;
+
+// Sizes are read via descriptors (`pg_largeobject` is readable only by superusers).
+// The dump transaction can be long, so descriptors are closed right away
+// (CTEs with volatile functions are evaluated once, so each descriptor is closed after seeking).
+const LARGE_OBJECTS_QUERY: &str = "WITH descriptors AS (
+         SELECT m.oid, pg_catalog.lo_open(m.oid, $1) AS fd
+         FROM pg_catalog.pg_largeobject_metadata m
+     ), sizes AS (
+         SELECT d.oid, d.fd, pg_catalog.lo_lseek64(d.fd, 0, 2) AS size FROM descriptors d
+     )
+     SELECT s.oid, s.size, pg_catalog.lo_close(s.fd) FROM sizes s
+     ORDER BY s.oid";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargeObject {
+    pub oid: Oid,
+    pub size: i64,
+}
+
+/// Fetches all large objects with actions according to the rules
+pub fn fetch(
+    qw: &mut QueryWrapper,
+    cfg: &LargeObjectsCfg,
+) -> Result<Vec<(LargeObject, LargeObjectAction)>> {
+    let objects: Vec<LargeObject> = qw
+        .query(LARGE_OBJECTS_QUERY, &[&INV_READ])?
+        .into_iter()
+        .map(|row| LargeObject {
+            oid: row.get("oid"),
+            size: row.get("size"),
+        })
+        .collect();
+
+    let mut rule_oids = Vec::with_capacity(cfg.rules.len());
+    for rule in cfg.rules.iter() {
+        let mut oids: HashSet<Oid> = rule.oids.iter().cloned().collect();
+        if let Some(query) = &rule.query {
+            let query = format!("SELECT q.oid::oid FROM ({}) AS q(oid)", query);
+            for row in qw.query(query.as_str(), &[])? {
+                if let Some(oid) = row.get::<_, Option<Oid>>(0) {
+                    oids.insert(oid);
+                }
+            }
+        }
+        rule_oids.push(oids);
+    }
+
+    Ok(with_actions(cfg, objects, &rule_oids))
+}
+
+/// The first matching rule (in the config order) is applied, otherwise the default action
+fn with_actions(
+    cfg: &LargeObjectsCfg,
+    objects: Vec<LargeObject>,
+    rule_oids: &[HashSet<Oid>],
+) -> Vec<(LargeObject, LargeObjectAction)> {
+    objects
+        .into_iter()
+        .map(|object| {
+            let action = cfg
+                .rules
+                .iter()
+                .zip(rule_oids.iter())
+                .find(|(_, oids)| oids.contains(&object.oid))
+                .map_or(cfg.default, |(rule, _)| rule.action);
+            (object, action)
+        })
+        .collect()
+}
+
+/// Writes SQL statements restoring the large object.
+/// `created` means that the object is already created by the schema dump (it is empty there).
+pub fn write<W: Write>(
+    writer: &mut W,
+    qw: &mut QueryWrapper,
+    object: &LargeObject,
+    action: LargeObjectAction,
+    created: bool,
+) -> Result<()> {
+    if action == LargeObjectAction::Drop {
+        if created {
+            writeln!(writer, "SELECT pg_catalog.lo_unlink('{}');", object.oid)?;
+        }
+        return Ok(());
+    }
+
+    if !created {
+        writeln!(writer, "SELECT pg_catalog.lo_create('{}');", object.oid)?;
+    }
+
+    match action {
+        LargeObjectAction::Placeholder => {
+            writeln!(writer, "{}", placeholder_sql(object))?;
+        }
+        _ => {
+            let mut offset: i64 = 0;
+            while offset < object.size {
+                let chunk: Vec<u8> = qw
+                    .query_one(
+                        "SELECT pg_catalog.lo_get($1, $2, $3)",
+                        &[&object.oid, &offset, &CHUNK_SIZE],
+                    )?
+                    .get(0);
+                if chunk.is_empty() {
+                    break;
+                }
+                writeln!(writer, "{}", put_sql(object.oid, offset, &chunk))?;
+                offset += chunk.len() as i64;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Truncating to a greater length fills the object with zero bytes
+fn placeholder_sql(object: &LargeObject) -> String {
+    format!(
+        "SELECT pg_catalog.lo_truncate64(pg_catalog.lo_open('{}', {}), {});",
+        object.oid, INV_WRITE, object.size
+    )
+}
+
+fn put_sql(oid: Oid, offset: i64, data: &[u8]) -> String {
+    let mut hex = String::with_capacity(data.len() * 2);
+    for byte in data {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    format!(
+        "SELECT pg_catalog.lo_put('{}', {}, '\\x{}');",
+        oid, offset, hex
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datanymizer_engine::LargeObjectRule;
+
+    fn object(oid: Oid) -> LargeObject {
+        LargeObject { oid, size: 10 }
+    }
+
+    #[test]
+    fn actions() {
+        let rule = |oids: Vec<Oid>, action| LargeObjectRule {
+            oids,
+            query: None,
+            action,
+        };
+        let cfg = LargeObjectsCfg {
+            default: LargeObjectAction::Drop,
+            rules: vec![
+                rule(vec![1], LargeObjectAction::Keep),
+                rule(vec![1, 2], LargeObjectAction::Placeholder),
+            ],
+        };
+        let rule_oids: Vec<HashSet<Oid>> = cfg
+            .rules
+            .iter()
+            .map(|r| r.oids.iter().cloned().collect())
+            .collect();
+
+        let actions: Vec<_> = with_actions(&cfg, vec![object(1), object(2), object(3)], &rule_oids)
+            .into_iter()
+            .map(|(o, a)| (o.oid, a))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (1, LargeObjectAction::Keep),
+                (2, LargeObjectAction::Placeholder),
+                (3, LargeObjectAction::Drop),
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholder() {
+        assert_eq!(
+            placeholder_sql(&object(16400)),
+            "SELECT pg_catalog.lo_truncate64(pg_catalog.lo_open('16400', 131072), 10);"
+        );
+    }
+
+    #[test]
+    fn put() {
+        assert_eq!(
+            put_sql(16400, 1048576, &[0, 15, 255]),
+            "SELECT pg_catalog.lo_put('16400', 1048576, '\\x000fff');"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow::{anyhow, Result};
+use postgres::{Client, Transaction};
+use std::{
+    io::{self, prelude::*, BufReader},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+const CHANNEL_CAPACITY: usize = 64;
+const COPY_END: &[u8] = b"\\.";
+
+/// Writer that loads the dump directly into the target database instead of writing SQL text
+/// somewhere (`psql` equivalent).
+/// Schema statements are executed as is, and table data (`COPY ... FROM STDIN` blocks) is
+/// streamed with the `COPY` protocol, so the dump is never stored in full.
+/// Loading happens in a separate thread, call `finish` to wait for it and get a result.
+/// The whole dump is loaded in one transaction, it is committed only by `finish`
+/// (if the loader is dropped without it, e.g. after a dump error, nothing is loaded).
+pub struct PgLoader {
+    sender: Option<SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<()>>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl PgLoader {
+    pub fn new(client: Client) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let finished = Arc::new(AtomicBool::new(false));
+        let worker_finished = finished.clone();
+        let worker = thread::spawn(move || {
+            let mut client = client;
+            let mut tx = client.transaction()?;
+            load(BufReader::new(ChannelReader::new(receiver)), &mut tx)?;
+            if !worker_finished.load(Ordering::SeqCst) {
+                return Err(anyhow!("The dump is not finished, loading is rolled back"));
+            }
+            tx.commit()?;
+            Ok(())
+        });
+
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+            finished,
+        }
+    }
+
+    /// Waits for all data to be loaded and commits it
+    pub fn finish(mut self) -> Result<()> {
+        self.finished.store(true, Ordering::SeqCst);
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.sender.take();
+        match self.worker.take() {
+            Some(worker) => worker
+                .join()
+                .map_err(|_| anyhow!("The loading thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Write for PgLoader {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let sent = match &self.sender {
+            Some(sender) => sender.send(buf.to_vec()).is_ok(),
+            None => false,
+        };
+
+        if sent {
+            Ok(buf.len())
+        } else {
+            // The loading thread has stopped, we try to get its error
+            let reason = match self.stop() {
+                Err(e) => e.to_string(),
+                Ok(_) => String::from("Loading is already finished"),
+            };
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, reason))
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PgLoader {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// The target for loading. It is implemented for Postgres transactions, but it can be replaced
+/// (e.g., in tests).
+trait LoadTarget {
+    /// Executes SQL (may contain several statements)
+    fn execute(&mut self, sql: &str) -> Result<()>;
+
+    /// Executes the `COPY ... FROM STDIN` query, `data` must write all rows to the given writer
+    fn copy_in(
+        &mut self,
+        query: &str,
+        data: &mut dyn FnMut(&mut dyn Write) -> Result<()>,
+    ) -> Result<()>;
+}
+
+impl LoadTarget for Transaction<'_> {
+    fn execute(&mut self, sql: &str) -> Result<()> {
+        self.batch_execute(sql).map_err(|e| e.into())
+    }
+
+    fn copy_in(
+        &mut self,
+        query: &str,
+        data: &mut dyn FnMut(&mut dyn Write) -> Result<()>,
+    ) -> Result<()> {
+        let mut writer = Transaction::copy_in(self, query)?;
+        data(&mut writer)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn load<R: BufRead, T: LoadTarget>(mut reader: R, target: &mut T) -> Result<()> {
+    let mut sql = String::new();
+    let mut line = vec![];
+
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if is_copy_from_stdin(&line) {
+            execute_sql(target, &mut sql)?;
+            let query = String::from_utf8(line.clone())?;
+            target.copy_in(query.trim_end(), &mut |w| copy_rows(&mut reader, w))?;
+        } else if !line.starts_with(b"\\") {
+            // Lines beginning with backslash are `psql` meta-commands, we just skip them
+            sql.push_str(std::str::from_utf8(&line)?);
+        }
+        line.clear();
+    }
+
+    execute_sql(target, &mut sql)
+}
+
+fn copy_rows<R: BufRead>(reader: &mut R, writer: &mut dyn Write) -> Result<()> {
+    let mut row = vec![];
+    while reader.read_until(b'\n', &mut row)? > 0 {
+        if trim_newline(&row) == COPY_END {
+            return Ok(());
+        }
+        writer.write_all(&row)?;
+        row.clear();
+    }
+
+    Err(anyhow!("Unexpected end of COPY data"))
+}
+
+fn execute_sql<T: LoadTarget>(target: &mut T, sql: &mut String) -> Result<()> {
+    if !sql.trim().is_empty() {
+        target.execute(sql)?;
+    }
+    sql.clear();
+
+    Ok(())
+}
+
+fn is_copy_from_stdin(line: &[u8]) -> bool {
+    line.starts_with(b"COPY ") && trim_newline(line).ends_with(b" FROM STDIN;")
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl ChannelReader {
+    fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            chunk: vec![],
+            position: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                // All senders are dropped, so it is the end of the stream
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.position);
+        buf[..len].copy_from_slice(&self.chunk[self.position..self.position + len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        Execute(String),
+        Copy(String, String),
+    }
+
+    #[derive(Default)]
+    struct TestTarget {
+        calls: Vec<Call>,
+    }
+
+    impl LoadTarget for TestTarget {
+        fn execute(&mut self, sql: &str) -> Result<()> {
+            self.calls.push(Call::Execute(sql.to_string()));
+            Ok(())
+        }
+
+        fn copy_in(
+            &mut self,
+            query: &str,
+            data: &mut dyn FnMut(&mut dyn Write) -> Result<()>,
+        ) -> Result<()> {
+            let mut rows = vec![];
+            data(&mut rows)?;
+            self.calls
+                .push(Call::Copy(query.to_string(), String::from_utf8(rows)?));
+            Ok(())
+        }
+    }
+
+    fn load_str(dump: &str) -> Result<Vec<Call>> {
+        let mut target = TestTarget::default();
+        load(dump.as_bytes(), &mut target)?;
+        Ok(target.calls)
+    }
+
+    #[test]
+    fn schema_and_data() {
+        let dump = "SET statement_timeout = 0;\n\
+                    \\restrict abc\n\
+                    CREATE TABLE public.users (\n    id integer\n);\n\
+                    \n---\n--- Start dumping data\n---\n\
+                    \nCOPY \"public\".\"users\"(\"id\", \"name\") FROM STDIN;\n\
+                    1\tJohn\n\
+                    2\tMulti\\nline\n\
+                    \\.\n\
+                    \nSELECT pg_catalog.setval('public.users_id_seq', 2, true);\n\
+                    \nCOPY \"public\".\"empty\"(\"id\") FROM STDIN;\n\
+                    \\.\n\
+                    CREATE INDEX users_id ON public.users (id);\n";
+
+        assert_eq!(
+            load_str(dump).unwrap(),
+            vec![
+                Call::Execute(
+                    "SET statement_timeout = 0;\n\
+                     CREATE TABLE public.users (\n    id integer\n);\n\
+                     \n---\n--- Start dumping data\n---\n\n"
+                        .to_string()
+                ),
+                Call::Copy(
+                    "COPY \"public\".\"users\"(\"id\", \"name\") FROM STDIN;".to_string(),
+                    "1\tJohn\n2\tMulti\\nline\n".to_string()
+                ),
+                Call::Execute(
+                    "\nSELECT pg_catalog.setval('public.users_id_seq', 2, true);\n\n".to_string()
+                ),
+                Call::Copy(
+                    "COPY \"public\".\"empty\"(\"id\") FROM STDIN;".to_string(),
+                    String::new()
+                ),
+                Call::Execute("CREATE INDEX users_id ON public.users (id);\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_whitespace() {
+        assert_eq!(load_str("\n\n  \n").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn unfinished_copy() {
+        let dump = "COPY \"public\".\"users\"(\"id\") FROM STDIN;\n1\n2\n";
+        assert!(load_str(dump).is_err());
+    }
+
+    #[test]
+    fn channel_reader() {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        sender.send(b"first line\nsec".to_vec()).unwrap();
+        sender.send(vec![]).unwrap();
+        sender.send(b"ond line\n".to_vec()).unwrap();
+        drop(sender);
+
+        let lines: Vec<_> = BufReader::new(ChannelReader::new(receiver))
+            .lines()
+            .map(|l| l.unwrap())
+            .collect();
+        assert_eq!(lines, vec!["first line", "second line"]);
+    }
+}
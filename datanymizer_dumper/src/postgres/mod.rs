@@ -1,15 +1,25 @@
 use crate::SchemaInspector;
 
+pub mod anonymizer;
+pub mod binary_copy;
 pub mod column;
 pub mod connector;
+pub mod dump_transformer;
 pub mod dumper;
+pub mod escaper;
 pub mod foreign_key;
+pub mod loader;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod relation;
 pub mod row;
 pub mod schema_inspector;
+pub mod subset;
 pub mod table;
 
-mod escaper;
+mod large_objects;
 mod query_wrapper;
 mod sequence;
 
+pub use binary_copy::CopyFormat;
 pub use postgres::IsolationLevel;
@@ -0,0 +1,447 @@
+//! Parquet files from the data stage of `PgDumper` (one file per table, `<schema>.<table>.parquet`).
+//! Column types are derived from `PgColumn` types (see `arrow_type`), values of other types
+//! (e.g., `numeric`, `uuid` or `jsonb`) are stored as strings.
+
+use super::{column::PgColumn, dump_transformer::parse_copy_header, table::PgTable};
+use crate::{
+    files::parquet::{properties, typed_array},
+    ColumnData, Table,
+};
+use anyhow::{anyhow, Result};
+use arrow_array::RecordBatch;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use postgres::types::Type;
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// Rows are written to files in batches of this size
+const BATCH_ROWS: usize = 8192;
+
+const NULL: &str = "\\N";
+
+/// The writer for `PgDumper` that converts `COPY` blocks of the dump to Parquet files
+/// (other SQL is ignored, so it should be used for the data stage only).
+/// Call `finish` after the dump to check that the last block is completed.
+pub struct ParquetTarget {
+    dir: PathBuf,
+    /// Table full name -> column name -> type
+    types: HashMap<String, HashMap<String, DataType>>,
+    /// The unfinished line
+    buffer: Vec<u8>,
+    table: Option<ParquetTable>,
+}
+
+struct ParquetTable {
+    name: String,
+    schema: Arc<Schema>,
+    values: Vec<Vec<Option<String>>>,
+    writer: ArrowWriter<File>,
+}
+
+impl ParquetTarget {
+    /// Creates the directory (if it doesn't exist). Column types are taken from `tables`.
+    pub fn new<P: Into<PathBuf>>(dir: P, tables: &[PgTable]) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let types = tables
+            .iter()
+            .map(|t| {
+                let columns = t
+                    .columns
+                    .iter()
+                    .map(|c| (c.name.clone(), arrow_type(c)))
+                    .collect();
+                (t.get_full_name(), columns)
+            })
+            .collect();
+
+        Ok(Self {
+            dir,
+            types,
+            buffer: vec![],
+            table: None,
+        })
+    }
+
+    /// Checks that the dump is completed
+    pub fn finish(self) -> Result<()> {
+        match self.table {
+            Some(table) => Err(anyhow!("Unfinished COPY block for {}", table.name)),
+            None if !self.buffer.is_empty() => Err(anyhow!("Unfinished line at the end of dump")),
+            None => Ok(()),
+        }
+    }
+
+    fn line(&mut self, line: &[u8]) -> Result<()> {
+        match self.table.as_mut() {
+            Some(_) if line == b"\\." => {
+                if let Some(mut table) = self.table.take() {
+                    table.write_batch()?;
+                    table.writer.close()?;
+                }
+            }
+            Some(table) => {
+                let line = std::str::from_utf8(line)
+                    .map_err(|e| anyhow!("Invalid UTF-8 in the table {}: {}", table.name, e))?;
+                table.push_row(line)?;
+            }
+            None => {
+                if let Some(header) = line.strip_prefix(b"COPY ") {
+                    self.table = Some(self.open(&String::from_utf8_lossy(header))?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn open(&self, header: &str) -> Result<ParquetTable> {
+        let (names, columns) = match parse_copy_header(header) {
+            Some((names, Some(columns))) => (names, columns),
+            _ => return Err(anyhow!("Invalid COPY statement: COPY {}", header)),
+        };
+        let name = names.join(".");
+        let types = self.types.get(&name);
+
+        let fields: Vec<Field> = columns
+            .iter()
+            .map(|c| {
+                let data_type = types
+                    .and_then(|t| t.get(c))
+                    .cloned()
+                    .unwrap_or(DataType::Utf8);
+                Field::new(c, data_type, true)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        let file = File::create(self.dir.join(file_name(&names)))?;
+        Ok(ParquetTable {
+            name,
+            values: vec![vec![]; columns.len()],
+            writer: ArrowWriter::try_new(file, schema.clone(), Some(properties()))?,
+            schema,
+        })
+    }
+}
+
+impl Write for ParquetTarget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(i) = memchr::memchr(b'\n', rest) {
+            let result = if self.buffer.is_empty() {
+                self.line(&rest[..i])
+            } else {
+                self.buffer.extend_from_slice(&rest[..i]);
+                let line = std::mem::take(&mut self.buffer);
+                self.line(&line)
+            };
+            result.map_err(io::Error::other)?;
+            rest = &rest[i + 1..];
+        }
+        self.buffer.extend_from_slice(rest);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ParquetTable {
+    fn push_row(&mut self, line: &str) -> Result<()> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != self.values.len() {
+            return Err(anyhow!(
+                "Wrong number of values in the table {}: {} (expected {})",
+                self.name,
+                fields.len(),
+                self.values.len()
+            ));
+        }
+        let types = self.schema.fields().iter().map(|f| f.data_type());
+        for ((column, data_type), field) in self.values.iter_mut().zip(types).zip(fields) {
+            let is_null = field == NULL || (is_temporal(data_type) && is_out_of_range(field));
+            column.push((!is_null).then(|| unescape(field)));
+        }
+
+        if self.values[0].len() >= BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        if self.values.first().is_none_or(|v| v.is_empty()) {
+            return Ok(());
+        }
+
+        let mut arrays = Vec::with_capacity(self.values.len());
+        for (field, values) in self.schema.fields().iter().zip(self.values.iter_mut()) {
+            let array = typed_array(values, field.data_type()).map_err(|e| {
+                anyhow!(
+                    "Invalid value of the column {} in the table {}: {}",
+                    field.name(),
+                    self.name,
+                    e
+                )
+            })?;
+            arrays.push(array);
+            values.clear();
+        }
+        self.writer
+            .write(&RecordBatch::try_new(self.schema.clone(), arrays)?)?;
+        Ok(())
+    }
+}
+
+/// The Arrow type for values of the column (`Utf8` for types without a lossless mapping)
+pub fn arrow_type(column: &PgColumn) -> DataType {
+    match column.inner_kind() {
+        Some(Type::BOOL) => DataType::Boolean,
+        Some(Type::INT2) => DataType::Int16,
+        Some(Type::INT4) => DataType::Int32,
+        Some(Type::INT8) => DataType::Int64,
+        Some(Type::FLOAT4) => DataType::Float32,
+        Some(Type::FLOAT8) => DataType::Float64,
+        Some(Type::DATE) => DataType::Date32,
+        Some(Type::TIME) => DataType::Time64(TimeUnit::Microsecond),
+        Some(Type::TIMESTAMP) => DataType::Timestamp(TimeUnit::Microsecond, None),
+        Some(Type::TIMESTAMPTZ) => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        Some(Type::BYTEA) => DataType::Binary,
+        _ => DataType::Utf8,
+    }
+}
+
+fn is_temporal(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Date32 | DataType::Timestamp(_, _))
+}
+
+// Dates and timestamps beyond the years 1-9999 (`infinity`, `-infinity`, BC values
+// and years with more than 4 digits) are written as NULL
+fn is_out_of_range(value: &str) -> bool {
+    value == "infinity"
+        || value == "-infinity"
+        || value.ends_with(" BC")
+        || value.bytes().take(5).filter(u8::is_ascii_digit).count() == 5
+}
+
+// Names can contain any characters (e.g. `../x` or `a/b`), so ASCII characters other than
+// letters, digits, `_` and `-` are percent-encoded (the file is always in the target directory,
+// and different tables never share a file)
+fn file_name<T: AsRef<str>>(names: &[T]) -> String {
+    let mut file_name = String::new();
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            file_name.push('.');
+        }
+        for c in name.as_ref().chars() {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') || !c.is_ascii() {
+                file_name.push(c);
+            } else {
+                let _ = write!(file_name, "%{:02X}", c as u8);
+            }
+        }
+    }
+    file_name.push_str(".parquet");
+    file_name
+}
+
+// Reverses the escaping of the text COPY format
+fn unescape(field: &str) -> String {
+    if !field.contains('\\') {
+        return field.to_string();
+    }
+
+    let mut s = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => s.push('\x08'),
+            Some('f') => s.push('\x0C'),
+            Some('n') => s.push('\n'),
+            Some('r') => s.push('\r'),
+            Some('t') => s.push('\t'),
+            Some('v') => s.push('\x0B'),
+            Some(c) => s.push(c),
+            None => s.push('\\'),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{
+        cast::AsArray,
+        types::{Date32Type, Int32Type, TimestampMicrosecondType},
+        Array,
+    };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn column(position: i32, name: &str, ty: Type) -> PgColumn {
+        PgColumn {
+            position,
+            name: name.to_string(),
+            data_type: String::new(),
+            inner_type: Some(ty.oid()),
+            not_null: false,
+        }
+    }
+
+    fn tables() -> Vec<PgTable> {
+        let mut table = PgTable::new("users".to_string(), "public".to_string());
+        table.set_columns(vec![
+            column(1, "id", Type::INT4),
+            column(2, "email", Type::TEXT),
+            column(3, "data", Type::BYTEA),
+            column(4, "created_at", Type::TIMESTAMPTZ),
+            column(5, "score", Type::NUMERIC),
+        ]);
+        vec![table]
+    }
+
+    fn read(path: PathBuf) -> RecordBatch {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        batches[0].clone()
+    }
+
+    #[test]
+    fn write_tables() {
+        let dir = std::env::temp_dir().join("datanymizer_parquet_target");
+        let mut target = ParquetTarget::new(&dir, &tables()).unwrap();
+        let dump = "\n---\n--- Dump table: public.users\n---\n\n\
+                    COPY \"public\".\"users\"(\"id\", \"email\", \"data\", \"created_at\", \"score\") FROM STDIN;\n\
+                    1\ta\\tb@example.com\t\\\\x0a0b\t2020-01-02 03:04:05+00\t1.50\n\
+                    2\t\\N\t\\N\t2020-01-02 06:04:05+03\t\\N\n\
+                    \\.\n\
+                    SELECT pg_catalog.setval('public.users_id_seq', 2, true);\n\
+                    \n---\n--- Dump table: public.other\n---\n\n\
+                    COPY \"public\".\"other\"(\"name\") FROM STDIN;\n\
+                    \\.\n";
+        // Writes are split in the middle of lines
+        for chunk in dump.as_bytes().chunks(7) {
+            target.write_all(chunk).unwrap();
+        }
+        target.finish().unwrap();
+
+        let batch = read(dir.join("public.users.parquet"));
+        let ids = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ids.values(), &[1, 2]);
+        let emails = batch.column(1).as_string::<i32>();
+        assert_eq!(emails.value(0), "a\tb@example.com");
+        assert!(emails.is_null(1));
+        let data = batch.column(2).as_binary::<i32>();
+        assert_eq!(data.value(0), b"\x0a\x0b");
+        assert!(data.is_null(1));
+        let created = batch.column(3).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(created.value(0), 1_577_934_245_000_000);
+        assert_eq!(created.value(1), 1_577_934_245_000_000);
+        assert_eq!(batch.column(4).as_string::<i32>().value(0), "1.50");
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(
+            File::open(dir.join("public.other.parquet")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 0);
+        assert_eq!(reader.schema().field(0).data_type(), &DataType::Utf8);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let dir = std::env::temp_dir().join("datanymizer_parquet_target_errors");
+        let header = "COPY \"public\".\"users\"(\"id\") FROM STDIN;\n";
+
+        let mut target = ParquetTarget::new(&dir, &tables()).unwrap();
+        target.write_all(header.as_bytes()).unwrap();
+        target.write_all(b"1\n").unwrap();
+        assert!(target.finish().is_err());
+
+        let mut target = ParquetTarget::new(&dir, &tables()).unwrap();
+        target.write_all(header.as_bytes()).unwrap();
+        assert!(target.write_all(b"1\t2\n").is_err());
+
+        let mut target = ParquetTarget::new(&dir, &tables()).unwrap();
+        target.write_all(header.as_bytes()).unwrap();
+        assert!(target.write_all(b"abc\n\\.\n").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        let dir = std::env::temp_dir().join("datanymizer_parquet_target_out_of_range");
+        let mut table = PgTable::new("events".to_string(), "public".to_string());
+        table.set_columns(vec![
+            column(1, "day", Type::DATE),
+            column(2, "at", Type::TIMESTAMP),
+            column(3, "note", Type::TEXT),
+        ]);
+        let mut target = ParquetTarget::new(&dir, &[table]).unwrap();
+        target
+            .write_all(
+                b"COPY \"public\".\"events\"(\"day\", \"at\", \"note\") FROM STDIN;\n\
+                  infinity\t-infinity\tinfinity\n\
+                  0044-03-15 BC\t0044-03-15 12:00:00 BC\tBC\n\
+                  10000-01-01\t10000-01-01 00:00:00\t10000\n\
+                  2020-01-02\t2020-01-02 03:04:05\t\\N\n\
+                  \\.\n",
+            )
+            .unwrap();
+        target.finish().unwrap();
+
+        let batch = read(dir.join("public.events.parquet"));
+        for i in 0..3 {
+            assert!(batch.column(0).is_null(i));
+            assert!(batch.column(1).is_null(i));
+            assert!(batch.column(2).is_valid(i));
+        }
+        assert_eq!(batch.column(0).as_primitive::<Date32Type>().value(3), 18263);
+        assert_eq!(
+            batch
+                .column(1)
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(3),
+            1_577_934_245_000_000
+        );
+        assert!(batch.column(2).is_null(3));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name(&["public", "users"]), "public.users.parquet");
+        assert_eq!(file_name(&["..", "x"]), "%2E%2E.x.parquet");
+        assert_eq!(file_name(&["a/b", "c.d"]), "a%2Fb.c%2Ed.parquet");
+        assert_eq!(file_name(&["public", "50%"]), "public.50%25.parquet");
+        assert_eq!(file_name(&["public", "заказы"]), "public.заказы.parquet");
+    }
+
+    #[test]
+    fn unescape_values() {
+        assert_eq!(unescape("plain"), "plain");
+        assert_eq!(unescape("a\\tb\\nc\\\\d"), "a\tb\nc\\d");
+        assert_eq!(unescape("\\\\x0a"), "\\x0a");
+    }
+}
@@ -29,6 +29,20 @@ pub fn copy_out<T>(&mut self, query: &T) -> Result<CopyOutReader<'_>, postgres::
         }
     }
 
+    pub fn query<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, postgres::Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        match self {
+            Self::WithTransaction(t) => t.query(query, params),
+            Self::WithoutTransaction(c) => c.query(query, params),
+        }
+    }
+
     pub fn query_one<T>(
         &mut self,
         query: &T,
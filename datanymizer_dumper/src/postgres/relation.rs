@@ -0,0 +1,77 @@
+use super::column::quote_identifier;
+use postgres::Row as PostgresRow;
+
+/// Kind of a relation (`pg_class.relkind`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    Table,
+    PartitionedTable,
+    View,
+    MaterializedView,
+    ForeignTable,
+    Other,
+}
+
+impl RelationKind {
+    pub fn from_relkind(relkind: i8) -> Self {
+        match relkind as u8 {
+            b'r' => Self::Table,
+            b'p' => Self::PartitionedTable,
+            b'v' => Self::View,
+            b'm' => Self::MaterializedView,
+            b'f' => Self::ForeignTable,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Table-like relation (a table, a view, a foreign table, etc.)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub schemaname: String,
+    pub name: String,
+    pub kind: RelationKind,
+}
+
+impl Relation {
+    pub fn get_full_name(&self) -> String {
+        format!("{}.{}", self.schemaname, self.name)
+    }
+
+    pub fn quoted_full_name(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&self.schemaname),
+            quote_identifier(&self.name)
+        )
+    }
+}
+
+impl From<PostgresRow> for Relation {
+    fn from(row: PostgresRow) -> Self {
+        Self {
+            schemaname: row.get("schemaname"),
+            name: row.get("name"),
+            kind: RelationKind::from_relkind(row.get("relkind")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_relkind() {
+        assert_eq!(RelationKind::from_relkind(b'r' as i8), RelationKind::Table);
+        assert_eq!(
+            RelationKind::from_relkind(b'm' as i8),
+            RelationKind::MaterializedView
+        );
+        assert_eq!(
+            RelationKind::from_relkind(b'f' as i8),
+            RelationKind::ForeignTable
+        );
+        assert_eq!(RelationKind::from_relkind(b'i' as i8), RelationKind::Other);
+    }
+}
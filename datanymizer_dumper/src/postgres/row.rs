@@ -3,7 +3,7 @@
 use anyhow::Result;
 use datanymizer_engine::Engine;
 use postgres::types::Type;
-use std::{borrow::Cow, char};
+use std::{borrow::Cow, collections::HashMap, io::Write, ops::Range};
 
 #[derive(Debug)]
 pub struct PgRow<T>
@@ -28,23 +28,110 @@ pub fn from_string_row(source: String, parent_table: T) -> Self {
     /// Applies the transform engine to every column in the row
     /// Returns a new StringRecord for store in the dump
     pub fn transform(&self, engine: &Engine, cfg_tbl_name: &str) -> Result<String> {
-        let split_char: char = char::from_u32(0x0009).unwrap();
-        let values: Vec<_> = self.source.split(split_char).collect();
-        let mut transformed_values = engine.process_row(
-            String::from(cfg_tbl_name),
-            self.table.get_column_indexes(),
-            &values,
-        )?;
-        for v in &mut transformed_values {
-            if let Cow::Owned(ref mut s) = v {
-                escaper::replace_chars(s);
+        let mut transformer = RowTransformer::new(engine, &self.table, cfg_tbl_name);
+        let mut buf = Vec::with_capacity(self.source.len());
+        transformer.write_line(&self.source, &mut buf)?;
+
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+/// Transforms rows of one table (in the text COPY format) and writes them.
+/// Values of untouched columns are not copied: byte ranges between transformed values
+/// are written straight from the source line.
+pub struct RowTransformer<'a> {
+    engine: &'a Engine,
+    cfg_tbl_name: &'a str,
+    column_indexes: &'a HashMap<String, usize>,
+    // Field ranges of the current line (reused between rows)
+    ranges: Vec<Range<usize>>,
+    // The allocation for field values (it's empty between rows)
+    values: Vec<&'static str>,
+}
+
+impl<'a> RowTransformer<'a> {
+    pub fn new<T: Table<Type>>(engine: &'a Engine, table: &'a T, cfg_tbl_name: &'a str) -> Self {
+        Self::with_column_indexes(engine, table.get_column_indexes(), cfg_tbl_name)
+    }
+
+    /// For rows without a table (e.g. from dump files)
+    pub fn with_column_indexes(
+        engine: &'a Engine,
+        column_indexes: &'a HashMap<String, usize>,
+        cfg_tbl_name: &'a str,
+    ) -> Self {
+        Self {
+            engine,
+            cfg_tbl_name,
+            ranges: Vec::with_capacity(column_indexes.len()),
+            values: Vec::with_capacity(column_indexes.len()),
+            column_indexes,
+        }
+    }
+
+    /// Transforms a line of the text COPY format (without the trailing newline)
+    pub fn write_line<W: Write>(&mut self, line: &str, writer: &mut W) -> Result<()> {
+        self.ranges.clear();
+        let mut start = 0;
+        for tab in memchr::memchr_iter(b'\t', line.as_bytes()) {
+            self.ranges.push(start..tab);
+            start = tab + 1;
+        }
+        self.ranges.push(start..line.len());
+
+        let mut values = reuse(std::mem::take(&mut self.values));
+        values.extend(self.ranges.iter().map(|r| &line[r.clone()]));
+        let transformed =
+            self.engine
+                .process_row(self.cfg_tbl_name, self.column_indexes, &values)?;
+
+        let mut written = 0;
+        for (i, value) in transformed.into_iter().enumerate() {
+            if let Cow::Owned(mut value) = value {
+                let range = &self.ranges[i];
+                writer.write_all(&line.as_bytes()[written..range.start])?;
+                escaper::replace_chars(&mut value);
+                writer.write_all(value.as_bytes())?;
+                written = range.end;
+            }
+        }
+        writer.write_all(&line.as_bytes()[written..])?;
+        self.values = reuse(values);
+
+        Ok(())
+    }
+
+    /// Transforms already split values (in the text COPY format) and writes them as a line
+    /// (without the trailing newline)
+    pub fn write_values<W: Write>(&mut self, values: &[&str], writer: &mut W) -> Result<()> {
+        let transformed =
+            self.engine
+                .process_row(self.cfg_tbl_name, self.column_indexes, values)?;
+
+        for (i, value) in transformed.into_iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b"\t")?;
+            }
+            match value {
+                Cow::Owned(mut value) => {
+                    escaper::replace_chars(&mut value);
+                    writer.write_all(value.as_bytes())?;
+                }
+                Cow::Borrowed(value) => writer.write_all(value.as_bytes())?,
             }
         }
 
-        Ok(transformed_values.join("\t"))
+        Ok(())
     }
 }
 
+// Reuses the allocation of an emptied vector for references with another lifetime
+// (collecting into the same layout is done in place)
+fn reuse<'b>(mut values: Vec<&str>) -> Vec<&'b str> {
+    values.clear();
+    values.into_iter().map(|_| "").collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,24 +166,28 @@ fn transform() {
             name: String::from("first_name"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col2 = PgColumn {
             position: 2,
             name: String::from("middle_name"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col3 = PgColumn {
             position: 3,
             name: String::from("last_name"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col4 = PgColumn {
             position: 4,
             name: String::from("comment"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
 
         table.set_columns(vec![col1, col2, col3, col4]);
@@ -107,4 +198,73 @@ fn transform() {
             "First\tMiddle\tLast\tMulti\\nline\\n"
         );
     }
+
+    fn table(names: &[&str]) -> PgTable {
+        let mut table = PgTable::new("table_name".to_string(), "public".to_string());
+        table.set_columns(
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| PgColumn {
+                    position: i as i32 + 1,
+                    name: name.to_string(),
+                    data_type: String::new(),
+                    inner_type: Some(0),
+                    not_null: false,
+                })
+                .collect(),
+        );
+        table
+    }
+
+    fn engine() -> Engine {
+        let config = r#"
+          tables:
+            - name: table_name
+              rules:
+                name:
+                  capitalize: ~
+                note:
+                  template:
+                    format: "{{ prev.id }}\t{{ final.name }}"
+              rule_order:
+                - name
+                - note
+        "#;
+        Engine::new(Settings::from_yaml(config).unwrap())
+    }
+
+    #[test]
+    fn write_line() {
+        let table = table(&["id", "name", "email", "note", "ёмкость"]);
+        let engine = engine();
+        let mut transformer = RowTransformer::new(&engine, &table, "table_name");
+
+        let mut buf = vec![];
+        transformer
+            .write_line("1\tивАН\ta@b.c\told\\nnote\t\\N", &mut buf)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "1\tИван\ta@b.c\t1\\tИван\t\\N"
+        );
+
+        // the transformer is reused for the next row
+        let mut buf = vec![];
+        transformer.write_line("2\tpetr\t\t\t", &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "2\tPetr\t\t2\\tPetr\t");
+    }
+
+    #[test]
+    fn write_values() {
+        let table = table(&["id", "name", "email", "note"]);
+        let engine = engine();
+        let mut transformer = RowTransformer::new(&engine, &table, "table_name");
+
+        let mut buf = vec![];
+        transformer
+            .write_values(&["3", "anna", "\\N", ""], &mut buf)
+            .unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "3\tAnna\t\\N\t3\\tAnna");
+    }
 }
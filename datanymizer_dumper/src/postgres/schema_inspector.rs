@@ -1,40 +1,110 @@
 use super::{
-    column::PgColumn, connector, foreign_key::ForeignKey, sequence::PgSequence, table::PgTable,
+    column::PgColumn,
+    connector,
+    foreign_key::{ForeignKey, ForeignKeyGraph},
+    relation::{Relation, RelationKind},
+    sequence::PgSequence,
+    table::PgTable,
     SchemaInspector,
 };
-use crate::Table;
+use crate::{weigh_by_dependencies, Table};
 use anyhow::Result;
 use postgres::types::Type;
+use std::collections::HashMap;
 
-const PG_CATALOG_SCHEMA: &str = "SELECT tablename, schemaname
-                                 FROM pg_catalog.pg_tables
-                                 WHERE schemaname != 'pg_catalog'
-                                 AND schemaname != 'information_schema'";
-
-const TABLE_FOREIGN_KEYS: &str = "SELECT
-                                    tc.table_schema,
-                                    tc.constraint_name,
-                                    tc.table_name,
-                                    kcu.column_name,
-                                    ccu.table_schema AS foreign_table_schema,
-                                    ccu.table_name AS foreign_table_name,
-                                    ccu.column_name AS foreign_column_name
-                                FROM
-                                    information_schema.table_constraints AS tc
-                                    JOIN information_schema.key_column_usage AS kcu
-                                    ON tc.constraint_name = kcu.constraint_name
-                                    AND tc.table_schema = kcu.table_schema
-                                    JOIN information_schema.constraint_column_usage AS ccu
-                                    ON ccu.constraint_name = tc.constraint_name
-                                    AND ccu.table_schema = tc.table_schema
-                                WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_name = $1";
-
-const TABLE_COLUMNS_QUERY: &str = "SELECT cc.column_name, cc.ordinal_position, cc.data_type, pt.oid
-                                   FROM information_schema.columns as cc
-                                   JOIN pg_catalog.pg_type as pt
-                                   ON cc.udt_name = pt.typname
-                                   WHERE cc.table_schema = $1 and cc.table_name = $2
-                                   ORDER BY cc.ordinal_position ASC";
+// Partitioned tables and partitions are listed with their partition parents (the nearest first)
+const PG_CATALOG_SCHEMA: &str = "WITH RECURSIVE partition_ancestors AS (
+                                     SELECT i.inhrelid AS relid, i.inhparent AS parent, 1 AS depth
+                                     FROM pg_catalog.pg_inherits i
+                                     JOIN pg_catalog.pg_class c ON c.oid = i.inhrelid
+                                     WHERE c.relispartition
+                                     UNION ALL
+                                     SELECT a.relid, i.inhparent, a.depth + 1
+                                     FROM partition_ancestors a
+                                     JOIN pg_catalog.pg_inherits i ON i.inhrelid = a.parent
+                                     JOIN pg_catalog.pg_class c ON c.oid = i.inhrelid
+                                     WHERE c.relispartition
+                                 )
+                                 SELECT t.tablename, t.schemaname,
+                                     c.relkind = 'p' AS is_partitioned,
+                                     ARRAY(
+                                         SELECT pn.nspname || '.' || pc.relname
+                                         FROM partition_ancestors a
+                                         JOIN pg_catalog.pg_class pc ON pc.oid = a.parent
+                                         JOIN pg_catalog.pg_namespace pn ON pn.oid = pc.relnamespace
+                                         WHERE a.relid = c.oid
+                                         ORDER BY a.depth
+                                     )::text[] AS partition_parents
+                                 FROM pg_catalog.pg_tables t
+                                 JOIN pg_catalog.pg_namespace n ON n.nspname = t.schemaname
+                                 JOIN pg_catalog.pg_class c
+                                 ON c.relnamespace = n.oid AND c.relname = t.tablename
+                                 WHERE t.schemaname != 'pg_catalog'
+                                 AND t.schemaname != 'information_schema'";
+
+// One row per column of a foreign key (in the order of the constraint columns).
+// Constraints that PostgreSQL clones for partitions of a referenced partitioned table are skipped
+// (they duplicate the parent constraint of the same referencing table).
+macro_rules! foreign_keys_query {
+    () => {
+        "SELECT
+             n.nspname AS table_schema,
+             con.conname AS constraint_name,
+             c.relname AS table_name,
+             a.attname AS column_name,
+             fn.nspname AS foreign_table_schema,
+             fc.relname AS foreign_table_name,
+             fa.attname AS foreign_column_name
+         FROM pg_catalog.pg_constraint con
+         JOIN pg_catalog.pg_class c ON c.oid = con.conrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         JOIN pg_catalog.pg_class fc ON fc.oid = con.confrelid
+         JOIN pg_catalog.pg_namespace fn ON fn.oid = fc.relnamespace
+         CROSS JOIN LATERAL unnest(con.conkey, con.confkey)
+             WITH ORDINALITY AS k(attnum, foreign_attnum, position)
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+         JOIN pg_catalog.pg_attribute fa
+         ON fa.attrelid = con.confrelid AND fa.attnum = k.foreign_attnum
+         LEFT JOIN pg_catalog.pg_constraint pcon ON pcon.oid = con.conparentid
+         WHERE con.contype = 'f'
+         AND (pcon.oid IS NULL OR pcon.conrelid <> con.conrelid)"
+    };
+}
+
+const FOREIGN_KEYS: &str = concat!(
+    foreign_keys_query!(),
+    " ORDER BY table_schema, table_name, constraint_name, k.position"
+);
+
+const TABLE_FOREIGN_KEYS: &str = concat!(
+    foreign_keys_query!(),
+    " AND n.nspname = $1 AND c.relname = $2",
+    " ORDER BY table_schema, table_name, constraint_name, k.position"
+);
+
+const RELATIONS_QUERY: &str = "SELECT n.nspname AS schemaname, c.relname AS name, c.relkind
+                               FROM pg_catalog.pg_class c
+                               JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                               WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                               AND n.nspname != 'pg_catalog'
+                               AND n.nspname != 'information_schema'
+                               AND n.nspname NOT LIKE 'pg_toast%'
+                               ORDER BY n.nspname, c.relname";
+
+// Columns are read from `pg_attribute`, because `information_schema` doesn't list columns of
+// materialized views. Types of domains are replaced with their base types.
+const TABLE_COLUMNS_QUERY: &str = "SELECT a.attname::text AS column_name,
+                                       a.attnum::int AS ordinal_position,
+                                       pg_catalog.format_type(a.atttypid, a.atttypmod) AS data_type,
+                                       CASE WHEN t.typtype = 'd' THEN t.typbasetype ELSE t.oid END AS oid,
+                                       a.attnotnull AS not_null
+                                   FROM pg_catalog.pg_attribute a
+                                   JOIN pg_catalog.pg_class c ON c.oid = a.attrelid
+                                   JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                                   JOIN pg_catalog.pg_type t ON t.oid = a.atttypid
+                                   WHERE n.nspname = $1 AND c.relname = $2
+                                   AND a.attnum > 0 AND NOT a.attisdropped
+                                   ORDER BY a.attnum ASC";
 
 const TABLE_SIZE_QUERY: &str =
     "SELECT
@@ -46,6 +116,13 @@
     INNER JOIN pg_catalog.pg_namespace ON pg_catalog.pg_class.relnamespace = pg_catalog.pg_namespace.oid
     WHERE pg_catalog.pg_class.relname = $1 AND pg_catalog.pg_namespace.nspname = $2";
 
+const TABLE_PRIMARY_KEY_QUERY: &str = "SELECT a.attname::text AS column_name
+                                       FROM pg_catalog.pg_index i
+                                       JOIN pg_catalog.pg_attribute a
+                                       ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+                                       WHERE i.indrelid = $1::text::regclass AND i.indisprimary
+                                       ORDER BY array_position(i.indkey::int2[], a.attnum)";
+
 #[derive(Clone)]
 pub struct PgSchemaInspector;
 
@@ -70,6 +147,9 @@ fn get_tables(&self, connection: &mut Self::Connection) -> Result<Vec<Self::Tabl
                 if let Ok(sequences) = self.get_sequences(connection, &table) {
                     table.set_sequences(sequences);
                 };
+                if let Ok(primary_key) = self.get_primary_key(connection, &table) {
+                    table.set_primary_key(primary_key);
+                };
 
                 match self.get_table_size(connection, &table) {
                     Ok(size) => table.size = size as i64,
@@ -103,17 +183,20 @@ fn get_dependencies(
         connection: &mut Self::Connection,
         table: &Self::Table,
     ) -> Result<Vec<Self::Table>> {
-        let fkeys_iterator = connection
+        let fkeys: Vec<ForeignKey> = connection
             .client
-            .query(TABLE_FOREIGN_KEYS, &[&table.get_name()])?
+            .query(TABLE_FOREIGN_KEYS, &[&table.schemaname, &table.tablename])?
             .into_iter()
-            .map(|row| row.into());
+            .map(|row| row.into())
+            .collect();
+        let graph = ForeignKeyGraph::from_foreign_keys(&fkeys);
 
-        let tables: Vec<Self::Table> = fkeys_iterator
+        let tables: Vec<Self::Table> = graph
+            .dependencies(&table.get_full_name())
+            .into_iter()
             // Table from foreign key
-            .map(|fkey: ForeignKey| {
-                PgTable::new(fkey.foreign_table_name, fkey.foreign_table_schema)
-            })
+            .filter_map(|name| name.split_once('.'))
+            .map(|(schema, name)| PgTable::new(name.to_string(), schema.to_string()))
             // Columns for table
             .map(|mut table| {
                 if let Ok(columns) = self.get_columns(connection, &table) {
@@ -122,6 +205,9 @@ fn get_dependencies(
                 if let Ok(sequences) = self.get_sequences(connection, &table) {
                     table.set_sequences(sequences);
                 };
+                if let Ok(primary_key) = self.get_primary_key(connection, &table) {
+                    table.set_primary_key(primary_key);
+                };
 
                 match self.get_table_size(connection, &table) {
                     Ok(size) => table.size = size as i64,
@@ -134,6 +220,25 @@ fn get_dependencies(
         Ok(tables)
     }
 
+    // Dependencies are taken from the foreign key graph (loaded once for all tables)
+    fn ordered_tables(&self, connection: &mut Self::Connection) -> Vec<(Self::Table, i32)> {
+        let tables = match self.get_tables(connection) {
+            Ok(tables) => tables,
+            Err(_) => return vec![],
+        };
+        let graph = self.get_foreign_key_graph(connection).unwrap_or_default();
+        let tables_by_name: HashMap<String, &PgTable> =
+            tables.iter().map(|t| (t.get_full_name(), t)).collect();
+
+        weigh_by_dependencies(&tables, |table| {
+            graph
+                .dependencies(&table.get_full_name())
+                .into_iter()
+                .filter_map(|name| tables_by_name.get(name).map(|t| (*t).clone()))
+                .collect()
+        })
+    }
+
     /// Get columns for table
     fn get_columns(
         &self,
@@ -172,4 +277,94 @@ pub fn get_sequences(
 
         Ok(sequences)
     }
+
+    /// Get relations (tables, views, foreign tables, etc.) of the given kinds
+    pub fn get_relations(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+        kinds: &[RelationKind],
+    ) -> Result<Vec<Relation>> {
+        let relations = connection
+            .client
+            .query(RELATIONS_QUERY, &[])?
+            .into_iter()
+            .map(Relation::from)
+            .filter(|r| kinds.contains(&r.kind))
+            .collect();
+
+        Ok(relations)
+    }
+
+    /// Get partitions (without columns) with their partition parents
+    pub fn get_partitions(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+    ) -> Result<Vec<PgTable>> {
+        let partitions = connection
+            .client
+            .query(PG_CATALOG_SCHEMA, &[])?
+            .into_iter()
+            .map(PgTable::from)
+            .filter(|t| !t.partition_parents.is_empty())
+            .collect();
+
+        Ok(partitions)
+    }
+
+    /// Get materialized views (as tables with columns) for dumping their rows
+    pub fn get_materialized_views(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+    ) -> Result<Vec<PgTable>> {
+        let mut views = vec![];
+        for relation in self.get_relations(connection, &[RelationKind::MaterializedView])? {
+            let mut view = PgTable::new(relation.name, relation.schemaname);
+            view.is_materialized_view = true;
+            view.set_columns(self.get_columns(connection, &view)?);
+            view.size = self.get_table_size(connection, &view)?;
+            views.push(view);
+        }
+
+        Ok(views)
+    }
+
+    /// Get the graph of all foreign keys in the database
+    pub fn get_foreign_key_graph(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+    ) -> Result<ForeignKeyGraph> {
+        let fkeys = self.get_foreign_keys(connection)?;
+        Ok(ForeignKeyGraph::from_foreign_keys(&fkeys))
+    }
+
+    /// Get all foreign keys in the database (one item per column)
+    pub fn get_foreign_keys(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+    ) -> Result<Vec<ForeignKey>> {
+        let fkeys = connection
+            .client
+            .query(FOREIGN_KEYS, &[])?
+            .into_iter()
+            .map(|row| row.into())
+            .collect();
+
+        Ok(fkeys)
+    }
+
+    /// Get primary key columns (empty if the table has no primary key)
+    pub fn get_primary_key(
+        &self,
+        connection: &mut <Self as SchemaInspector>::Connection,
+        table: &<Self as SchemaInspector>::Table,
+    ) -> Result<Vec<String>> {
+        let columns = connection
+            .client
+            .query(TABLE_PRIMARY_KEY_QUERY, &[&table.quoted_full_name()])?
+            .into_iter()
+            .map(|row| row.get("column_name"))
+            .collect();
+
+        Ok(columns)
+    }
 }
@@ -0,0 +1,378 @@
+use super::{column::quote_identifier, foreign_key::Reference, table::PgTable};
+use crate::Table;
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Subset as SubsetCfg, SubsetRoot};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+const NO_ROWS: &str = "FALSE";
+
+/// Row conditions for a referentially complete slice of the database.
+///
+/// Subsetting starts from the root tables (rows satisfying root conditions) and goes in two passes:
+/// 1. Down: the rows referencing already selected rows are selected (e.g., orders of selected users).
+///    Only tables reached in this pass are used for going down further.
+/// 2. Up: all rows referenced by selected rows are selected too (e.g., products of selected orders),
+///    so every foreign key in the dump points to a dumped row.
+///
+/// Tables that are not connected to the roots by foreign keys are dumped in full.
+/// Conditions are plain SQL (nested `IN` subqueries), so nothing is written to the database.
+#[derive(Debug, Default)]
+pub struct Subset {
+    conditions: HashMap<String, String>,
+    /// Foreign keys that could not be followed (cycles between several tables)
+    pub skipped_references: Vec<Reference>,
+}
+
+impl Subset {
+    pub fn new(cfg: &SubsetCfg, tables: &[PgTable], references: &[Reference]) -> Result<Self> {
+        let names: BTreeSet<String> = tables.iter().map(|t| t.get_full_name()).collect();
+        let references: Vec<&Reference> = references
+            .iter()
+            .filter(|r| names.contains(&r.table) && names.contains(&r.foreign_table))
+            .collect();
+
+        let mut builder = Builder {
+            references,
+            down: HashMap::new(),
+            up: HashMap::new(),
+            in_progress: HashSet::new(),
+            skipped: vec![],
+        };
+
+        let roots = cfg
+            .roots
+            .iter()
+            .map(|root| Ok((find_root(root, tables)?, root.condition.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        builder.go_down(roots);
+
+        let mut conditions = HashMap::new();
+        for name in builder.connected(&names) {
+            let condition = builder
+                .go_up(&name)
+                .0
+                .unwrap_or_else(|| NO_ROWS.to_string());
+            conditions.insert(name, condition);
+        }
+
+        Ok(Self {
+            conditions,
+            skipped_references: builder.skipped,
+        })
+    }
+
+    /// SQL condition for the table rows (`None` if the table is dumped in full)
+    pub fn condition(&self, table: &PgTable) -> Option<&String> {
+        self.conditions.get(&table.get_full_name())
+    }
+}
+
+fn find_root(root: &SubsetRoot, tables: &[PgTable]) -> Result<String> {
+    tables
+        .iter()
+        .find(|t| t.get_full_name() == root.table)
+        .or_else(|| tables.iter().find(|t| t.get_name() == root.table))
+        .map(|t| t.get_full_name())
+        .ok_or_else(|| anyhow!("Subset root table {} is not found", root.table))
+}
+
+struct Builder<'a> {
+    references: Vec<&'a Reference>,
+    /// Conditions from the down pass
+    down: HashMap<String, String>,
+    /// Final conditions (`None` means no rows)
+    up: HashMap<String, Option<String>>,
+    in_progress: HashSet<String>,
+    skipped: Vec<Reference>,
+}
+
+impl<'a> Builder<'a> {
+    fn go_down(&mut self, roots: Vec<(String, String)>) {
+        let mut order: Vec<String> = vec![];
+        let mut queue: VecDeque<String> = VecDeque::new();
+
+        for (name, condition) in roots {
+            let condition = format!("({})", condition);
+            match self.down.get_mut(&name) {
+                Some(c) => *c = format!("{} OR {}", c, condition),
+                None => {
+                    self.down.insert(name.clone(), condition);
+                    order.push(name.clone());
+                    queue.push_back(name);
+                }
+            }
+        }
+        let roots_count = order.len();
+
+        while let Some(parent) = queue.pop_front() {
+            let mut children: Vec<String> = self
+                .references
+                .iter()
+                .filter(|r| r.foreign_table == parent && !r.is_self_reference())
+                .map(|r| r.table.clone())
+                .filter(|t| !order.contains(t))
+                .collect();
+            children.sort();
+            children.dedup();
+
+            for child in children {
+                order.push(child.clone());
+                queue.push_back(child);
+            }
+        }
+
+        // A table condition is built from the tables reached before it
+        for (i, name) in order.iter().enumerate().skip(roots_count) {
+            let terms: Vec<String> = self
+                .references
+                .iter()
+                .filter(|r| &r.table == name && order[..i].contains(&r.foreign_table))
+                .map(|r| {
+                    format!(
+                        "({}) IN (SELECT {} FROM {} WHERE {})",
+                        quote_columns(&r.columns, None),
+                        quote_columns(&r.foreign_columns, None),
+                        quote_table(&r.foreign_table),
+                        self.down[&r.foreign_table]
+                    )
+                })
+                .collect();
+            self.down.insert(name.clone(), terms.join(" OR "));
+        }
+    }
+
+    // Returns the condition and whether it is complete (no foreign keys were skipped because of
+    // cycles). Only complete conditions are cached.
+    fn go_up(&mut self, name: &str) -> (Option<String>, bool) {
+        if let Some(condition) = self.up.get(name) {
+            return (condition.clone(), true);
+        }
+        self.in_progress.insert(name.to_string());
+
+        let mut complete = true;
+        let mut terms: Vec<String> = self.down.get(name).cloned().into_iter().collect();
+        let references: Vec<&Reference> = self
+            .references
+            .iter()
+            .copied()
+            .filter(|r| r.foreign_table == name && !r.is_self_reference())
+            .collect();
+        for r in references {
+            if self.in_progress.contains(&r.table) {
+                if !self.skipped.contains(r) {
+                    self.skipped.push(r.clone());
+                }
+                complete = false;
+                continue;
+            }
+
+            let (condition, ref_complete) = self.go_up(&r.table);
+            complete &= ref_complete;
+            if let Some(condition) = condition {
+                terms.push(format!(
+                    "({}) IN (SELECT {} FROM {} WHERE {})",
+                    quote_columns(&r.foreign_columns, None),
+                    quote_columns(&r.columns, None),
+                    quote_table(&r.table),
+                    condition
+                ));
+            }
+        }
+
+        let condition = if terms.is_empty() {
+            None
+        } else {
+            let condition = terms
+                .iter()
+                .map(|t| format!("({})", t))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            Some(self.with_self_references(name, condition))
+        };
+
+        self.in_progress.remove(name);
+        if complete {
+            self.up.insert(name.to_string(), condition.clone());
+        }
+        (condition, complete)
+    }
+
+    // Rows referenced by selected rows of the same table (e.g., parent categories) are selected
+    // recursively
+    fn with_self_references(&self, name: &str, condition: String) -> String {
+        self.references
+            .iter()
+            .filter(|r| r.table == name && r.is_self_reference())
+            .fold(condition, |condition, r| {
+                let keys = quote_columns(&r.foreign_columns, None);
+                let refs = quote_columns(&r.columns, None);
+                format!(
+                    "({keys}) IN (WITH RECURSIVE subset_rows AS (\
+                     SELECT {keys}, {refs} FROM {table} WHERE {condition} \
+                     UNION SELECT {t_keys}, {t_refs} FROM {table} AS t \
+                     JOIN subset_rows AS r ON ({t_keys}) = ({r_refs})\
+                     ) SELECT {keys} FROM subset_rows)",
+                    table = quote_table(name),
+                    t_keys = quote_columns(&r.foreign_columns, Some("t")),
+                    t_refs = quote_columns(&r.columns, Some("t")),
+                    r_refs = quote_columns(&r.columns, Some("r")),
+                )
+            })
+    }
+
+    // Tables connected with subsetted tables (in any direction)
+    fn connected(&self, names: &BTreeSet<String>) -> Vec<String> {
+        let mut connected: BTreeSet<String> = self.down.keys().cloned().collect();
+        let mut queue: VecDeque<String> = connected.iter().cloned().collect();
+        while let Some(name) = queue.pop_front() {
+            for r in self.references.iter() {
+                let other = if r.table == name {
+                    &r.foreign_table
+                } else if r.foreign_table == name {
+                    &r.table
+                } else {
+                    continue;
+                };
+                if names.contains(other) && connected.insert(other.clone()) {
+                    queue.push_back(other.clone());
+                }
+            }
+        }
+
+        connected.into_iter().collect()
+    }
+}
+
+fn quote_table(full_name: &str) -> String {
+    PgTable::quote_table_name(full_name).unwrap_or_else(|_| full_name.to_string())
+}
+
+fn quote_columns(columns: &[String], alias: Option<&str>) -> String {
+    columns
+        .iter()
+        .map(|c| match alias {
+            Some(alias) => format!("{}.{}", alias, quote_identifier(c)),
+            None => quote_identifier(c),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str) -> PgTable {
+        PgTable::new(name.to_string(), "public".to_string())
+    }
+
+    fn reference(table: &str, column: &str, foreign_table: &str) -> Reference {
+        Reference {
+            table: format!("public.{}", table),
+            columns: vec![column.to_string()],
+            foreign_table: format!("public.{}", foreign_table),
+            foreign_columns: vec!["id".to_string()],
+        }
+    }
+
+    fn cfg(table: &str, condition: &str) -> SubsetCfg {
+        SubsetCfg {
+            roots: vec![SubsetRoot {
+                table: table.to_string(),
+                condition: condition.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn down_and_up() {
+        let tables = vec![
+            table("users"),
+            table("orders"),
+            table("products"),
+            table("reviews"),
+            table("settings"),
+        ];
+        let references = vec![
+            reference("orders", "user_id", "users"),
+            reference("orders", "product_id", "products"),
+            reference("reviews", "product_id", "products"),
+        ];
+
+        let subset = Subset::new(&cfg("users", "id < 1000"), &tables, &references).unwrap();
+
+        // Users referenced by selected orders are selected too (in general, orders can be selected
+        // not only by users)
+        assert_eq!(
+            subset.condition(&tables[0]).unwrap(),
+            "((id < 1000)) OR ((\"id\") IN (SELECT \"user_id\" FROM \"public\".\"orders\" \
+            WHERE ((\"user_id\") IN (SELECT \"id\" FROM \"public\".\"users\" WHERE (id < 1000)))))"
+        );
+        assert_eq!(
+            subset.condition(&tables[1]).unwrap(),
+            "((\"user_id\") IN (SELECT \"id\" FROM \"public\".\"users\" WHERE (id < 1000)))"
+        );
+        assert_eq!(
+            subset.condition(&tables[2]).unwrap(),
+            "((\"id\") IN (SELECT \"product_id\" FROM \"public\".\"orders\" \
+            WHERE ((\"user_id\") IN (SELECT \"id\" FROM \"public\".\"users\" WHERE (id < 1000)))))"
+        );
+        // Reviews are not referenced by orders, and we don't go down from products
+        assert_eq!(subset.condition(&tables[3]).unwrap(), NO_ROWS);
+        // Not connected
+        assert_eq!(subset.condition(&tables[4]), None);
+        assert!(subset.skipped_references.is_empty());
+    }
+
+    #[test]
+    fn root_by_full_name() {
+        let tables = vec![table("users")];
+        let subset = Subset::new(&cfg("public.users", "id = 1"), &tables, &[]).unwrap();
+        assert_eq!(subset.condition(&tables[0]).unwrap(), "((id = 1))");
+
+        assert!(Subset::new(&cfg("other.users", "id = 1"), &tables, &[]).is_err());
+    }
+
+    #[test]
+    fn self_reference() {
+        let tables = vec![table("categories")];
+        let references = vec![reference("categories", "parent_id", "categories")];
+
+        let subset = Subset::new(&cfg("categories", "id = 5"), &tables, &references).unwrap();
+
+        assert_eq!(
+            subset.condition(&tables[0]).unwrap(),
+            "(\"id\") IN (WITH RECURSIVE subset_rows AS (\
+            SELECT \"id\", \"parent_id\" FROM \"public\".\"categories\" WHERE ((id = 5)) \
+            UNION SELECT t.\"id\", t.\"parent_id\" FROM \"public\".\"categories\" AS t \
+            JOIN subset_rows AS r ON (t.\"id\") = (r.\"parent_id\")\
+            ) SELECT \"id\" FROM subset_rows)"
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let tables = vec![table("stores"), table("staff"), table("customers")];
+        let references = vec![
+            reference("stores", "manager_id", "staff"),
+            reference("staff", "store_id", "stores"),
+            reference("customers", "store_id", "stores"),
+        ];
+
+        let subset = Subset::new(&cfg("customers", "id = 1"), &tables, &references).unwrap();
+
+        // Managers of stores of selected customers are selected
+        assert_eq!(
+            subset.condition(&tables[1]).unwrap(),
+            "((\"id\") IN (SELECT \"manager_id\" FROM \"public\".\"stores\" \
+            WHERE ((\"id\") IN (SELECT \"store_id\" FROM \"public\".\"customers\" WHERE ((id = 1))))))"
+        );
+        assert_eq!(
+            subset.skipped_references,
+            vec![
+                reference("staff", "store_id", "stores"),
+                reference("stores", "manager_id", "staff")
+            ]
+        );
+    }
+}
@@ -1,7 +1,13 @@
-use super::{column::PgColumn, row::PgRow, sequence::PgSequence};
-use crate::Table;
+use super::{
+    column::{quote_identifier, PgColumn},
+    row::PgRow,
+    sequence::PgSequence,
+};
+use crate::{column_exclusions, Table};
 use anyhow::{anyhow, Result};
-use datanymizer_engine::{Query as QueryCfg, Table as TableCfg};
+use datanymizer_engine::{
+    Query as QueryCfg, Sample, SampleMethod, Table as TableCfg, Transformers,
+};
 use postgres::{types::Type, Row as PostgresRow};
 use std::{
     collections::HashMap,
@@ -14,6 +20,22 @@ pub struct PgTable {
     pub schemaname: String,
     pub columns: Vec<PgColumn>,
     pub sequences: Vec<PgSequence>,
+    pub primary_key: Vec<String>,
+    /// SQL condition for a referentially complete subset (see `Subset`)
+    pub subset_condition: Option<String>,
+    /// Columns dumped as NULL
+    pub excluded_columns: Vec<String>,
+    /// Columns omitted from the dump (they are already removed from `columns`)
+    pub dropped_columns: Vec<String>,
+    /// Is it a partitioned table (its data is stored in partitions)?
+    pub is_partitioned: bool,
+    /// Full names of partitioned tables this table is a partition of (the nearest parent first)
+    pub partition_parents: Vec<String>,
+    /// Is it a materialized view (its rows are dumped into a table, see `create_query`)?
+    pub is_materialized_view: bool,
+    /// Seed for random samples without a configured seed that are split by
+    /// `transform_condition` (both queries must sample the same rows)
+    pub sample_seed: u32,
     column_indexes: HashMap<String, usize>,
     pub size: i64,
 }
@@ -45,8 +67,16 @@ fn get_full_name(&self) -> String {
         format!("{}.{}", self.schemaname, self.tablename)
     }
 
+    // Partitions are also found by the names of their parents (after their own names)
     fn get_names(&self) -> Vec<String> {
-        vec![self.get_full_name(), self.get_name()]
+        let mut names = vec![self.get_full_name(), self.get_name()];
+        for parent in self.partition_parents.iter() {
+            names.push(parent.clone());
+            if let Some((_, name)) = parent.split_once('.') {
+                names.push(name.to_string());
+            }
+        }
+        names
     }
 
     fn get_columns(&self) -> Vec<Self::Column> {
@@ -73,6 +103,14 @@ pub fn new(tablename: String, schemaname: String) -> Self {
             schemaname,
             columns: vec![],
             sequences: vec![],
+            primary_key: vec![],
+            subset_condition: None,
+            excluded_columns: vec![],
+            dropped_columns: vec![],
+            is_partitioned: false,
+            partition_parents: vec![],
+            is_materialized_view: false,
+            sample_seed: rand::random(),
             column_indexes: HashMap::new(),
             size: 0,
         }
@@ -81,14 +119,29 @@ pub fn new(tablename: String, schemaname: String) -> Self {
     pub fn quote_table_name(name: &str) -> Result<String> {
         let parts: Vec<_> = name.split('.').collect();
         match parts.len() {
-            1 => Ok(format!(r#""{}""#, name)),
-            2 => Ok(format!(r#""{}"."{}""#, parts[0], parts[1])),
+            1 => Ok(quote_identifier(name)),
+            2 => Ok(format!(
+                "{}.{}",
+                quote_identifier(parts[0]),
+                quote_identifier(parts[1])
+            )),
             _ => Err(anyhow!("Invalid table name {}", name)),
         }
     }
 
+    /// The full name with full names of partition parents (filters are applied to all of them)
+    pub fn full_names(&self) -> Vec<String> {
+        let mut names = vec![self.get_full_name()];
+        names.extend(self.partition_parents.iter().cloned());
+        names
+    }
+
     pub fn quoted_full_name(&self) -> String {
-        format!(r#""{}"."{}""#, self.schemaname, self.tablename)
+        format!(
+            "{}.{}",
+            quote_identifier(&self.schemaname),
+            quote_identifier(&self.tablename)
+        )
     }
 
     pub fn set_columns(&mut self, columns: Vec<PgColumn>) {
@@ -107,14 +160,56 @@ pub fn set_sequences(&mut self, sequences: Vec<PgSequence>) {
         self.sequences = sequences;
     }
 
+    pub fn set_primary_key(&mut self, primary_key: Vec<String>) {
+        self.primary_key = primary_key;
+    }
+
+    pub fn set_subset_condition(&mut self, condition: Option<String>) {
+        self.subset_condition = condition;
+    }
+
+    /// Applies `exclude_columns` and `drop_columns` from the table config (see `column_exclusions`).
+    /// Dropped columns are removed from the table columns (and column indexes).
+    pub fn apply_column_exclusions(&mut self, cfg: &TableCfg) -> Result<()> {
+        let (excluded, dropped) = column_exclusions(self, cfg)?;
+        // Excluded columns are dumped as NULL, so the dump couldn't be restored
+        if let Some(column) = self
+            .columns
+            .iter()
+            .find(|c| c.not_null && excluded.contains(&c.name))
+        {
+            return Err(anyhow!(
+                "Column {} in the table {} is NOT NULL, so it can't be excluded (drop it or add a rule instead)",
+                column.name,
+                self.get_full_name()
+            ));
+        }
+
+        if !dropped.is_empty() {
+            let columns = self
+                .columns
+                .iter()
+                .filter(|c| !dropped.contains(&c.name))
+                .cloned()
+                .collect();
+            self.set_columns(columns);
+        }
+        self.excluded_columns = excluded;
+        self.dropped_columns = dropped;
+
+        Ok(())
+    }
+
     pub fn transformed_query_to(
         &self,
         cfg: Option<&TableCfg>,
         already_dumped: u64,
     ) -> Option<String> {
         cfg.and_then(|c| match &c.query {
-            Some(q) => self.query_unless_already_dumped(q, |s| format!("({})", s), already_dumped),
-            None => Some(self.default_query()),
+            Some(q) => {
+                self.query_unless_already_dumped(q, Some(c), |s| format!("({})", s), already_dumped)
+            }
+            None => Some(self.default_query(Some(c))),
         })
     }
 
@@ -126,19 +221,28 @@ pub fn untransformed_query_to(
         match cfg {
             Some(c) => c.query.as_ref().and_then(|q| {
                 if q.transform_condition.is_some() {
-                    self.query_unless_already_dumped(q, |s| format!("NOT ({})", s), already_dumped)
+                    self.query_unless_already_dumped(
+                        q,
+                        None,
+                        |s| format!("NOT ({})", s),
+                        already_dumped,
+                    )
                 } else {
                     None
                 }
             }),
-            None => Some(self.default_query()),
+            None => Some(self.default_query(None)),
         }
     }
 
     pub fn count_of_query_to(&self, cfg: Option<&TableCfg>) -> u64 {
-        let number = self.get_size() as u64;
+        let query = cfg.and_then(|c| c.query.as_ref());
+        let number = match query.and_then(|q| q.sample.as_ref()) {
+            Some(sample) => (self.get_size() as f64 * sample.percent / 100.0).ceil() as u64,
+            None => self.get_size() as u64,
+        };
 
-        cfg.and_then(|c| c.query.as_ref())
+        query
             .and_then(|q| q.limit)
             .and_then(|limit| {
                 if number > limit as u64 {
@@ -158,28 +262,91 @@ pub fn query_from(&self) -> String {
         )
     }
 
+    /// Creates a table with the same columns (materialized views are restored as tables)
+    pub fn create_query(&self) -> String {
+        let mut column_refs: Vec<_> = self.columns.iter().collect();
+        column_refs.sort_by_key(|c| c.position);
+        let columns: Vec<String> = column_refs
+            .iter()
+            .map(|c| format!("{} {}", c.quoted_name(), c.data_type))
+            .collect();
+
+        format!(
+            "CREATE TABLE {} ({});",
+            self.quoted_full_name(),
+            columns.join(", ")
+        )
+    }
+
+    // With a limit, rows are split by the transform condition after limiting,
+    // so the transformed and untransformed queries split the same (limited) set of rows
     fn query_unless_already_dumped(
         &self,
         q: &QueryCfg,
+        transformed: Option<&TableCfg>,
         tr_fmt: fn(s: &String) -> String,
         already_dumped: u64,
     ) -> Option<String> {
-        if q.limit
-            .map_or(false, |limit| limit as u64 <= already_dumped)
-        {
+        let limit = q.limit.map(|limit| limit as u64);
+        if limit.is_some_and(|limit| limit <= already_dumped) {
             return None;
         }
 
-        Some(self.query_with_select(
-            vec![
-                q.dump_condition.as_ref().map(|c| format!("({})", c)),
-                q.transform_condition.as_ref().map(tr_fmt),
-            ],
-            q.limit.map(|limit| limit as u64 - already_dumped),
-        ))
+        let mut cs = vec![
+            self.subset_condition(),
+            q.dump_condition.as_ref().map(|c| format!("({})", c)),
+        ];
+        let split_condition = q.transform_condition.as_ref().map(tr_fmt);
+        let sample = q.sample.clone().map(|mut s| {
+            if split_condition.is_some() && s.method != SampleMethod::Hash && s.seed.is_none() {
+                s.seed = Some(self.sample_seed);
+            }
+            s
+        });
+        Some(match split_condition {
+            Some(split_condition) if limit.is_some() => format!(
+                "COPY (SELECT {} FROM ({}) AS {} WHERE {}) TO STDOUT",
+                self.select_list(transformed),
+                self.select_query(
+                    "*".to_string(),
+                    cs,
+                    sample.as_ref(),
+                    self.order_by(q),
+                    limit
+                ),
+                quote_identifier(&self.tablename),
+                split_condition
+            ),
+            split_condition => {
+                cs.push(split_condition);
+                self.query_with_select(
+                    self.select_list(transformed),
+                    cs,
+                    sample.as_ref(),
+                    self.order_by(q),
+                    limit,
+                )
+            }
+        })
     }
 
-    fn default_query(&self) -> String {
+    // `transformed` is the table config for the transformed query (for SQL expression rules).
+    // Materialized views can't be copied directly (only with a query).
+    fn default_query(&self, transformed: Option<&TableCfg>) -> String {
+        if self.is_materialized_view
+            || self.subset_condition.is_some()
+            || !self.excluded_columns.is_empty()
+            || !Self::sql_expressions(transformed).is_empty()
+        {
+            return self.query_with_select(
+                self.select_list(transformed),
+                vec![self.subset_condition()],
+                None,
+                None,
+                None,
+            );
+        }
+
         format!(
             "COPY {}({}) TO STDOUT",
             self.quoted_full_name(),
@@ -187,15 +354,133 @@ fn default_query(&self) -> String {
         )
     }
 
-    fn query_with_select(&self, cs: Vec<Option<String>>, limit: Option<u64>) -> String {
+    fn subset_condition(&self) -> Option<String> {
+        self.subset_condition.as_ref().map(|c| format!("({})", c))
+    }
+
+    fn query_with_select(
+        &self,
+        select_list: String,
+        cs: Vec<Option<String>>,
+        sample: Option<&Sample>,
+        order_by: Option<String>,
+        limit: Option<u64>,
+    ) -> String {
+        format!(
+            "COPY ({}) TO STDOUT",
+            self.select_query(select_list, cs, sample, order_by, limit)
+        )
+    }
+
+    fn select_query(
+        &self,
+        select_list: String,
+        mut cs: Vec<Option<String>>,
+        sample: Option<&Sample>,
+        order_by: Option<String>,
+        limit: Option<u64>,
+    ) -> String {
+        let tablesample = match sample {
+            Some(s) if s.method == SampleMethod::Hash => {
+                cs.push(Some(self.sql_sample_condition(s)));
+                String::new()
+            }
+            Some(s) => Self::sql_tablesample(s),
+            None => String::new(),
+        };
+
         format!(
-            "COPY (SELECT * FROM {}{}{}) TO STDOUT",
+            "SELECT {} FROM {}{}{}{}{}",
+            select_list,
             self.quoted_full_name(),
+            tablesample,
             Self::sql_conditions(cs),
+            Self::sql_order_by(order_by),
             Self::sql_limit(limit),
         )
     }
 
+    /// SQL expressions of `sql` rules (column name -> expression)
+    pub fn sql_expressions(cfg: Option<&TableCfg>) -> HashMap<&str, &str> {
+        cfg.map(|c| {
+            c.rules
+                .iter()
+                .filter_map(|(name, rule)| match rule {
+                    Transformers::Sql(t) => Some((name.as_str(), t.expression.as_str())),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    // Excluded columns are selected as NULL (their values are not read at all).
+    // Columns with `sql` rules are selected as their expressions (only for transformed queries).
+    fn select_list(&self, transformed: Option<&TableCfg>) -> String {
+        let expressions = Self::sql_expressions(transformed);
+        if self.excluded_columns.is_empty()
+            && self.dropped_columns.is_empty()
+            && expressions.is_empty()
+        {
+            return "*".to_string();
+        }
+
+        let mut column_refs: Vec<_> = self.columns.iter().collect();
+        column_refs.sort_by_key(|c| c.position);
+        column_refs
+            .iter()
+            .map(|c| {
+                if self.excluded_columns.contains(&c.name) {
+                    format!("NULL::{} AS {}", c.data_type, c.quoted_name())
+                } else if let Some(expression) = expressions.get(c.name.as_str()) {
+                    format!("({}) AS {}", expression, c.quoted_name())
+                } else {
+                    c.quoted_name()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // The seed makes the sample repeatable, so the transformed and untransformed queries
+    // split the same rows (without a configured seed they use `sample_seed`)
+    fn sql_tablesample(sample: &Sample) -> String {
+        let method = match sample.method {
+            SampleMethod::System => "SYSTEM",
+            _ => "BERNOULLI",
+        };
+        let repeatable = sample
+            .seed
+            .map_or(String::new(), |seed| format!(" REPEATABLE ({})", seed));
+
+        format!(" TABLESAMPLE {} ({}){}", method, sample.percent, repeatable)
+    }
+
+    // Rows are selected by the first 32 bits of the MD5 hash of the primary key
+    // (or of the whole row if there is no primary key)
+    fn sql_sample_condition(&self, sample: &Sample) -> String {
+        let key = if self.primary_key.is_empty() {
+            format!("ROW({}.*)::text", quote_identifier(&self.tablename))
+        } else {
+            let columns: Vec<String> = self
+                .primary_key
+                .iter()
+                .map(|c| quote_identifier(c))
+                .collect();
+            format!("ROW({})::text", columns.join(", "))
+        };
+        let key = match sample.seed {
+            Some(seed) => format!("{} || ':{}'", key, seed),
+            None => key,
+        };
+        let threshold = (sample.percent / 100.0 * 4_294_967_296.0).round() as u64;
+
+        format!(
+            "('x' || left(md5({}), 8))::bit(32)::bigint < {}",
+            key, threshold
+        )
+    }
+
     fn sql_conditions(cs: Vec<Option<String>>) -> String {
         let conditions: Vec<String> = cs.into_iter().flatten().collect();
         if conditions.is_empty() {
@@ -205,6 +490,32 @@ fn sql_conditions(cs: Vec<Option<String>>) -> String {
         }
     }
 
+    // Without the order, `limit` selects arbitrary rows, so we use the primary key by default.
+    // Limited rows split by the transform condition are selected by both queries, so without
+    // the primary key they are ordered by their physical location.
+    fn order_by(&self, q: &QueryCfg) -> Option<String> {
+        q.order_by.clone().or_else(|| {
+            if q.limit.is_none() {
+                None
+            } else if !self.primary_key.is_empty() {
+                let columns: Vec<String> = self
+                    .primary_key
+                    .iter()
+                    .map(|c| quote_identifier(c))
+                    .collect();
+                Some(columns.join(", "))
+            } else if q.transform_condition.is_some() {
+                Some("tableoid, ctid".to_string())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn sql_order_by(order_by: Option<String>) -> String {
+        order_by.map_or(String::new(), |o| format!(" ORDER BY {}", o))
+    }
+
     fn sql_limit(limit: Option<u64>) -> String {
         limit.map_or(String::new(), |limit| format!(" LIMIT {}", limit))
     }
@@ -212,14 +523,17 @@ fn sql_limit(limit: Option<u64>) -> String {
     fn quoted_columns(&self) -> Vec<String> {
         self.get_columns_names()
             .into_iter()
-            .map(|x| format!("\"{}\"", x))
+            .map(|x| quote_identifier(&x))
             .collect()
     }
 }
 
 impl From<PostgresRow> for PgTable {
     fn from(row: PostgresRow) -> Self {
-        Self::new(row.get("tablename"), row.get("schemaname"))
+        let mut table = Self::new(row.get("tablename"), row.get("schemaname"));
+        table.is_partitioned = row.get("is_partitioned");
+        table.partition_parents = row.get("partition_parents");
+        table
     }
 }
 
@@ -235,6 +549,39 @@ fn table_full_name() {
         assert_eq!(table.get_full_name(), String::from("public.name"));
     }
 
+    #[test]
+    fn names() {
+        let mut table = PgTable::new(String::from("events_2024_01"), String::from("public"));
+        assert_eq!(
+            table.get_names(),
+            vec!["public.events_2024_01", "events_2024_01"]
+        );
+
+        table.partition_parents = vec![
+            String::from("public.events_2024"),
+            String::from("public.events"),
+        ];
+        assert_eq!(
+            table.get_names(),
+            vec![
+                "public.events_2024_01",
+                "events_2024_01",
+                "public.events_2024",
+                "events_2024",
+                "public.events",
+                "events"
+            ]
+        );
+        assert_eq!(
+            table.full_names(),
+            vec![
+                "public.events_2024_01",
+                "public.events_2024",
+                "public.events"
+            ]
+        );
+    }
+
     #[test]
     fn quote_table_name() {
         let name = PgTable::quote_table_name("table").unwrap();
@@ -253,6 +600,63 @@ fn quoted_full_name() {
         assert_eq!(table.quoted_full_name(), r#""public2"."name""#)
     }
 
+    #[test]
+    fn quoted_identifiers() {
+        let mut table = PgTable::new(String::from("na\"me"), String::from("sch\"ema"));
+        table.set_columns(vec![
+            PgColumn {
+                position: 1,
+                name: String::from("i\"d"),
+                data_type: String::from("integer"),
+                inner_type: Some(0),
+                not_null: false,
+            },
+            PgColumn {
+                position: 2,
+                name: String::from("no\"te"),
+                data_type: String::from("text"),
+                inner_type: Some(0),
+                not_null: false,
+            },
+        ]);
+        table.set_primary_key(vec![String::from("i\"d")]);
+        table.excluded_columns = vec![String::from("no\"te")];
+        let cfg = TableCfg {
+            name: String::from("na\"me"),
+            rules: HashMap::new(),
+            rule_order: None,
+            query: Some(QueryCfg {
+                limit: Some(10),
+                sample: None,
+                order_by: None,
+                dump_condition: None,
+                transform_condition: Some(String::from("true")),
+            }),
+            exclude_columns: vec![],
+            drop_columns: vec![],
+        };
+
+        assert_eq!(table.quoted_full_name(), r#""sch""ema"."na""me""#);
+        assert_eq!(
+            PgTable::quote_table_name("sch\"ema.na\"me").unwrap(),
+            r#""sch""ema"."na""me""#
+        );
+        assert_eq!(
+            table.create_query(),
+            r#"CREATE TABLE "sch""ema"."na""me" ("i""d" integer, "no""te" text);"#
+        );
+        assert_eq!(
+            table.query_from(),
+            r#"COPY "sch""ema"."na""me"("i""d", "no""te") FROM STDIN;"#
+        );
+        assert_eq!(
+            table.transformed_query_to(Some(&cfg), 0).unwrap(),
+            "COPY (SELECT \"i\"\"d\", NULL::text AS \"no\"\"te\" \
+            FROM (SELECT * FROM \"sch\"\"ema\".\"na\"\"me\" ORDER BY \"i\"\"d\" LIMIT 10) \
+            AS \"na\"\"me\" WHERE (true)) TO STDOUT"
+        );
+    }
+
     #[test]
     fn set_columns() {
         let mut table = PgTable::new(String::from("name"), String::from("public"));
@@ -262,12 +666,14 @@ fn set_columns() {
             name: String::from("col1"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col2 = PgColumn {
             position: 2,
             name: String::from("col2"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
         let col3 = PgColumn {
             // Column positions in Postgres are not always in sequence
@@ -276,6 +682,7 @@ fn set_columns() {
             name: String::from("col4"),
             data_type: String::new(),
             inner_type: Some(0),
+            not_null: false,
         };
 
         table.set_columns(vec![col1.clone(), col2.clone(), col3.clone()]);
@@ -288,6 +695,37 @@ fn set_columns() {
         assert_eq!(table.column_indexes["col4"], 2);
     }
 
+    #[test]
+    fn materialized_view() {
+        let mut table = PgTable::new(String::from("stats"), String::from("public"));
+        table.is_materialized_view = true;
+        table.set_columns(vec![
+            PgColumn {
+                position: 2,
+                name: String::from("total"),
+                data_type: String::from("numeric(10,2)"),
+                inner_type: Some(0),
+                not_null: false,
+            },
+            PgColumn {
+                position: 1,
+                name: String::from("name"),
+                data_type: String::from("character varying(20)"),
+                inner_type: Some(0),
+                not_null: false,
+            },
+        ]);
+
+        assert_eq!(
+            table.create_query(),
+            r#"CREATE TABLE "public"."stats" ("name" character varying(20), "total" numeric(10,2));"#
+        );
+        assert_eq!(
+            table.untransformed_query_to(None, 0).unwrap(),
+            r#"COPY (SELECT * FROM "public"."stats") TO STDOUT"#
+        );
+    }
+
     mod query_to {
         use super::*;
 
@@ -301,12 +739,14 @@ fn columns() -> Vec<PgColumn> {
                 name: String::from("col1"),
                 data_type: String::new(),
                 inner_type: Some(0),
+                not_null: false,
             };
             let col2 = PgColumn {
                 position: 2,
                 name: String::from("col2"),
                 data_type: String::new(),
                 inner_type: Some(0),
+                not_null: false,
             };
             vec![col1, col2]
         }
@@ -324,6 +764,8 @@ fn cfg(query: Option<QueryCfg>) -> TableCfg {
                 rules: HashMap::new(),
                 rule_order: None,
                 query,
+                exclude_columns: vec![],
+                drop_columns: vec![],
             }
         }
 
@@ -353,6 +795,8 @@ fn no_query() {
         fn only_limit() {
             let cfg = cfg(Some(QueryCfg {
                 limit: Some(100),
+                sample: None,
+                order_by: None,
                 dump_condition: None,
                 transform_condition: None,
             }));
@@ -369,6 +813,8 @@ fn only_limit() {
         fn only_dump_condition() {
             let cfg = cfg(Some(QueryCfg {
                 limit: None,
+                sample: None,
+                order_by: None,
                 dump_condition: Some("col1 = 'value'".to_string()),
                 transform_condition: None,
             }));
@@ -385,6 +831,8 @@ fn only_dump_condition() {
         fn only_transform_condition() {
             let cfg = cfg(Some(QueryCfg {
                 limit: None,
+                sample: None,
+                order_by: None,
                 dump_condition: None,
                 transform_condition: Some("col1 = 'value'".to_string()),
             }));
@@ -404,23 +852,418 @@ fn only_transform_condition() {
         fn all_query_params() {
             let cfg = cfg(Some(QueryCfg {
                 limit: Some(500),
+                sample: None,
+                order_by: None,
                 dump_condition: Some("col1 = 'value'".to_string()),
                 transform_condition: Some("col2 <> 'other_value'".to_string()),
             }));
 
             assert_eq!(
                 table().transformed_query_to(Some(&cfg), 0).unwrap(),
-                "COPY (SELECT * FROM \"public\".\"some_table\" \
-                WHERE (col1 = 'value') AND (col2 <> 'other_value') LIMIT 500) TO STDOUT"
+                "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                WHERE (col1 = 'value') ORDER BY tableoid, ctid LIMIT 500) AS \"some_table\" \
+                WHERE (col2 <> 'other_value')) TO STDOUT"
             );
             assert_eq!(
                 table().untransformed_query_to(Some(&cfg), 0).unwrap(),
-                "COPY (SELECT * FROM \"public\".\"some_table\" \
-                WHERE (col1 = 'value') AND NOT (col2 <> 'other_value') LIMIT 500) TO STDOUT"
+                "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                WHERE (col1 = 'value') ORDER BY tableoid, ctid LIMIT 500) AS \"some_table\" \
+                WHERE NOT (col2 <> 'other_value')) TO STDOUT"
             );
             assert_eq!(table().count_of_query_to(Some(&cfg)), 500);
         }
 
+        #[test]
+        fn subset_condition() {
+            let mut table = table();
+            table.set_subset_condition(Some("col1 IN (SELECT id FROM other)".to_string()));
+
+            assert_eq!(
+                table.untransformed_query_to(None, 0).unwrap(),
+                "COPY (SELECT * FROM \"public\".\"some_table\" \
+                WHERE (col1 IN (SELECT id FROM other))) TO STDOUT"
+            );
+
+            let cfg = cfg(Some(QueryCfg {
+                limit: Some(500),
+                sample: None,
+                order_by: None,
+                dump_condition: Some("col1 = 'value'".to_string()),
+                transform_condition: Some("col2 <> 'other_value'".to_string()),
+            }));
+            assert_eq!(
+                table.transformed_query_to(Some(&cfg), 0).unwrap(),
+                "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                WHERE (col1 IN (SELECT id FROM other)) AND (col1 = 'value') \
+                ORDER BY tableoid, ctid LIMIT 500) AS \"some_table\" \
+                WHERE (col2 <> 'other_value')) TO STDOUT"
+            );
+        }
+
+        mod column_exclusions {
+            use super::*;
+
+            fn cfg_with_columns(exclude: &[&str], drop: &[&str]) -> TableCfg {
+                let mut cfg = cfg(Some(QueryCfg {
+                    limit: Some(10),
+                    sample: None,
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: None,
+                }));
+                cfg.exclude_columns = exclude.iter().map(|c| c.to_string()).collect();
+                cfg.drop_columns = drop.iter().map(|c| c.to_string()).collect();
+                cfg
+            }
+
+            fn table_with_columns() -> PgTable {
+                let mut table = table();
+                let mut columns = columns();
+                columns.push(PgColumn {
+                    position: 3,
+                    name: String::from("col3"),
+                    data_type: String::new(),
+                    inner_type: Some(0),
+                    not_null: false,
+                });
+                for (column, data_type) in columns.iter_mut().zip(["integer", "text", "date"]) {
+                    column.data_type = data_type.to_string();
+                }
+                table.set_columns(columns);
+                table
+            }
+
+            #[test]
+            fn exclude() {
+                let cfg = cfg_with_columns(&["col2"], &[]);
+                let mut table = table_with_columns();
+                table.apply_column_exclusions(&cfg).unwrap();
+
+                assert_eq!(
+                    table.query_from(),
+                    "COPY \"public\".\"some_table\"(\"col1\", \"col2\", \"col3\") FROM STDIN;"
+                );
+                assert_eq!(
+                    table.transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT \"col1\", NULL::text AS \"col2\", \"col3\" \
+                    FROM \"public\".\"some_table\" LIMIT 10) TO STDOUT"
+                );
+                assert_eq!(
+                    table.untransformed_query_to(None, 0).unwrap(),
+                    "COPY (SELECT \"col1\", NULL::text AS \"col2\", \"col3\" \
+                    FROM \"public\".\"some_table\") TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn drop() {
+                let cfg = cfg_with_columns(&["col3"], &["col2"]);
+                let mut table = table_with_columns();
+                table.apply_column_exclusions(&cfg).unwrap();
+
+                assert_eq!(
+                    table.query_from(),
+                    "COPY \"public\".\"some_table\"(\"col1\", \"col3\") FROM STDIN;"
+                );
+                assert_eq!(table.get_column_indexes()["col1"], 0);
+                assert_eq!(table.get_column_indexes()["col3"], 1);
+                assert!(!table.get_column_indexes().contains_key("col2"));
+                assert_eq!(
+                    table.transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT \"col1\", NULL::date AS \"col3\" \
+                    FROM \"public\".\"some_table\" LIMIT 10) TO STDOUT"
+                );
+
+                let cfg = cfg_with_columns(&[], &["col2"]);
+                let mut table = table_with_columns();
+                table.apply_column_exclusions(&cfg).unwrap();
+                assert_eq!(
+                    table.untransformed_query_to(None, 0).unwrap(),
+                    "COPY \"public\".\"some_table\"(\"col1\", \"col3\") TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn invalid() {
+                let mut table = table_with_columns();
+                assert!(table
+                    .apply_column_exclusions(&cfg_with_columns(&["unknown"], &[]))
+                    .is_err());
+
+                let mut cfg = cfg_with_columns(&[], &["col1"]);
+                cfg.rules.insert(
+                    "col1".to_string(),
+                    datanymizer_engine::Transformers::None(
+                        datanymizer_engine::transformers::NoneTransformer,
+                    ),
+                );
+                assert!(table.apply_column_exclusions(&cfg).is_err());
+            }
+
+            #[test]
+            fn not_null() {
+                let mut table = table_with_columns();
+                let mut columns = table.columns.clone();
+                columns[1].not_null = true;
+                table.set_columns(columns);
+
+                assert!(table
+                    .apply_column_exclusions(&cfg_with_columns(&["col2"], &[]))
+                    .is_err());
+                table
+                    .apply_column_exclusions(&cfg_with_columns(&[], &["col2"]))
+                    .unwrap();
+            }
+
+            #[test]
+            fn pattern() {
+                let mut cfg = cfg_with_columns(&["col2", "unknown"], &["other"]);
+                cfg.name = String::from("public.some_*");
+                let mut table = table_with_columns();
+                table.apply_column_exclusions(&cfg).unwrap();
+
+                assert_eq!(table.excluded_columns, vec!["col2"]);
+                assert!(table.dropped_columns.is_empty());
+            }
+        }
+
+        mod sql_rules {
+            use super::*;
+
+            fn cfg_with_sql(query: Option<QueryCfg>) -> TableCfg {
+                let mut cfg = cfg(query);
+                cfg.rules.insert(
+                    "col2".to_string(),
+                    Transformers::Sql(datanymizer_engine::transformers::SqlTransformer {
+                        expression: "md5(col2)".to_string(),
+                    }),
+                );
+                cfg
+            }
+
+            #[test]
+            fn no_query() {
+                let cfg = cfg_with_sql(None);
+
+                assert_eq!(
+                    table().transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT \"col1\", (md5(col2)) AS \"col2\" \
+                    FROM \"public\".\"some_table\") TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn transform_condition() {
+                let cfg = cfg_with_sql(Some(QueryCfg {
+                    limit: None,
+                    sample: None,
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: Some("col1 = 'value'".to_string()),
+                }));
+
+                assert_eq!(
+                    table().transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT \"col1\", (md5(col2)) AS \"col2\" \
+                    FROM \"public\".\"some_table\" WHERE (col1 = 'value')) TO STDOUT"
+                );
+                // Original values for untransformed rows
+                assert_eq!(
+                    table().untransformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" WHERE NOT (col1 = 'value')) TO STDOUT"
+                );
+            }
+        }
+
+        mod order_by {
+            use super::*;
+
+            fn table_with_primary_key() -> PgTable {
+                let mut table = table();
+                table.set_primary_key(vec!["col1".to_string(), "col2".to_string()]);
+                table
+            }
+
+            #[test]
+            fn explicit() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: Some(1000),
+                    sample: None,
+                    order_by: Some("col2 DESC".to_string()),
+                    dump_condition: None,
+                    transform_condition: Some("col1 = 'value'".to_string()),
+                }));
+
+                // The limited (and ordered) rows are split by the transform condition
+                assert_eq!(
+                    table_with_primary_key()
+                        .transformed_query_to(Some(&cfg), 0)
+                        .unwrap(),
+                    "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                    ORDER BY col2 DESC LIMIT 1000) AS \"some_table\" WHERE (col1 = 'value')) TO STDOUT"
+                );
+                assert_eq!(
+                    table_with_primary_key()
+                        .untransformed_query_to(Some(&cfg), 100)
+                        .unwrap(),
+                    "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                    ORDER BY col2 DESC LIMIT 1000) AS \"some_table\" WHERE NOT (col1 = 'value')) TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn primary_key_by_default() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: Some(100),
+                    sample: None,
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: None,
+                }));
+
+                assert_eq!(
+                    table_with_primary_key()
+                        .transformed_query_to(Some(&cfg), 0)
+                        .unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" \
+                    ORDER BY \"col1\", \"col2\" LIMIT 100) TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn no_limit() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: None,
+                    sample: None,
+                    order_by: None,
+                    dump_condition: Some("col1 = 'value'".to_string()),
+                    transform_condition: None,
+                }));
+
+                assert_eq!(
+                    table_with_primary_key()
+                        .transformed_query_to(Some(&cfg), 0)
+                        .unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" \
+                    WHERE (col1 = 'value')) TO STDOUT"
+                );
+            }
+        }
+
+        mod sample {
+            use super::*;
+
+            fn sample(method: SampleMethod, seed: Option<u32>) -> Option<Sample> {
+                Some(Sample {
+                    percent: 5.0,
+                    method,
+                    seed,
+                })
+            }
+
+            #[test]
+            fn bernoulli() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: None,
+                    sample: sample(SampleMethod::Bernoulli, None),
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: None,
+                }));
+
+                assert_eq!(
+                    table().transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" TABLESAMPLE BERNOULLI (5)) TO STDOUT"
+                );
+                assert_eq!(table().count_of_query_to(Some(&cfg)), 50);
+            }
+
+            #[test]
+            fn system_with_conditions() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: Some(20),
+                    sample: sample(SampleMethod::System, Some(42)),
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: Some("col1 = 'value'".to_string()),
+                }));
+
+                assert_eq!(
+                    table().transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                    TABLESAMPLE SYSTEM (5) REPEATABLE (42) ORDER BY tableoid, ctid LIMIT 20) \
+                    AS \"some_table\" \
+                    WHERE (col1 = 'value')) TO STDOUT"
+                );
+                assert_eq!(
+                    table().untransformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                    TABLESAMPLE SYSTEM (5) REPEATABLE (42) ORDER BY tableoid, ctid LIMIT 20) \
+                    AS \"some_table\" \
+                    WHERE NOT (col1 = 'value')) TO STDOUT"
+                );
+                assert_eq!(table().count_of_query_to(Some(&cfg)), 20);
+            }
+
+            #[test]
+            fn split_without_seed() {
+                let cfg = cfg(Some(QueryCfg {
+                    limit: None,
+                    sample: sample(SampleMethod::Bernoulli, None),
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: Some("col1 = 'value'".to_string()),
+                }));
+                let mut table = table();
+                table.sample_seed = 17;
+
+                // Both queries sample the same rows
+                assert_eq!(
+                    table.transformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" \
+                    TABLESAMPLE BERNOULLI (5) REPEATABLE (17) WHERE (col1 = 'value')) TO STDOUT"
+                );
+                assert_eq!(
+                    table.untransformed_query_to(Some(&cfg), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" \
+                    TABLESAMPLE BERNOULLI (5) REPEATABLE (17) WHERE NOT (col1 = 'value')) TO STDOUT"
+                );
+            }
+
+            #[test]
+            fn hash() {
+                let cfg_without_seed = cfg(Some(QueryCfg {
+                    limit: None,
+                    sample: sample(SampleMethod::Hash, None),
+                    order_by: None,
+                    dump_condition: Some("col2 > 0".to_string()),
+                    transform_condition: None,
+                }));
+
+                assert_eq!(
+                    table().transformed_query_to(Some(&cfg_without_seed), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" WHERE (col2 > 0) AND \
+                    ('x' || left(md5(ROW(\"some_table\".*)::text), 8))::bit(32)::bigint < 214748365) TO STDOUT"
+                );
+
+                let cfg_with_seed = cfg(Some(QueryCfg {
+                    limit: None,
+                    sample: sample(SampleMethod::Hash, Some(7)),
+                    order_by: None,
+                    dump_condition: None,
+                    transform_condition: None,
+                }));
+                let mut table = table();
+                table.set_primary_key(vec!["col1".to_string()]);
+
+                assert_eq!(
+                    table.transformed_query_to(Some(&cfg_with_seed), 0).unwrap(),
+                    "COPY (SELECT * FROM \"public\".\"some_table\" WHERE \
+                    ('x' || left(md5(ROW(\"col1\")::text || ':7'), 8))::bit(32)::bigint < 214748365) TO STDOUT"
+                );
+            }
+        }
+
         mod already_dumped {
             use super::*;
 
@@ -428,6 +1271,8 @@ mod already_dumped {
             fn no_limit() {
                 let cfg = cfg(Some(QueryCfg {
                     limit: None,
+                    sample: None,
+                    order_by: None,
                     dump_condition: None,
                     transform_condition: Some("col1 = 'value'".to_string()),
                 }));
@@ -446,17 +1291,18 @@ fn no_limit() {
             fn limit_is_greater() {
                 let cfg = cfg(Some(QueryCfg {
                     limit: Some(150),
+                    sample: None,
+                    order_by: None,
                     dump_condition: None,
                     transform_condition: Some("col1 = 'value'".to_string()),
                 }));
 
-                assert_eq!(
-                    table().transformed_query_to(Some(&cfg), 100).unwrap(),
-                    "COPY (SELECT * FROM \"public\".\"some_table\" WHERE (col1 = 'value') LIMIT 50) TO STDOUT"
-                );
+                // The same limited rows are split (the query doesn't depend on dumped rows)
                 assert_eq!(
                     table().untransformed_query_to(Some(&cfg), 100).unwrap(),
-                    "COPY (SELECT * FROM \"public\".\"some_table\" WHERE NOT (col1 = 'value') LIMIT 50) TO STDOUT"
+                    "COPY (SELECT * FROM (SELECT * FROM \"public\".\"some_table\" \
+                    ORDER BY tableoid, ctid LIMIT 150) \
+                    AS \"some_table\" WHERE NOT (col1 = 'value')) TO STDOUT"
                 );
             }
 
@@ -464,6 +1310,8 @@ fn limit_is_greater() {
             fn limit_is_equal() {
                 let cfg = cfg(Some(QueryCfg {
                     limit: Some(100),
+                    sample: None,
+                    order_by: None,
                     dump_condition: None,
                     transform_condition: Some("col1 = 'value'".to_string()),
                 }));
@@ -476,6 +1324,8 @@ fn limit_is_equal() {
             fn limit_is_lesser() {
                 let cfg = cfg(Some(QueryCfg {
                     limit: Some(99),
+                    sample: None,
+                    order_by: None,
                     dump_condition: None,
                     transform_condition: Some("col1 = 'value'".to_string()),
                 }));
@@ -0,0 +1,55 @@
+use crate::ColumnData;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteColumn {
+    /// Position of column in the dumped column list (starting from 1)
+    pub position: usize,
+    /// Column name
+    pub name: String,
+    /// Declared column type (it can be empty, SQLite values are dynamically typed)
+    pub data_type: String,
+}
+
+impl SqliteColumn {
+    pub fn new(position: usize, name: String, data_type: String) -> Self {
+        Self {
+            position,
+            name,
+            data_type,
+        }
+    }
+
+    pub fn quoted_name(&self) -> String {
+        quote_identifier(&self.name)
+    }
+}
+
+impl ColumnData<String> for SqliteColumn {
+    fn position(&self) -> usize {
+        self.position - 1
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    fn inner_kind(&self) -> Option<String> {
+        Some(self.data_type.clone())
+    }
+}
+
+/// Quotes an identifier with double quotes (double quotes inside are doubled)
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote_identifier("name"), "\"name\"");
+        assert_eq!(quote_identifier("na\"me"), "\"na\"\"me\"");
+    }
+}
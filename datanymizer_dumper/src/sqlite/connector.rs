@@ -0,0 +1,34 @@
+use anyhow::Result;
+use rusqlite::OpenFlags;
+use std::path::PathBuf;
+
+pub struct Connection {
+    pub conn: rusqlite::Connection,
+    pub path: PathBuf,
+}
+
+impl Connection {
+    pub fn new(conn: rusqlite::Connection, path: PathBuf) -> Self {
+        Self { conn, path }
+    }
+}
+
+pub struct Connector {
+    path: PathBuf,
+}
+
+impl Connector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The source database is opened in the read-only mode
+    pub fn connect(&self) -> Result<Connection> {
+        let conn = rusqlite::Connection::open_with_flags(
+            &self.path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        Ok(Connection::new(conn, self.path.clone()))
+    }
+}
@@ -0,0 +1,312 @@
+use super::{
+    connector,
+    literal::{self, Kind},
+    schema_inspector::SqliteSchemaInspector,
+    table::SqliteTable,
+};
+use crate::{indicator::Indicator, sort_tables, Dumper, SchemaInspector, Table};
+use anyhow::{anyhow, Result};
+use datanymizer_engine::{Engine, Settings};
+use rusqlite::Connection;
+use std::{borrow::Cow, io::prelude::*, time::Instant};
+
+/// The writer is flushed after this number of rows (see `SqliteLoader`)
+const FLUSH_ROWS: u64 = 1000;
+
+// Internal and virtual tables are skipped (as in the schema inspector)
+const SCHEMA_TABLES_QUERY: &str = "SELECT name, sql FROM sqlite_master
+                                   WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+                                   AND sql NOT LIKE 'CREATE VIRTUAL TABLE%'
+                                   ORDER BY rowid";
+
+// Automatic indexes have no SQL. Objects are created in the original order,
+// because views can depend on other views.
+const SCHEMA_OBJECTS_QUERY: &str = "SELECT tbl_name, sql FROM sqlite_master
+                                    WHERE type IN ('index', 'trigger', 'view')
+                                    AND sql IS NOT NULL
+                                    ORDER BY rowid";
+
+const SEQUENCE_EXISTS_QUERY: &str =
+    "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence'";
+
+const SEQUENCE_QUERY: &str = "SELECT name, seq FROM sqlite_sequence ORDER BY name";
+
+/// Dumps a SQLite database to SQL text (`sqlite3 .dump` equivalent).
+/// The writer is flushed only at statement boundaries, so the dump can be executed
+/// in a new database with `SqliteLoader`.
+pub struct SqliteDumper<W: Write + Send, I: Indicator + Send> {
+    schema_inspector: SqliteSchemaInspector,
+    engine: Engine,
+    dump_writer: W,
+    indicator: I,
+}
+
+impl<W: 'static + Write + Send, I: 'static + Indicator + Send> SqliteDumper<W, I> {
+    pub fn new(engine: Engine, dump_writer: W, indicator: I) -> Result<Self> {
+        check_settings(&engine.settings)?;
+
+        Ok(Self {
+            engine,
+            dump_writer,
+            indicator,
+            schema_inspector: SqliteSchemaInspector {},
+        })
+    }
+
+    /// Consumes the dumper, returning the underlying writer
+    pub fn into_writer(self) -> W {
+        self.dump_writer
+    }
+
+    fn write_statements(&mut self, statements: Vec<(String, String)>) -> Result<()> {
+        let filter = self.engine.settings.filter.clone();
+        for (table, sql) in statements {
+            if filter.as_ref().is_none_or(|f| f.filter_schema(&table)) {
+                self.dump_writer.write_all(sql.as_bytes())?;
+                self.dump_writer.write_all(b";\n")?;
+            }
+        }
+        self.dump_writer.flush()?;
+        Ok(())
+    }
+
+    fn dump_table(&mut self, table: &SqliteTable, conn: &Connection) -> Result<()> {
+        let settings = self.settings();
+        let started = Instant::now();
+
+        self.write_log(format!("Dump table: {}", &table.get_full_name()))?;
+
+        let cfg = settings.find_table(&table.get_names());
+
+        self.indicator
+            .start_pb(table.count_of_query_to(cfg), &table.get_full_name());
+
+        let prefix = table.insert_prefix();
+        let mut count: u64 = 0;
+        if let Some(cfg) = cfg {
+            if let Some(transformed_query) = table.transformed_query_to(Some(cfg)) {
+                let mut statement = conn.prepare(&transformed_query)?;
+                let mut rows = statement.query([])?;
+                while let Some(row) = rows.next()? {
+                    let (values, kinds) = row_values(row, table)?;
+                    let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+                    let transformed =
+                        self.engine
+                            .process_row(&cfg.name, table.get_column_indexes(), &values)?;
+                    let changed: Vec<bool> = transformed
+                        .iter()
+                        .map(|v| matches!(v, Cow::Owned(_)))
+                        .collect();
+                    self.write_row(&prefix, &transformed, &kinds, &changed)?;
+
+                    count += 1;
+                    self.row_written(count)?;
+                }
+            }
+        }
+
+        if let Some(untransformed_query) = table.untransformed_query_to(cfg, count) {
+            let mut statement = conn.prepare(&untransformed_query)?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                let (values, kinds) = row_values(row, table)?;
+                self.write_row(&prefix, &values, &kinds, &[])?;
+
+                count += 1;
+                self.row_written(count)?;
+            }
+        }
+        self.dump_writer.flush()?;
+
+        let finished = started.elapsed();
+        self.indicator
+            .finish_pb(table.get_full_name().as_str(), finished);
+
+        Ok(())
+    }
+
+    // `transformed` flags the values returned by transformers (it's empty for untransformed rows)
+    fn write_row<T: AsRef<str>>(
+        &mut self,
+        prefix: &str,
+        values: &[T],
+        kinds: &[Kind],
+        transformed: &[bool],
+    ) -> Result<()> {
+        let mut statement = String::from(prefix);
+        statement.push('(');
+        for (i, (value, kind)) in values.iter().zip(kinds.iter()).enumerate() {
+            if i > 0 {
+                statement.push(',');
+            }
+            let transformed = transformed.get(i).copied().unwrap_or(false);
+            literal::push(&mut statement, value.as_ref(), *kind, transformed);
+        }
+        statement.push_str(");\n");
+
+        self.dump_writer.write_all(statement.as_bytes())?;
+        Ok(())
+    }
+
+    fn row_written(&mut self, count: u64) -> Result<()> {
+        self.indicator.inc_pb(1);
+        if count.is_multiple_of(FLUSH_ROWS) {
+            self.dump_writer.flush()?;
+        }
+        Ok(())
+    }
+
+    // AUTOINCREMENT counters. `sqlite_sequence` is created with the first AUTOINCREMENT table,
+    // so counters are dumped only for tables in the dumped schema.
+    fn dump_sequences(&mut self, conn: &Connection) -> Result<()> {
+        let exists: i64 = conn.query_row(SEQUENCE_EXISTS_QUERY, [], |row| row.get(0))?;
+        if exists == 0 {
+            return Ok(());
+        }
+
+        let filter = self.engine.settings.filter.clone();
+        let mut sequences: Vec<(String, i64)> = conn
+            .prepare(SEQUENCE_QUERY)?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        sequences.retain(|(name, _)| filter.as_ref().is_none_or(|f| f.filter_schema(name)));
+        if sequences.is_empty() {
+            return Ok(());
+        }
+
+        self.dump_writer
+            .write_all(b"DELETE FROM sqlite_sequence;\n")?;
+        for (name, seq) in sequences {
+            let mut statement = String::from("INSERT INTO sqlite_sequence(name,seq) VALUES(");
+            literal::push(&mut statement, &name, Kind::Text, false);
+            statement.push_str(&format!(",{});\n", seq));
+            self.dump_writer.write_all(statement.as_bytes())?;
+        }
+        self.dump_writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: 'static + Write + Send, I: 'static + Indicator + Send> Dumper for SqliteDumper<W, I> {
+    type Table = SqliteTable;
+    type Connection = connector::Connection;
+    type SchemaInspector = SqliteSchemaInspector;
+
+    // Tables only (indexes, triggers and views are created after data).
+    // The whole dump is restored in one transaction.
+    fn pre_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        self.debug("Prepare data scheme...".into());
+        self.dump_writer
+            .write_all(b"PRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\n")?;
+        let statements = schema_statements(&connection.conn, SCHEMA_TABLES_QUERY)?;
+        self.write_statements(statements)
+    }
+
+    // This stage makes dump data only (in one read transaction)
+    fn data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        let settings = self.settings();
+        self.write_log("Start dumping data".into())?;
+        self.debug("Fetch tables metadata...".into());
+
+        let mut tables = self.schema_inspector().ordered_tables(&mut *connection);
+        for (table, _) in tables.iter_mut() {
+            if let Some(cfg) = settings.find_table(&table.get_names()) {
+                table.apply_column_exclusions(cfg)?;
+            }
+        }
+        sort_tables(
+            &mut tables,
+            settings.table_order.as_ref().unwrap_or(&vec![]),
+        );
+
+        let all_tables_count = tables.len();
+
+        let tx = connection.conn.transaction()?;
+        for (ind, (table, _weight)) in tables.iter().enumerate() {
+            self.debug(format!(
+                "[{} / {}] Prepare to dump table: {}",
+                ind + 1,
+                all_tables_count,
+                table.get_full_name(),
+            ));
+
+            if self.filter_table(&[table.get_full_name()], &settings.filter) {
+                self.dump_table(table, &tx)?;
+            } else {
+                self.debug(format!("[Dumping: {}] --- SKIP ---", table.get_full_name()));
+            }
+        }
+        self.dump_sequences(&tx)?;
+        tx.commit()?;
+
+        self.write_log("End dumping data".into())?;
+        Ok(())
+    }
+
+    // Indexes, triggers and views
+    fn post_data(&mut self, connection: &mut Self::Connection) -> Result<()> {
+        self.debug("Finishing with indexes...".into());
+        let statements = schema_statements(&connection.conn, SCHEMA_OBJECTS_QUERY)?;
+        self.write_statements(statements)?;
+        self.dump_writer.write_all(b"COMMIT;\n")?;
+        self.dump_writer.flush()?;
+        Ok(())
+    }
+
+    fn schema_inspector(&self) -> Self::SchemaInspector {
+        self.schema_inspector.clone()
+    }
+
+    fn settings(&mut self) -> Settings {
+        self.engine.settings.clone()
+    }
+
+    fn write_log(&mut self, message: String) -> Result<()> {
+        self.dump_writer
+            .write_all(format!("\n--\n-- {}\n--\n", message).as_bytes())
+            .map_err(|e| e.into())
+    }
+
+    fn debug(&self, message: String) {
+        self.indicator.debug_msg(message.as_str());
+    }
+}
+
+/// Table names and SQL of schema objects
+fn schema_statements(conn: &Connection, query: &str) -> Result<Vec<(String, String)>> {
+    Ok(conn
+        .prepare(query)?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?)
+}
+
+fn row_values(row: &rusqlite::Row, table: &SqliteTable) -> Result<(Vec<String>, Vec<Kind>)> {
+    let mut values = Vec::with_capacity(table.columns.len());
+    let mut kinds = Vec::with_capacity(table.columns.len());
+    for (i, column) in table.columns.iter().enumerate() {
+        let (value, kind) = literal::from_value(row.get_ref(i)?)
+            .map_err(|e| anyhow!("Invalid UTF-8 in the column {}: {}", column.name, e))?;
+        values.push(value);
+        kinds.push(kind);
+    }
+    Ok((values, kinds))
+}
+
+// Subsets, samples and large objects are specific for PostgreSQL
+fn check_settings(settings: &Settings) -> Result<()> {
+    if settings.subset.is_some() {
+        return Err(anyhow!("Subsets are not supported for SQLite"));
+    }
+    if settings.large_objects.is_some() {
+        return Err(anyhow!("Large objects are not supported for SQLite"));
+    }
+    for table in settings.tables.iter() {
+        if table.query.as_ref().is_some_and(|q| q.sample.is_some()) {
+            return Err(anyhow!(
+                "Samples are not supported for SQLite (table {})",
+                table.name
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! Conversion of SQLite values to SQL literals (for `INSERT` statements).
+//! Values are passed to transformers as strings: NULL is `\N` (as in PostgreSQL dumps),
+//! and blobs are hex strings prefixed with `\x` (as PostgreSQL `bytea`).
+//! SQLite values are dynamically typed, so the storage class of the fetched value is kept,
+//! and numbers are written back as numbers (if they are still numbers after transformation).
+//! A text value can be `\N` too, so it means NULL only for fetched NULLs and transformed values.
+
+use rusqlite::types::ValueRef;
+use std::fmt::Write;
+
+const NULL: &str = "\\N";
+
+/// Storage class of a fetched value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Null,
+    Integer,
+    Real,
+    Text,
+    Blob,
+}
+
+/// Converts a fetched value to a string for transformers
+pub fn from_value(value: ValueRef) -> Result<(String, Kind), std::str::Utf8Error> {
+    Ok(match value {
+        ValueRef::Null => (NULL.to_string(), Kind::Null),
+        ValueRef::Integer(i) => (i.to_string(), Kind::Integer),
+        ValueRef::Real(f) => (format!("{:?}", f), Kind::Real),
+        ValueRef::Text(bytes) => (std::str::from_utf8(bytes)?.to_string(), Kind::Text),
+        ValueRef::Blob(bytes) => {
+            let mut s = String::with_capacity(bytes.len() * 2 + 2);
+            s.push_str("\\x");
+            for byte in bytes {
+                let _ = write!(s, "{:02x}", byte);
+            }
+            (s, Kind::Blob)
+        }
+    })
+}
+
+/// Appends the value as an SQL literal of the original storage class
+/// (values that don't fit it anymore are written as text).
+/// `transformed` is true if the value is returned by a transformer.
+pub fn push(out: &mut String, value: &str, kind: Kind, transformed: bool) {
+    if value == NULL && (transformed || kind == Kind::Null) {
+        out.push_str("NULL");
+        return;
+    }
+
+    match kind {
+        Kind::Integer if value.parse::<i64>().is_ok() => {
+            out.push_str(value);
+            return;
+        }
+        Kind::Real | Kind::Integer if is_real(value) => {
+            match value.parse::<f64>() {
+                // SQLite has no infinity literal, but it parses too large numbers as infinity
+                Ok(f) if f.is_infinite() => out.push_str(if f > 0.0 { "9e999" } else { "-9e999" }),
+                _ => out.push_str(value),
+            }
+            return;
+        }
+        Kind::Blob => {
+            let hex = value
+                .strip_prefix("\\\\x")
+                .or_else(|| value.strip_prefix("\\x"));
+            if let Some(hex) =
+                hex.filter(|h| h.len() % 2 == 0 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+            {
+                out.push_str("X'");
+                out.push_str(hex);
+                out.push('\'');
+                return;
+            }
+        }
+        _ => {}
+    }
+
+    out.push('\'');
+    out.push_str(&value.replace('\'', "''"));
+    out.push('\'');
+}
+
+// Infinity is formatted as `inf`, other non-numeric strings accepted by `f64::from_str`
+// (e.g. `NaN`) are not SQL literals
+fn is_real(value: &str) -> bool {
+    matches!(value, "inf" | "-inf")
+        || (value.parse::<f64>().is_ok()
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(value: &str, kind: Kind) -> String {
+        let mut s = String::new();
+        push(&mut s, value, kind, true);
+        s
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(
+            from_value(ValueRef::Null).unwrap(),
+            ("\\N".into(), Kind::Null)
+        );
+        assert_eq!(
+            from_value(ValueRef::Integer(-5)).unwrap(),
+            ("-5".into(), Kind::Integer)
+        );
+        assert_eq!(
+            from_value(ValueRef::Real(1.0)).unwrap(),
+            ("1.0".into(), Kind::Real)
+        );
+        assert_eq!(
+            from_value(ValueRef::Text(b"text")).unwrap(),
+            ("text".into(), Kind::Text)
+        );
+        assert_eq!(
+            from_value(ValueRef::Blob(&[0, 15, 255])).unwrap(),
+            ("\\x000fff".into(), Kind::Blob)
+        );
+        assert!(from_value(ValueRef::Text(&[0xff])).is_err());
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(literal("\\N", Kind::Text), "NULL");
+        assert_eq!(literal("42", Kind::Integer), "42");
+        assert_eq!(literal("4.5", Kind::Integer), "4.5");
+        assert_eq!(literal("1.5e-7", Kind::Real), "1.5e-7");
+        assert_eq!(literal("inf", Kind::Real), "9e999");
+        assert_eq!(literal("-inf", Kind::Real), "-9e999");
+        assert_eq!(literal("NaN", Kind::Real), "'NaN'");
+        assert_eq!(literal("abc", Kind::Integer), "'abc'");
+        assert_eq!(literal("42", Kind::Text), "'42'");
+        assert_eq!(literal("it's\nme", Kind::Text), "'it''s\nme'");
+        assert_eq!(literal("value", Kind::Null), "'value'");
+    }
+
+    #[test]
+    fn nulls() {
+        let untransformed = |value: &str, kind: Kind| {
+            let mut s = String::new();
+            push(&mut s, value, kind, false);
+            s
+        };
+        assert_eq!(untransformed("\\N", Kind::Null), "NULL");
+        // The original text value
+        assert_eq!(untransformed("\\N", Kind::Text), "'\\N'");
+        // A transformed value
+        assert_eq!(literal("\\N", Kind::Text), "NULL");
+    }
+
+    #[test]
+    fn blob_literals() {
+        assert_eq!(literal("\\x000fff", Kind::Blob), "X'000fff'");
+        assert_eq!(literal("\\\\x0a", Kind::Blob), "X'0a'");
+        assert_eq!(literal("\\x", Kind::Blob), "X''");
+        assert_eq!(literal("\\x0", Kind::Blob), "'\\x0'");
+        assert_eq!(literal("\\x00", Kind::Text), "'\\x00'");
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Writer that executes the dump in a new SQLite database instead of writing SQL text somewhere
+/// (`sqlite3` equivalent).
+/// SQL is buffered and executed on every `flush`, so it relies on the dumper flushing
+/// only at statement boundaries (`SqliteDumper` does so). Call `finish` to execute the rest.
+/// A database file created with `create` is removed if the loader is dropped without `finish`
+/// (e.g. after an error), so a failed dump never leaves a partial database.
+pub struct SqliteLoader {
+    conn: Option<Connection>,
+    buffer: Vec<u8>,
+    created_path: Option<PathBuf>,
+}
+
+impl SqliteLoader {
+    /// Creates a new database file (an existing file is never overwritten)
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Err(anyhow!("The file {} already exists", path.display()));
+        }
+
+        let mut loader = Self::new(Connection::open(path)?);
+        loader.created_path = Some(path.to_path_buf());
+        Ok(loader)
+    }
+
+    pub fn new(conn: Connection) -> Self {
+        Self {
+            conn: Some(conn),
+            buffer: vec![],
+            created_path: None,
+        }
+    }
+
+    /// Executes the rest of the dump, returning the connection to the new database
+    pub fn finish(mut self) -> Result<Connection> {
+        self.execute()?;
+        self.created_path = None;
+        Ok(self.conn.take().expect("the connection is taken only once"))
+    }
+
+    fn execute(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let sql = std::str::from_utf8(&self.buffer)?;
+        if let Some(conn) = &self.conn {
+            conn.execute_batch(sql)?;
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl Drop for SqliteLoader {
+    fn drop(&mut self) {
+        if let Some(path) = self.created_path.take() {
+            // The file can be removed only after the connection is closed (on Windows)
+            drop(self.conn.take());
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Write for SqliteLoader {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.execute().map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executes_on_flush() {
+        let mut loader = SqliteLoader::new(Connection::open_in_memory().unwrap());
+        loader
+            .write_all(b"CREATE TABLE t(a);\nINSERT INTO t VALUES(1);\n")
+            .unwrap();
+        loader.write_all(b"INSERT INTO t VALUES(").unwrap();
+        loader.write_all(b"2);\n").unwrap();
+        loader.flush().unwrap();
+        loader.write_all(b"INSERT INTO t VALUES(3);\n").unwrap();
+
+        let conn = loader.finish().unwrap();
+        let sum: i64 = conn
+            .query_row("SELECT SUM(a) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    fn errors() {
+        let mut loader = SqliteLoader::new(Connection::open_in_memory().unwrap());
+        loader
+            .write_all(b"INSERT INTO unknown VALUES(1);\n")
+            .unwrap();
+        assert!(loader.flush().is_err());
+    }
+}
@@ -0,0 +1,10 @@
+use crate::SchemaInspector;
+
+pub mod column;
+pub mod connector;
+pub mod dumper;
+pub mod loader;
+pub mod schema_inspector;
+pub mod table;
+
+mod literal;
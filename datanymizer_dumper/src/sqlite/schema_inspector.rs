@@ -0,0 +1,88 @@
+use super::{column::SqliteColumn, connector, table::SqliteTable, SchemaInspector};
+use anyhow::Result;
+
+// Internal tables (e.g. `sqlite_sequence`) and virtual tables are skipped
+const TABLES_QUERY: &str = "SELECT name FROM sqlite_master
+                            WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+                            AND sql NOT LIKE 'CREATE VIRTUAL TABLE%'
+                            ORDER BY name";
+
+// Generated (and hidden) columns are skipped, they can't be inserted
+const COLUMNS_QUERY: &str = "SELECT name, type FROM pragma_table_xinfo(?1)
+                             WHERE hidden = 0
+                             ORDER BY cid";
+
+// Self-references are skipped (they don't affect the order of tables)
+const DEPENDENCIES_QUERY: &str = "SELECT DISTINCT \"table\" FROM pragma_foreign_key_list(?1)
+                                  WHERE \"table\" <> ?1";
+
+#[derive(Clone)]
+pub struct SqliteSchemaInspector;
+
+impl SchemaInspector for SqliteSchemaInspector {
+    type Type = String;
+    type Connection = connector::Connection;
+    type Table = SqliteTable;
+    type Column = SqliteColumn;
+
+    fn get_tables(&self, connection: &mut Self::Connection) -> Result<Vec<Self::Table>> {
+        let names: Vec<String> = connection
+            .conn
+            .prepare(TABLES_QUERY)?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let mut table = SqliteTable::new(name);
+            let columns = self.get_columns(connection, &table)?;
+            table.set_columns(columns);
+            table.size = self.get_table_size(connection, &table)?;
+            tables.push(table);
+        }
+
+        Ok(tables)
+    }
+
+    // There are no statistics by default, so rows are counted
+    fn get_table_size(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<i64> {
+        let query = format!("SELECT COUNT(*) FROM {}", table.quoted_name());
+        Ok(connection.conn.query_row(&query, [], |row| row.get(0))?)
+    }
+
+    fn get_dependencies(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<Vec<Self::Table>> {
+        let names: Vec<String> = connection
+            .conn
+            .prepare(DEPENDENCIES_QUERY)?
+            .query_map([&table.tablename], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(names.into_iter().map(SqliteTable::new).collect())
+    }
+
+    fn get_columns(
+        &self,
+        connection: &mut Self::Connection,
+        table: &Self::Table,
+    ) -> Result<Vec<Self::Column>> {
+        let mut statement = connection.conn.prepare(COLUMNS_QUERY)?;
+        let rows = statement.query_map([&table.tablename], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut columns = vec![];
+        for (i, row) in rows.enumerate() {
+            let (name, data_type) = row?;
+            columns.push(SqliteColumn::new(i + 1, name, data_type));
+        }
+        Ok(columns)
+    }
+}
@@ -0,0 +1,110 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{anonymizer::PgAnonymizer, connector::Connector},
+};
+use datanymizer_engine::{Engine, Settings};
+
+#[test]
+fn anonymize_in_place() {
+    let url = helpers::create_src_copy("in_place");
+    let mut connection = Connector::new(url, false, false).connect().unwrap();
+
+    let config = r#"
+      tables:
+        - name: actor
+          rules:
+            first_name:
+              template:
+                format: "Name {{ prev.actor_id }}"
+            last_name:
+              template:
+                format: "{{ prev.last_name | lower }}"
+          query:
+            transform_condition: "actor_id <= 100"
+    "#;
+    let engine = Engine::new(Settings::from_yaml(config).unwrap());
+    PgAnonymizer::new(engine, SilentIndicator, 30, true)
+        .anonymize(&mut connection)
+        .unwrap();
+
+    let mut src_client = helpers::src_client();
+    let mut dst_client = helpers::dst_client("in_place");
+
+    let rows_query = "SELECT actor_id, first_name, last_name FROM actor ORDER BY actor_id";
+    let src_rows = src_client.query(rows_query, &[]).unwrap();
+    let dst_rows = dst_client.query(rows_query, &[]).unwrap();
+    assert_eq!(src_rows.len(), dst_rows.len());
+
+    for (src_row, dst_row) in src_rows.iter().zip(dst_rows.iter()) {
+        let actor_id: i32 = dst_row.get("actor_id");
+        assert_eq!(src_row.get::<_, i32>("actor_id"), actor_id);
+
+        let dst_first_name: String = dst_row.get("first_name");
+        let dst_last_name: String = dst_row.get("last_name");
+        let src_last_name: String = src_row.get("last_name");
+        if actor_id <= 100 {
+            assert_eq!(dst_first_name, format!("Name {}", actor_id));
+            assert_eq!(dst_last_name, src_last_name.to_lowercase());
+        } else {
+            assert_eq!(dst_first_name, src_row.get::<_, String>("first_name"));
+            assert_eq!(dst_last_name, src_last_name);
+        }
+    }
+}
+
+#[test]
+fn anonymize_without_primary_key() {
+    let url = helpers::create_src_copy("in_place_no_pk");
+    let mut client = helpers::dst_client("in_place_no_pk");
+    // Rows of the parent and the child have the same `ctid`
+    client
+        .batch_execute(
+            r#"CREATE TABLE notes (body text, "ti""tle" text);
+               CREATE TABLE notes_child () INHERITS (notes);
+               INSERT INTO notes VALUES ('\N', 'parent');
+               INSERT INTO notes_child VALUES (NULL, 'child');"#,
+        )
+        .unwrap();
+    let mut connection = Connector::new(url, false, false).connect().unwrap();
+
+    let config = r#"
+      tables:
+        - name: notes
+          rules:
+            body:
+              sql: body
+            ti"tle:
+              capitalize: ~
+        - name: notes_child
+          rules:
+            ti"tle:
+              capitalize: ~
+    "#;
+    let engine = Engine::new(Settings::from_yaml(config).unwrap());
+    PgAnonymizer::new(engine, SilentIndicator, 30, false)
+        .anonymize(&mut connection)
+        .unwrap();
+
+    let rows: Vec<(String, Option<String>, String)> = client
+        .query(
+            r#"SELECT tableoid::regclass::text, body, "ti""tle" FROM notes ORDER BY 1"#,
+            &[],
+        )
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "notes".to_string(),
+                Some("\\N".to_string()),
+                "Parent".to_string()
+            ),
+            ("notes_child".to_string(), None, "Child".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,166 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+        CopyFormat,
+    },
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+const COLUMNS: [&str; 19] = [
+    "id",
+    "flag",
+    "data",
+    "body",
+    "code",
+    "title",
+    "doc",
+    "docb",
+    "small",
+    "big",
+    "num_oid",
+    "real_num",
+    "double_num",
+    "amount",
+    "uid",
+    "day",
+    "at_time",
+    "created",
+    "created_tz",
+];
+
+fn create_types(name: &str) {
+    helpers::create_src_copy(name);
+    helpers::dst_client(name)
+        .batch_execute(
+            r#"CREATE TABLE binary_types (
+                 id integer PRIMARY KEY, flag boolean, data bytea, body text, code char(3),
+                 title varchar(50), doc json, docb jsonb, small smallint, big bigint, num_oid oid,
+                 real_num real, double_num double precision, amount numeric, uid uuid, day date,
+                 at_time time, created timestamp, created_tz timestamptz
+               );
+               INSERT INTO binary_types VALUES
+                 (1, true, '\x0a0b5c09', E'multi\nline\ttab \\ \\N ё', 'ab', 'title',
+                  '{"a": [1, 2]}', '{"b": "c\td"}', -32768, 9223372036854775807, 16400,
+                  1.5, 0.1, 123456789.0012300, 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11',
+                  '2024-02-29', '12:34:56.789', '2024-02-29 23:59:59.999999',
+                  '2000-01-01 00:00:00+00'),
+                 (2, false, '\x', '', '', '', 'null', '[]', 0, -1, 0,
+                  'Infinity', '-Infinity', -0.00001, '00000000-0000-0000-0000-000000000000',
+                  '0044-03-15 BC', '00:00:00', '1999-12-31 00:00:00.5', '-infinity'),
+                 (3, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL,
+                  'NaN', 1e-300, 'NaN', NULL, 'infinity', NULL, 'infinity', NULL),
+                 (4, true, NULL, '\N', NULL, NULL, NULL, NULL, NULL, NULL, NULL,
+                  NULL, NULL, 0, NULL, NULL, NULL, NULL, NULL);
+               -- an unsupported column type (it is read as text)
+               CREATE TABLE binary_arrays (id integer PRIMARY KEY, title text, tags integer[]);
+               INSERT INTO binary_arrays VALUES (1, 'first', '{1,2}'), (2, 'second', NULL);"#,
+        )
+        .unwrap();
+}
+
+fn dump(src_name: &str, dst_name: &str, copy_format: CopyFormat) -> postgres::Client {
+    let config = r#"
+        tables:
+          - name: binary_types
+            rules:
+              title:
+                capitalize: ~
+          - name: binary_arrays
+            rules:
+              title:
+                capitalize: ~
+    "#;
+    let dst_url = helpers::create_dst_db(dst_name);
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    dumper.set_copy_format(copy_format);
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    helpers::dst_client(dst_name)
+}
+
+fn values(
+    client: &mut postgres::Client,
+    table: &str,
+    columns: &[&str],
+) -> Vec<Vec<Option<String>>> {
+    let select_list: Vec<_> = columns.iter().map(|c| format!("{}::text", c)).collect();
+    client
+        .query(
+            format!(
+                "SELECT {} FROM {} ORDER BY id",
+                select_list.join(", "),
+                table
+            )
+            .as_str(),
+            &[],
+        )
+        .unwrap()
+        .into_iter()
+        .map(|row| (0..columns.len()).map(|i| row.get(i)).collect())
+        .collect()
+}
+
+#[test]
+fn binary_copy() {
+    let src_name = "binary_copy_src";
+    create_types(src_name);
+    let mut src = helpers::dst_client(src_name);
+
+    for (dst_name, copy_format) in [
+        ("binary_copy_text", CopyFormat::Text),
+        ("binary_copy_binary", CopyFormat::Binary),
+    ] {
+        let mut dst = dump(src_name, dst_name, copy_format);
+
+        let columns: Vec<_> = COLUMNS[..19]
+            .iter()
+            .filter(|c| **c != "title")
+            .cloned()
+            .collect();
+        assert_eq!(
+            values(&mut dst, "binary_types", &columns),
+            values(&mut src, "binary_types", &columns)
+        );
+        assert_eq!(
+            values(&mut dst, "binary_types", &["title"]),
+            vec![
+                vec![Some("Title".to_string())],
+                vec![Some("".to_string())],
+                vec![None],
+                vec![None],
+            ]
+        );
+
+        assert_eq!(
+            values(&mut dst, "binary_arrays", &["id", "title", "tags"]),
+            vec![
+                vec![
+                    Some("1".to_string()),
+                    Some("First".to_string()),
+                    Some("{1,2}".to_string())
+                ],
+                vec![Some("2".to_string()), Some("Second".to_string()), None],
+            ]
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use super::helpers;
+
+use datanymizer_dumper::{indicator::SilentIndicator, postgres::dump_transformer::DumpTransformer};
+use datanymizer_engine::{Engine, Settings};
+use std::process::Command;
+
+fn transform_dump(name: &str, pg_dump_args: &[&str]) {
+    helpers::create_src_db();
+    let output = Command::new(helpers::pg_dump_path())
+        .args(["-t", "public.actor", "-t", "public.actor_actor_id_seq"])
+        .args(["--no-owner", "--no-acl"])
+        .args(pg_dump_args)
+        .arg(helpers::src_database_url().as_str())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let settings = Settings::new("tests/postgres/configs/simple.yml".to_string()).unwrap();
+    let transformer = DumpTransformer::new(Engine::new(settings), SilentIndicator).unwrap();
+
+    let mut dst = helpers::dst_wrapper(name);
+    let mut io = dst.io();
+    transformer
+        .transform(output.stdout.as_slice(), &mut io)
+        .unwrap();
+    drop(io);
+    dst.wait();
+}
+
+fn check(name: &str) {
+    let mut src_client = helpers::src_client();
+    let mut dst_client = helpers::dst_client(name);
+
+    let query = "SELECT actor_id, first_name, last_name FROM actor ORDER BY actor_id";
+    let src_rows = src_client.query(query, &[]).unwrap();
+    let dst_rows = dst_client.query(query, &[]).unwrap();
+    assert_eq!(src_rows.len(), dst_rows.len());
+
+    let mut changed = 0;
+    for (src_row, dst_row) in src_rows.iter().zip(dst_rows.iter()) {
+        assert_eq!(src_row.get::<_, i32>(0), dst_row.get::<_, i32>(0));
+        if src_row.get::<_, String>(1) != dst_row.get::<_, String>(1) {
+            changed += 1;
+        }
+    }
+    // Random names can match the original ones sometimes
+    assert!(changed > src_rows.len() / 2);
+}
+
+#[test]
+fn transform_copy_dump() {
+    transform_dump("transform_copy", &[]);
+    check("transform_copy");
+}
+
+#[test]
+fn transform_insert_dump() {
+    transform_dump(
+        "transform_inserts",
+        &["--inserts", "--rows-per-insert", "10"],
+    );
+    check("transform_inserts");
+}
@@ -2,7 +2,11 @@
 
 use datanymizer_dumper::{
     indicator::SilentIndicator,
-    postgres::{connector::Connection, dumper::PgDumper},
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+    },
     Dumper,
 };
 use datanymizer_engine::{Engine, Settings};
@@ -59,3 +63,53 @@ fn simple_dump() {
         assert_ne!(src_last_name, dst_last_name);
     }
 }
+
+#[test]
+fn limit_with_transform_condition() {
+    let dst_url = helpers::create_dst_db("limit_with_transform_condition");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    // The first 10 rows (by the primary key) are dumped, and only some of them are transformed
+    let config = r#"
+        tables:
+          - name: actor
+            rules:
+              first_name:
+                template:
+                  format: "Anonymous"
+            query:
+              transform_condition: "actor_id % 2 = 0"
+              limit: 10
+        filter:
+          data:
+            only:
+              - public.actor
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(helpers::src_client(), helpers::src_database_url());
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    let mut dst_client = helpers::dst_client("limit_with_transform_condition");
+    let rows = dst_client
+        .query(
+            "SELECT actor_id, first_name FROM actor ORDER BY actor_id",
+            &[],
+        )
+        .unwrap();
+    let ids: Vec<i32> = rows.iter().map(|row| row.get(0)).collect();
+    assert_eq!(ids, (1..=10).collect::<Vec<_>>());
+    for row in rows.iter() {
+        let id: i32 = row.get(0);
+        let first_name: String = row.get(1);
+        assert_eq!(first_name == "Anonymous", id % 2 == 0);
+    }
+}
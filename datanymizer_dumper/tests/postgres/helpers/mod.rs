@@ -21,6 +21,11 @@ pub fn io(&mut self) -> ChildStdin {
         self.0.stdin.take().unwrap()
     }
 
+    /// Waits until psql applies the whole input (the input must be dropped before)
+    pub fn wait(&mut self) {
+        self.0.wait().unwrap();
+    }
+
     pub fn close(&mut self) {
         self.0.kill().unwrap();
     }
@@ -51,14 +56,18 @@ pub fn create_src_db() {
     CREATE_SRC_DB.call_once(|| {
         let database_url = src_database_url();
         create_db(&database_url);
-        pg_restore_command()
-            .args(&["-d", database_url.as_str()])
-            .arg(SRC_DUMP_PATH)
-            .status()
-            .expect("Error when restoring the test source database");
+        restore_src_dump(&database_url);
     });
 }
 
+/// Creates a copy of the source database (restored from the same dump)
+pub fn create_src_copy(name: &str) -> Url {
+    let url = create_dst_db(name);
+    restore_src_dump(&url);
+
+    url
+}
+
 pub fn src_client() -> Client {
     create_src_db();
     client(&src_database_url())
@@ -68,12 +77,18 @@ pub fn dst_client(name: &str) -> Client {
     client(&dst_database_url(name))
 }
 
-pub fn dst_wrapper(name: &str) -> DstWrapper {
+pub fn create_dst_db(name: &str) -> Url {
     create_src_db();
 
     let dst_url = dst_database_url(name);
     create_db(&dst_url);
 
+    dst_url
+}
+
+pub fn dst_wrapper(name: &str) -> DstWrapper {
+    let dst_url = create_dst_db(name);
+
     DstWrapper(
         psql_command()
             .arg(dst_url.as_str())
@@ -99,6 +114,14 @@ fn create_db(url: &Url) {
     );
 }
 
+fn restore_src_dump(url: &Url) {
+    pg_restore_command()
+        .args(&["-d", url.as_str()])
+        .arg(SRC_DUMP_PATH)
+        .status()
+        .expect("Error when restoring the test source database");
+}
+
 fn psql_command() -> Command {
     let psql_path = env::var(PSQL_PATH_KEY).unwrap_or("psql".to_string());
     let mut cmd = Command::new(psql_path);
@@ -0,0 +1,123 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+    },
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+// Creates a copy of the source database with large objects (the second one is larger than a chunk)
+fn create_large_objects(name: &str) {
+    helpers::create_src_copy(name);
+    helpers::dst_client(name)
+        .batch_execute(
+            "SELECT lo_from_bytea(900001, 'public document'::bytea);
+             SELECT lo_from_bytea(900002, convert_to(repeat('secret', 500000), 'UTF8'));
+             SELECT lo_from_bytea(900003, 'another secret'::bytea);",
+        )
+        .unwrap();
+}
+
+fn dump(src_name: &str, dst_name: &str, config: &str) -> postgres::Client {
+    let dst_url = helpers::create_dst_db(dst_name);
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    helpers::dst_client(dst_name)
+}
+
+fn content(client: &mut postgres::Client, oid: u32) -> Option<Vec<u8>> {
+    client
+        .query_opt(
+            "SELECT lo_get(oid) FROM pg_largeobject_metadata WHERE oid = $1",
+            &[&oid],
+        )
+        .unwrap()
+        .map(|row| row.get(0))
+}
+
+fn check_large_objects(client: &mut postgres::Client) {
+    assert_eq!(
+        content(client, 900001).unwrap(),
+        b"public document".to_vec()
+    );
+    assert_eq!(content(client, 900002).unwrap(), vec![0; 3_000_000]);
+    assert_eq!(content(client, 900003), None);
+}
+
+#[test]
+fn dump_large_objects() {
+    let src_name = "large_objects_src";
+    create_large_objects(src_name);
+    let config = r#"
+        tables: []
+        large_objects:
+          default: drop
+          rules:
+            - oids: [900001]
+              action: keep
+            - query: "SELECT 900001 UNION SELECT 900002"
+              action: placeholder
+    "#;
+    let mut client = dump(src_name, "large_objects", config);
+    check_large_objects(&mut client);
+}
+
+#[test]
+fn dump_large_objects_with_schema_filter() {
+    let src_name = "large_objects_filter_src";
+    create_large_objects(src_name);
+    // pg_dump doesn't create large objects in this case, so they are created in the data section
+    let config = r#"
+        tables: []
+        filter:
+          schema:
+            only:
+              - public.actor
+        large_objects:
+          default: drop
+          rules:
+            - oids: [900001]
+              action: keep
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        vec![],
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    let dump = String::from_utf8(dumper.into_writer()).unwrap();
+
+    assert_eq!(dump.matches("lo_create").count(), 1);
+    assert!(dump.contains("SELECT pg_catalog.lo_create('900001');"));
+    assert!(dump
+        .contains("SELECT pg_catalog.lo_put('900001', 0, '\\x7075626c696320646f63756d656e74');"));
+    assert!(!dump.contains("lo_unlink"));
+}
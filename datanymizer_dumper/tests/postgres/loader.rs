@@ -0,0 +1,75 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+    },
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+use std::io::Write;
+
+#[test]
+fn dump_into_target_db() {
+    let dst_url = helpers::create_dst_db("loader");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    let settings = Settings::new("tests/postgres/configs/simple.yml".to_string()).unwrap();
+    let mut dumper = PgDumper::new(
+        Engine::new(settings),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(helpers::src_client(), helpers::src_database_url());
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    let mut src_client = helpers::src_client();
+    let mut dst_client = helpers::dst_client("loader");
+
+    let count_query = "SELECT COUNT(*) FROM actor";
+    let src_count: i64 = src_client.query_one(count_query, &[]).unwrap().get(0);
+    let dst_count: i64 = dst_client.query_one(count_query, &[]).unwrap().get(0);
+    assert_eq!(src_count, dst_count);
+
+    let names_query = "SELECT first_name FROM actor ORDER BY actor_id";
+    let src_names: Vec<String> = src_client
+        .query(names_query, &[])
+        .unwrap()
+        .iter()
+        .map(|r| r.get(0))
+        .collect();
+    let dst_names: Vec<String> = dst_client
+        .query(names_query, &[])
+        .unwrap()
+        .iter()
+        .map(|r| r.get(0))
+        .collect();
+    assert_ne!(src_names, dst_names);
+}
+
+#[test]
+fn rollback_unfinished_load() {
+    let dst_url = helpers::create_dst_db("loader_rollback");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    let mut loader = PgLoader::new(target.client);
+    loader
+        .write_all(b"CREATE TABLE t (id integer);\nINSERT INTO t VALUES (1);\n")
+        .unwrap();
+    // The loader is dropped without `finish` (e.g. after a dump error)
+    drop(loader);
+
+    let exists: bool = helpers::dst_client("loader_rollback")
+        .query_one("SELECT to_regclass('public.t') IS NOT NULL", &[])
+        .unwrap()
+        .get(0);
+    assert!(!exists);
+}
@@ -2,6 +2,16 @@
 
 mod helpers;
 
+mod anonymizer;
+mod binary_copy;
 mod connector;
+mod dump_transformer;
 mod dumper;
+mod large_objects;
+mod loader;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod partitions;
+mod relations;
 mod schema_inspector;
+mod subset;
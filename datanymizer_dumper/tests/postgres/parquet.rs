@@ -0,0 +1,73 @@
+use super::helpers;
+
+use arrow_array::{cast::AsArray, types::Int32Type, RecordBatch};
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::Connector, dumper::PgDumper, parquet::ParquetTarget,
+        schema_inspector::PgSchemaInspector,
+    },
+    Dumper, SchemaInspector,
+};
+use datanymizer_engine::{Engine, Settings};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::{self, File};
+
+#[test]
+fn dump_to_parquet() {
+    helpers::create_src_db();
+    let dir = std::env::temp_dir().join("datanymizer_parquet_dump");
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut connection = Connector::new(helpers::src_database_url(), false, false)
+        .connect()
+        .unwrap();
+    let tables = PgSchemaInspector {}.get_tables(&mut connection).unwrap();
+    let settings = Settings::new("tests/postgres/configs/simple.yml".to_string()).unwrap();
+    let mut dumper = PgDumper::new(
+        Engine::new(settings),
+        None,
+        helpers::pg_dump_path(),
+        ParquetTarget::new(&dir, &tables).unwrap(),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    dumper.data(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    // Only the table from the filter is exported
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(files.len(), 1);
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(
+        File::open(dir.join("public.actor.parquet")).unwrap(),
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+
+    let query = "SELECT actor_id, first_name FROM actor";
+    let src_rows = helpers::src_client().query(query, &[]).unwrap();
+    let src: std::collections::HashMap<i32, String> =
+        src_rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+
+    let mut count = 0;
+    let mut changed = 0;
+    for batch in batches.iter() {
+        let ids = batch.column(0).as_primitive::<Int32Type>();
+        let names = batch.column(1).as_string::<i32>();
+        for i in 0..batch.num_rows() {
+            count += 1;
+            if src[&ids.value(i)] != names.value(i) {
+                changed += 1;
+            }
+        }
+    }
+    assert_eq!(count, src.len());
+    // Random names can match the original ones sometimes
+    assert!(changed > count / 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,203 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+        schema_inspector::PgSchemaInspector,
+    },
+    Dumper, SchemaInspector, Table,
+};
+use datanymizer_engine::{Engine, Settings};
+
+// Creates a copy of the source database with partitioned tables
+fn create_partitioned_tables(name: &str) -> postgres::Client {
+    helpers::create_src_copy(name);
+    let mut client = helpers::dst_client(name);
+    client
+        .batch_execute(
+            "CREATE TABLE events (
+                 id serial,
+                 kind text NOT NULL,
+                 email text NOT NULL,
+                 created_at date NOT NULL
+             ) PARTITION BY RANGE (created_at);
+             CREATE TABLE events_2024_01 PARTITION OF events
+                 FOR VALUES FROM ('2024-01-01') TO ('2024-02-01');
+             CREATE TABLE events_2024_02 PARTITION OF events
+                 FOR VALUES FROM ('2024-02-01') TO ('2024-03-01')
+                 PARTITION BY LIST (kind);
+             CREATE TABLE events_2024_02_default PARTITION OF events_2024_02 DEFAULT;
+             INSERT INTO events (kind, email, created_at)
+             SELECT 'kind', 'user' || i || '@example.com', DATE '2024-01-01' + i
+             FROM generate_series(0, 49) AS i;",
+        )
+        .unwrap();
+    client
+}
+
+#[test]
+fn inspect_partitions() {
+    let src_name = "partitions_inspect";
+    create_partitioned_tables(src_name);
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    let tables = PgSchemaInspector.get_tables(&mut connection).unwrap();
+    let find = |name: &str| tables.iter().find(|t| t.get_full_name() == name).unwrap();
+
+    assert!(find("public.events").is_partitioned);
+    assert!(find("public.events_2024_02").is_partitioned);
+    assert!(!find("public.events_2024_01").is_partitioned);
+    assert_eq!(
+        find("public.events_2024_02_default").partition_parents,
+        vec!["public.events_2024_02", "public.events"]
+    );
+    assert!(find("public.actor").partition_parents.is_empty());
+}
+
+#[test]
+fn dump_partitions() {
+    let src_name = "partitions_src";
+    let mut src_client = create_partitioned_tables(src_name);
+    let dst_url = helpers::create_dst_db("partitions");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    // The rule for the parent table is applied to all partitions
+    let config = r#"
+        tables:
+          - name: events
+            rules:
+              email:
+                template:
+                  format: "anonymous@example.com"
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    let mut dst_client = helpers::dst_client("partitions");
+    let count = |client: &mut postgres::Client, query: &str| -> i64 {
+        client.query_one(query, &[]).unwrap().get(0)
+    };
+
+    // Every row is dumped once
+    assert_eq!(count(&mut dst_client, "SELECT COUNT(*) FROM events"), 50);
+    assert_eq!(
+        count(
+            &mut dst_client,
+            "SELECT COUNT(*) FROM events_2024_02_default"
+        ),
+        count(
+            &mut src_client,
+            "SELECT COUNT(*) FROM events_2024_02_default"
+        )
+    );
+    assert_eq!(
+        count(
+            &mut dst_client,
+            "SELECT COUNT(*) FROM events WHERE email = 'anonymous@example.com'"
+        ),
+        50
+    );
+    // The sequence of the partitioned table
+    assert_eq!(
+        count(&mut dst_client, "SELECT last_value FROM events_id_seq"),
+        50
+    );
+}
+
+#[test]
+fn exclude_partitioned_table() {
+    let src_name = "partitions_src_exclude";
+    create_partitioned_tables(src_name);
+    let dst_url = helpers::create_dst_db("partitions_exclude");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    // Partitions are excluded with their parent
+    let config = r#"
+        tables: []
+        filter:
+          schema:
+            except:
+              - public.events
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    let mut dst_client = helpers::dst_client("partitions_exclude");
+    let tables: Vec<String> = dst_client
+        .query(
+            "SELECT tablename::text FROM pg_tables WHERE tablename LIKE 'events%'",
+            &[],
+        )
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert!(tables.is_empty());
+    let actors: i64 = dst_client
+        .query_one("SELECT COUNT(*) FROM actor", &[])
+        .unwrap()
+        .get(0);
+    assert!(actors > 0);
+}
+
+#[test]
+fn limit_for_partitioned_table() {
+    let src_name = "partitions_src_limit";
+    create_partitioned_tables(src_name);
+
+    let config = r#"
+        tables:
+          - name: events
+            rules: {}
+            query:
+              limit: 10
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        Vec::new(),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    let error = dumper.data(&mut connection).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Limits are not supported for partitioned tables"));
+}
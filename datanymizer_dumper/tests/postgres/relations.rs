@@ -0,0 +1,168 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+        relation::RelationKind,
+        schema_inspector::PgSchemaInspector,
+    },
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+// Creates a copy of the source database with a materialized view and a foreign table
+fn create_relations(name: &str) {
+    helpers::create_src_copy(name);
+    helpers::dst_client(name)
+        .batch_execute(
+            "CREATE MATERIALIZED VIEW actor_names AS SELECT first_name, last_name FROM actor;
+             CREATE EXTENSION postgres_fdw;
+             CREATE SERVER loopback FOREIGN DATA WRAPPER postgres_fdw;
+             CREATE FOREIGN TABLE remote_actor (actor_id int, first_name text)
+                 SERVER loopback OPTIONS (table_name 'actor');",
+        )
+        .unwrap();
+}
+
+fn dump(src_name: &str, dst_name: &str, config: &str) -> postgres::Client {
+    let dst_url = helpers::create_dst_db(dst_name);
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(
+        helpers::dst_client(src_name),
+        helpers::dst_database_url(src_name),
+    );
+    dumper.dump(&mut connection).unwrap();
+    dumper.into_writer().finish().unwrap();
+
+    helpers::dst_client(dst_name)
+}
+
+fn relkind(client: &mut postgres::Client, name: &str) -> Option<(i8, bool)> {
+    client
+        .query_opt(
+            "SELECT relkind, relispopulated FROM pg_class WHERE relname = $1",
+            &[&name],
+        )
+        .unwrap()
+        .map(|row| (row.get(0), row.get(1)))
+}
+
+#[test]
+fn get_relations() {
+    let name = "relations_inspect";
+    create_relations(name);
+    let mut connection =
+        Connection::new(helpers::dst_client(name), helpers::dst_database_url(name));
+
+    let relations = PgSchemaInspector
+        .get_relations(
+            &mut connection,
+            &[RelationKind::MaterializedView, RelationKind::ForeignTable],
+        )
+        .unwrap();
+    let names: Vec<_> = relations
+        .iter()
+        .map(|r| (r.get_full_name(), r.kind))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (
+                "public.actor_names".to_string(),
+                RelationKind::MaterializedView
+            ),
+            (
+                "public.remote_actor".to_string(),
+                RelationKind::ForeignTable
+            ),
+        ]
+    );
+}
+
+#[test]
+fn refresh_and_keep() {
+    let src_name = "relations_src_default";
+    create_relations(src_name);
+    let mut client = dump(src_name, "relations_default", "tables: []");
+
+    assert_eq!(
+        relkind(&mut client, "actor_names"),
+        Some((b'm' as i8, true))
+    );
+    assert_eq!(
+        relkind(&mut client, "remote_actor"),
+        Some((b'f' as i8, true))
+    );
+}
+
+#[test]
+fn skip() {
+    let src_name = "relations_src_skip";
+    create_relations(src_name);
+    let config = r#"
+        tables: []
+        materialized_views: skip
+        foreign_tables: skip
+    "#;
+    let mut client = dump(src_name, "relations_skip", config);
+
+    // The view is restored without data
+    assert_eq!(
+        relkind(&mut client, "actor_names"),
+        Some((b'm' as i8, false))
+    );
+    assert_eq!(relkind(&mut client, "remote_actor"), None);
+}
+
+#[test]
+fn dump_views() {
+    let src_name = "relations_src_dump";
+    create_relations(src_name);
+    let config = r#"
+        tables:
+          - name: actor_names
+            rules:
+              first_name:
+                template:
+                  format: "Anonymous"
+        materialized_views: dump
+        foreign_tables: skip
+    "#;
+    let mut client = dump(src_name, "relations_dump", config);
+
+    // Rows of the view are restored (transformed) into a table
+    assert_eq!(
+        relkind(&mut client, "actor_names"),
+        Some((b'r' as i8, true))
+    );
+    let row = client
+        .query_one(
+            "SELECT count(*), count(*) FILTER (WHERE first_name = 'Anonymous'),
+                 count(DISTINCT last_name)
+             FROM actor_names",
+            &[],
+        )
+        .unwrap();
+    let actors: i64 = client
+        .query_one("SELECT count(*) FROM actor", &[])
+        .unwrap()
+        .get(0);
+    let total: i64 = row.get(0);
+    let anonymized: i64 = row.get(1);
+    let last_names: i64 = row.get(2);
+    assert_eq!(total, actors);
+    assert_eq!(anonymized, actors);
+    assert!(last_names > 1);
+}
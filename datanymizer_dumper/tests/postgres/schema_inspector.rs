@@ -22,3 +22,79 @@ fn get_tables() {
     assert_eq!(table.tablename, "actor");
     assert_eq!(table.schemaname, "public");
 }
+
+#[test]
+fn foreign_keys() {
+    let name = "foreign_keys";
+    helpers::create_src_copy(name);
+    helpers::dst_client(name)
+        .batch_execute(
+            "CREATE SCHEMA billing;
+             CREATE TABLE billing.tenants (id int PRIMARY KEY);
+             CREATE TABLE billing.users (
+                 tenant_id int REFERENCES billing.tenants,
+                 id int,
+                 manager_id int,
+                 PRIMARY KEY (tenant_id, id),
+                 FOREIGN KEY (tenant_id, manager_id) REFERENCES billing.users (tenant_id, id)
+             );
+             CREATE TABLE users (id int PRIMARY KEY);
+             CREATE TABLE billing.invoices (
+                 id int PRIMARY KEY,
+                 user_tenant_id int,
+                 user_id int,
+                 FOREIGN KEY (user_tenant_id, user_id) REFERENCES billing.users (tenant_id, id)
+             );",
+        )
+        .unwrap();
+    let mut connection =
+        Connection::new(helpers::dst_client(name), helpers::dst_database_url(name));
+    let inspector = PgSchemaInspector;
+
+    let graph = inspector.get_foreign_key_graph(&mut connection).unwrap();
+    let invoice_references: Vec<_> = graph.references_from("billing.invoices").collect();
+    assert_eq!(invoice_references.len(), 1);
+    assert_eq!(invoice_references[0].foreign_table, "billing.users");
+    assert_eq!(
+        invoice_references[0].columns,
+        vec!["user_tenant_id", "user_id"]
+    );
+    assert_eq!(
+        invoice_references[0].foreign_columns,
+        vec!["tenant_id", "id"]
+    );
+    assert!(graph.has_self_reference("billing.users"));
+    assert_eq!(
+        graph
+            .dependencies("billing.users")
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["billing.tenants"]
+    );
+    assert!(graph.references_to("public.users").next().is_none());
+
+    // Dependencies are found by the schema and the table name
+    let tables = inspector.get_tables(&mut connection).unwrap();
+    let deps = inspector
+        .get_dependencies(&mut connection, find_table(&tables, "billing.users"))
+        .unwrap();
+    let deps: Vec<_> = deps.iter().map(|t| t.get_full_name()).collect();
+    assert_eq!(deps, vec!["billing.tenants"]);
+    assert!(inspector
+        .get_dependencies(&mut connection, find_table(&tables, "public.users"))
+        .unwrap()
+        .is_empty());
+
+    let ordered = inspector.ordered_tables(&mut connection);
+    let weight = |name: &str| {
+        ordered
+            .iter()
+            .find(|(t, _)| t.get_full_name() == name)
+            .unwrap()
+            .1
+    };
+    assert!(weight("billing.tenants") > weight("billing.users"));
+    assert!(weight("billing.users") > weight("billing.invoices"));
+    // Nothing depends on it (but the table itself)
+    assert_eq!(weight("public.users"), 1);
+}
@@ -0,0 +1,67 @@
+use super::helpers;
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    postgres::{
+        connector::{Connection, Connector},
+        dumper::PgDumper,
+        loader::PgLoader,
+    },
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+
+#[test]
+fn dump_subset() {
+    let dst_url = helpers::create_dst_db("subset");
+    let target = Connector::new(dst_url, false, false).connect().unwrap();
+
+    let config = r#"
+        tables: []
+        subset:
+          roots:
+            - table: customer
+              condition: "customer_id <= 10"
+    "#;
+    let mut dumper = PgDumper::new(
+        Engine::new(Settings::from_yaml(config).unwrap()),
+        None,
+        helpers::pg_dump_path(),
+        PgLoader::new(target.client),
+        SilentIndicator,
+        vec![],
+    )
+    .unwrap();
+    let mut connection = Connection::new(helpers::src_client(), helpers::src_database_url());
+    dumper.dump(&mut connection).unwrap();
+    // Foreign keys are created after loading data, so it fails if the subset is not consistent
+    dumper.into_writer().finish().unwrap();
+
+    let mut src_client = helpers::src_client();
+    let mut dst_client = helpers::dst_client("subset");
+
+    let count = |client: &mut postgres::Client, query: &str| -> i64 {
+        client.query_one(query, &[]).unwrap().get(0)
+    };
+
+    assert_eq!(count(&mut dst_client, "SELECT COUNT(*) FROM customer"), 10);
+    // All rentals of selected customers
+    assert_eq!(
+        count(&mut dst_client, "SELECT COUNT(*) FROM rental"),
+        count(
+            &mut src_client,
+            "SELECT COUNT(*) FROM rental WHERE customer_id <= 10"
+        )
+    );
+    // Only films of rented inventory
+    assert_eq!(
+        count(&mut dst_client, "SELECT COUNT(*) FROM film"),
+        count(
+            &mut src_client,
+            "SELECT COUNT(DISTINCT i.film_id) FROM rental r \
+             JOIN inventory i ON i.inventory_id = r.inventory_id WHERE r.customer_id <= 10"
+        )
+    );
+    // Not referenced by selected rows
+    assert_eq!(count(&mut dst_client, "SELECT COUNT(*) FROM actor"), 0);
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "sqlite")]
+
+use datanymizer_dumper::{
+    indicator::SilentIndicator,
+    sqlite::{connector::Connector, dumper::SqliteDumper, loader::SqliteLoader},
+    Dumper,
+};
+use datanymizer_engine::{Engine, Settings};
+use rusqlite::Connection;
+use std::{fs, io::Write, path::PathBuf};
+
+const SCHEMA: &str = "
+    CREATE TABLE users (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        email TEXT NOT NULL,
+        name TEXT,
+        avatar BLOB,
+        score REAL,
+        title TEXT GENERATED ALWAYS AS (name || '!') VIRTUAL
+    );
+    CREATE TABLE orders (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER REFERENCES users(id),
+        amount INTEGER
+    );
+    CREATE INDEX orders_user_id ON orders(user_id);
+    CREATE VIEW user_orders AS
+        SELECT u.email, o.amount FROM users u JOIN orders o ON o.user_id = u.id;
+    CREATE TRIGGER orders_check BEFORE INSERT ON orders
+    WHEN NEW.amount < 0
+    BEGIN
+        SELECT RAISE(ABORT, 'negative amount');
+    END;
+
+    INSERT INTO users (email, name, avatar, score) VALUES
+        ('john@example.com', 'John', X'0001ff', 1.5),
+        ('jane@example.com', 'It''s
+        Jane', NULL, 2.0),
+        ('bob@example.com', NULL, X'', NULL);
+    DELETE FROM users WHERE id = 3;
+    INSERT INTO orders (user_id, amount) VALUES (1, 10), (1, 20), (2, 30);
+";
+
+const CONFIG: &str = r#"
+    tables:
+      - name: users
+        rules:
+          email:
+            email: {}
+          avatar:
+            bytea_random: {}
+"#;
+
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "datanymizer_{}_{}.sqlite",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn src_db(name: &str) -> TempDb {
+    let db = TempDb::new(name);
+    Connection::open(&db.0)
+        .unwrap()
+        .execute_batch(SCHEMA)
+        .unwrap();
+    db
+}
+
+fn dumper<W: 'static + std::io::Write + Send>(
+    config: &str,
+    writer: W,
+) -> SqliteDumper<W, SilentIndicator> {
+    let settings = Settings::from_yaml(config).unwrap();
+    SqliteDumper::new(Engine::new(settings), writer, SilentIndicator).unwrap()
+}
+
+fn query_strings(conn: &Connection, query: &str) -> Vec<Option<String>> {
+    conn.prepare(query)
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn dump_into_new_db() {
+    let src = src_db("loader_src");
+    let dst = TempDb::new("loader_dst");
+
+    let mut dumper = dumper(CONFIG, SqliteLoader::create(&dst.0).unwrap());
+    let mut connection = Connector::new(&src.0).connect().unwrap();
+    dumper.dump(&mut connection).unwrap();
+    let dst_conn = dumper.into_writer().finish().unwrap();
+    let src_conn = Connection::open(&src.0).unwrap();
+
+    let emails_query = "SELECT email FROM users ORDER BY id";
+    let src_emails = query_strings(&src_conn, emails_query);
+    let dst_emails = query_strings(&dst_conn, emails_query);
+    assert_eq!(src_emails.len(), dst_emails.len());
+    assert_ne!(src_emails, dst_emails);
+
+    // Untransformed values keep their storage classes
+    for query in [
+        "SELECT name || '|' || typeof(score) || '|' || title FROM users ORDER BY id",
+        "SELECT typeof(avatar) || '|' || length(avatar) FROM users ORDER BY id",
+        "SELECT id || '|' || user_id || '|' || typeof(amount) FROM orders ORDER BY id",
+        "SELECT CAST(seq AS TEXT) FROM sqlite_sequence",
+        "SELECT name FROM sqlite_master WHERE type != 'table' ORDER BY name",
+        "SELECT CAST(amount AS TEXT) FROM user_orders ORDER BY amount",
+    ] {
+        assert_eq!(
+            query_strings(&src_conn, query),
+            query_strings(&dst_conn, query),
+            "{}",
+            query
+        );
+    }
+
+    let error = dst_conn
+        .execute("INSERT INTO orders (user_id, amount) VALUES (1, -1)", [])
+        .unwrap_err();
+    assert!(error.to_string().contains("negative amount"));
+}
+
+#[test]
+fn dump_to_sql() {
+    let src = src_db("sql_src");
+
+    let mut dumper = dumper(
+        r#"
+        filter:
+          schema:
+            except:
+              - orders
+              - user_orders
+        tables:
+          - name: users
+            rules:
+              name:
+                template:
+                  format: "{{ _0 | upper }}"
+        "#,
+        vec![],
+    );
+    let mut connection = Connector::new(&src.0).connect().unwrap();
+    dumper.dump(&mut connection).unwrap();
+    let dump = String::from_utf8(dumper.into_writer()).unwrap();
+
+    assert!(dump.starts_with("PRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\n"));
+    assert!(dump.ends_with("COMMIT;\n"));
+    assert!(dump.contains(
+        "INSERT INTO \"users\"(\"id\",\"email\",\"name\",\"avatar\",\"score\") \
+         VALUES(1,'john@example.com','JOHN',X'0001ff',1.5);"
+    ));
+    assert!(dump.contains("VALUES(2,'jane@example.com','IT''S\n        JANE',NULL,2.0);"));
+    assert!(dump.contains("INSERT INTO sqlite_sequence(name,seq) VALUES('users',3);"));
+    assert!(!dump.contains("orders"));
+
+    // The dump can be executed with the same result
+    let dst = Connection::open_in_memory().unwrap();
+    dst.execute_batch(&dump).unwrap();
+    assert_eq!(
+        query_strings(&dst, "SELECT title FROM users ORDER BY id"),
+        vec![
+            Some("JOHN!".to_string()),
+            Some("IT'S\n        JANE!".to_string())
+        ]
+    );
+}
+
+#[test]
+fn existing_target_file() {
+    let src = src_db("existing_target");
+    assert!(SqliteLoader::create(&src.0).is_err());
+}
+
+#[test]
+fn failed_load() {
+    let dst = TempDb::new("failed_load");
+    let mut loader = SqliteLoader::create(&dst.0).unwrap();
+    loader
+        .write_all(b"CREATE TABLE t(a);\nINSERT INTO unknown VALUES(1);\n")
+        .unwrap();
+    assert!(loader.flush().is_err());
+    assert!(dst.0.exists());
+
+    // The partially loaded database is removed
+    drop(loader);
+    assert!(!dst.0.exists());
+}
@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use datanymizer_engine::{Engine, Settings};
+use std::collections::HashMap;
+
+const COLUMNS: usize = 80;
+
+fn column_indexes() -> HashMap<String, usize> {
+    (0..COLUMNS).map(|i| (format!("column_{}", i), i)).collect()
+}
+
+fn values() -> Vec<String> {
+    (0..COLUMNS)
+        .map(|i| format!("value number {} with some text", i))
+        .collect()
+}
+
+fn engine(rules: &str, rule_order: &[&str]) -> Engine {
+    let rule_order: String = rule_order
+        .iter()
+        .map(|name| format!("      - {}\n", name))
+        .collect();
+    let config = format!(
+        "tables:\n  - name: wide\n    rules:\n{}    rule_order:\n{}",
+        rules, rule_order
+    );
+    Engine::new(Settings::from_yaml(&config).unwrap())
+}
+
+// `transformed` columns (evenly spread over the row) with simple rules
+fn wide_row(c: &mut Criterion) {
+    let column_indexes = column_indexes();
+    let values = values();
+    let values: Vec<_> = values.iter().map(|v| v.as_str()).collect();
+    let mut group = c.benchmark_group("process_row/wide_row");
+
+    for transformed in [1, 10, COLUMNS] {
+        let step = COLUMNS / transformed;
+        let rules: String = (0..transformed)
+            .map(|i| format!("      column_{}:\n        capitalize: ~\n", i * step))
+            .collect();
+        let engine = engine(&rules, &[]);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(transformed),
+            &values,
+            |b, values| {
+                b.iter(|| {
+                    engine
+                        .process_row("wide", &column_indexes, black_box(values))
+                        .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// Templates referencing original (`prev`) and transformed (`final`) values of other columns
+fn row_references(c: &mut Criterion) {
+    let column_indexes = column_indexes();
+    let values = values();
+    let values: Vec<_> = values.iter().map(|v| v.as_str()).collect();
+    let rules = r#"      column_0:
+        first_name: {}
+      column_1:
+        last_name: {}
+      column_2:
+        template:
+          format: "{{ prev.column_0 }} {{ final.column_1 }}"
+      column_3:
+        template:
+          format: "{{ final.column_2 | upper }} <{{ prev.column_79 }}>"
+"#;
+    let engine = engine(rules, &["column_0", "column_1", "column_2", "column_3"]);
+
+    c.bench_function("process_row/row_references", |b| {
+        b.iter(|| {
+            engine
+                .process_row("wide", &column_indexes, black_box(&values))
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, wide_row, row_references);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use datanymizer_engine::{Settings, TransformContext, Transformer, Transformers};
+use std::{env, fs};
+
+const TABLE: &str = "bench";
+const VALUE: &str = "Some original value";
+const BYTEA_VALUE: &str = "\\\\x89504e470d0a1a0a0000000d49484452";
+
+// Configs for transformers that can't be created with `{}`
+fn config(name: &str, file: &str) -> String {
+    match name {
+        "none" | "capitalize" => "~".to_string(),
+        "template" => {
+            r#"{ format: "Hello, {{ _1 }}! {{ _0 | upper }}", rules: [{ first_name: {} }] }"#
+                .to_string()
+        }
+        "pipeline" => "{ pipes: [{ email: {} }, { capitalize: ~ }] }".to_string(),
+        "sql" => "\"md5(id::text)\"".to_string(),
+        "bytea_file" => format!("{{ path: \"{}\" }}", file),
+        _ => "{}".to_string(),
+    }
+}
+
+// All transformers (initialized in the same way as in dumps) as rules of one table
+fn settings() -> Settings {
+    let file = env::temp_dir().join("datanymizer_bench_avatar.png");
+    fs::write(&file, [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]).unwrap();
+    let file = file.to_string_lossy();
+
+    let rules: String = Transformers::NAMES
+        .iter()
+        .map(|name| {
+            format!(
+                "      {}:\n        {}: {}\n",
+                name,
+                name,
+                config(name, &file)
+            )
+        })
+        .collect();
+    let config = format!("tables:\n  - name: {}\n    rules:\n{}", TABLE, rules);
+    Settings::from_yaml(&config).unwrap_or_else(|e| panic!("{}\n{}", e, config))
+}
+
+fn transformers(c: &mut Criterion) {
+    let settings = settings();
+    let ctx = Some(TransformContext::new(&settings.globals, None, None, None));
+    let mut group = c.benchmark_group("transformers");
+
+    for (name, transformer) in settings.transformers_for(TABLE).unwrap() {
+        let field_name = format!("{}.{}", TABLE, name);
+        let value = if name.starts_with("bytea_") {
+            BYTEA_VALUE
+        } else {
+            VALUE
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(name), value, |b, value| {
+            b.iter(|| transformer.transform(&field_name, black_box(value), &ctx))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, transformers);
+criterion_main!(benches);
@@ -1,9 +1,11 @@
 use crate::{
     errors::{EngineError, UnknownColumnError},
-    Settings, TransformContext, Transformer,
+    Settings, TransformContext, Transformer, Transformers,
 };
 use std::{borrow::Cow, collections::HashMap};
 
+const NULL: &str = "\\N";
+
 pub struct Engine {
     pub settings: Settings,
 }
@@ -13,13 +15,113 @@ pub fn new(settings: Settings) -> Self {
         Self { settings }
     }
 
-    pub fn process_row<'a>(
+    /// Transforms the value with the rule for the column of the table (the table config is found
+    /// by the name or a pattern). The value is returned as is if there is no such rule.
+    /// Values of excluded and dropped columns are replaced with NULL.
+    /// Other values of the row are unknown here, so rules that refer to them (e.g., `final` and
+    /// `prev` in templates) should be applied with `transform_record`.
+    pub fn transform_value(
+        &self,
+        table: &str,
+        column: &str,
+        value: &str,
+    ) -> Result<String, EngineError> {
+        let cfg = match self.settings.find_table(&[table]) {
+            Some(cfg) => cfg,
+            None => return Ok(value.to_string()),
+        };
+        if cfg.exclude_columns.iter().any(|c| c == column)
+            || cfg.drop_columns.iter().any(|c| c == column)
+        {
+            return Ok(NULL.to_string());
+        }
+
+        let rule = self
+            .settings
+            .transformers_for(&cfg.name)
+            .and_then(|ts| ts.iter().find(|(c, _)| c == column).map(|(_, r)| r));
+
+        match rule {
+            Some(Transformers::Sql(_)) => Err(EngineError::SqlRuleError(format!(
+                "{}.{}",
+                cfg.name, column
+            ))),
+            Some(rule) => match rule.transform(
+                &format!("{}.{}", cfg.name, column),
+                value,
+                &Some(TransformContext::new(
+                    &self.settings.globals,
+                    None,
+                    None,
+                    None,
+                )),
+            ) {
+                Ok(res) => Ok(res.unwrap_or_else(|| value.to_string())),
+                Err(e) => Err(EngineError::TransformFieldError(e)),
+            },
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Transforms the record (column name -> value) with rules of the table (the table config
+    /// is found by the name or a pattern). NULL values are `\N` (as in `process_row`).
+    /// Columns with rules that are missing in the record are NULL for other rules
+    /// (and they are not added to the result). Excluded columns are NULL in the result
+    /// and dropped columns are removed from it.
+    pub fn transform_record(
         &self,
-        table: String,
+        table: &str,
+        record: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, EngineError> {
+        let cfg = match self.settings.find_table(&[table]) {
+            Some(cfg) => cfg,
+            None => return Ok(record.clone()),
+        };
+        if let Some(column) = cfg
+            .rules
+            .iter()
+            .find_map(|(name, rule)| matches!(rule, Transformers::Sql(_)).then_some(name))
+        {
+            return Err(EngineError::SqlRuleError(format!(
+                "{}.{}",
+                cfg.name, column
+            )));
+        }
+
+        let mut column_indexes = HashMap::with_capacity(record.len() + cfg.rules.len());
+        let mut values = Vec::with_capacity(record.len() + cfg.rules.len());
+        for (column, value) in record {
+            column_indexes.insert(column.clone(), values.len());
+            if cfg.exclude_columns.contains(column) || cfg.drop_columns.contains(column) {
+                values.push(NULL);
+            } else {
+                values.push(value.as_str());
+            }
+        }
+        for column in cfg.rules.keys() {
+            if !column_indexes.contains_key(column) {
+                column_indexes.insert(column.clone(), values.len());
+                values.push(NULL);
+            }
+        }
+
+        let transformed = self.process_row(&cfg.name, &column_indexes, &values)?;
+        Ok(column_indexes
+            .into_iter()
+            .filter(|(column, _)| record.contains_key(column) && !cfg.drop_columns.contains(column))
+            .map(|(column, i)| (column, transformed[i].to_string()))
+            .collect())
+    }
+
+    /// The table name can be passed as `&str` or `String`
+    pub fn process_row<'a, T: AsRef<str>>(
+        &self,
+        table: T,
         column_indexes: &HashMap<String, usize>,
         values: &'a [&str],
     ) -> Result<Vec<Cow<'a, str>>, EngineError> {
-        let ts = self.settings.transformers_for(&table);
+        let table = table.as_ref();
+        let ts = self.settings.transformers_for(table);
 
         let mut transformed_values = Vec::with_capacity(values.len());
         for &v in values {
@@ -89,6 +191,7 @@ fn process_row() {
         column_indexes.insert(String::from("one_more_column"), 3);
         column_indexes.insert(String::from("last_update"), 4);
 
+        // The table name can be owned
         let tr_values = Engine::new(settings)
             .process_row(table, &column_indexes, &values)
             .unwrap();
@@ -100,6 +203,139 @@ fn process_row() {
         assert_ne!(tr_values[4], "");
     }
 
+    #[test]
+    fn transform_value() {
+        let config = r#"
+          tables:
+            - name: "public.user_*"
+              rules:
+                email:
+                  template:
+                    format: "{{ _0 | upper }}@{{ company }}.com"
+          globals:
+            company: example
+        "#;
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+
+        assert_eq!(
+            engine
+                .transform_value("public.user_accounts", "email", "john")
+                .unwrap(),
+            "JOHN@example.com"
+        );
+        assert_eq!(
+            engine
+                .transform_value("public.user_accounts", "name", "John")
+                .unwrap(),
+            "John"
+        );
+        assert_eq!(
+            engine.transform_value("other", "email", "john").unwrap(),
+            "john"
+        );
+    }
+
+    #[test]
+    fn transform_record() {
+        let config = r#"
+          tables:
+            - name: users
+              rule_order:
+                - greeting
+              rules:
+                first_name:
+                  template:
+                    format: "Alice"
+                greeting:
+                  template:
+                    format: "Hello, {{ final.first_name }}! {{ prev.note }}"
+                note:
+                  template:
+                    format: "-"
+        "#;
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+
+        let mut record = HashMap::new();
+        record.insert(String::from("first_name"), String::from("Bob"));
+        record.insert(String::from("greeting"), String::from(""));
+        record.insert(String::from("id"), String::from("42"));
+
+        let transformed = engine.transform_record("users", &record).unwrap();
+        assert_eq!(transformed.len(), 3);
+        assert_eq!(transformed["first_name"], "Alice");
+        assert_eq!(transformed["greeting"], "Hello, Alice! \\N");
+        assert_eq!(transformed["id"], "42");
+
+        assert_eq!(engine.transform_record("other", &record).unwrap(), record);
+    }
+
+    #[test]
+    fn excluded_and_dropped_columns() {
+        let config = r#"
+          tables:
+            - name: users
+              rules:
+                first_name:
+                  template:
+                    format: "Alice"
+              exclude_columns:
+                - password
+              drop_columns:
+                - token
+        "#;
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+
+        let mut record = HashMap::new();
+        record.insert(String::from("first_name"), String::from("Bob"));
+        record.insert(String::from("password"), String::from("secret"));
+        record.insert(String::from("token"), String::from("abc"));
+
+        let transformed = engine.transform_record("users", &record).unwrap();
+        assert_eq!(transformed.len(), 2);
+        assert_eq!(transformed["first_name"], "Alice");
+        assert_eq!(transformed["password"], "\\N");
+        assert!(!transformed.contains_key("token"));
+
+        assert_eq!(
+            engine
+                .transform_value("users", "password", "secret")
+                .unwrap(),
+            "\\N"
+        );
+        assert_eq!(
+            engine.transform_value("users", "token", "abc").unwrap(),
+            "\\N"
+        );
+    }
+
+    #[test]
+    fn sql_rules() {
+        let config = r#"
+          tables:
+            - name: users
+              rules:
+                first_name:
+                  template:
+                    format: "Alice"
+                code:
+                  sql: "md5(code)"
+        "#;
+        let engine = Engine::new(Settings::from_yaml(config).unwrap());
+
+        assert!(matches!(
+            engine.transform_value("users", "code", "abc"),
+            Err(EngineError::SqlRuleError(c)) if c == "users.code"
+        ));
+
+        let mut record = HashMap::new();
+        record.insert(String::from("first_name"), String::from("Bob"));
+        record.insert(String::from("code"), String::from("abc"));
+        assert!(matches!(
+            engine.transform_record("users", &record),
+            Err(EngineError::SqlRuleError(_))
+        ));
+    }
+
     mod row_refs {
         use super::*;
         use crate::transformers::CapitalizeTransformer;
@@ -142,7 +378,7 @@ fn final_row() {
             let values = vec!["", "", "", "", ""];
 
             let tr_values = Engine::new(settings)
-                .process_row(table, &column_indexes(), &values)
+                .process_row(&table, &column_indexes(), &values)
                 .unwrap();
 
             assert_ne!(tr_values[0], "");
@@ -185,7 +421,7 @@ fn pipeline_and_final_row() {
             let values = vec!["", "", "", "", ""];
 
             let tr_values = Engine::new(settings)
-                .process_row(table, &column_indexes(), &values)
+                .process_row(&table, &column_indexes(), &values)
                 .unwrap();
 
             assert_ne!(tr_values[0], "");
@@ -229,7 +465,7 @@ fn prev_and_final_row() {
             let values = vec!["orig_name", "orig_middle_name", "", "", ""];
 
             let tr_values = Engine::new(settings)
-                .process_row(table, &column_indexes(), &values)
+                .process_row(&table, &column_indexes(), &values)
                 .unwrap();
 
             assert_ne!(tr_values[0], "orig_name");
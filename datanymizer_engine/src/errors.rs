@@ -20,4 +20,6 @@ pub enum EngineError {
     TransformFieldError(TransformError),
     #[error("Unknown column {0}")]
     UnknownColumnError(UnknownColumnError),
+    #[error("SQL rules are not supported here (column {0})")]
+    SqlRuleError(String),
 }
@@ -1,3 +1,61 @@
+//! The anonymization engine of datanymizer: settings (the config) and transformers.
+//!
+//! The engine can be embedded in other applications to anonymize records with the same rules
+//! as dumps. Settings are loaded from a config (`Settings::new`, `Settings::from_yaml`) or built
+//! with `SettingsBuilder`, which also registers custom transformers for `custom` rules.
+//!
+//! ```
+//! use datanymizer_engine::{
+//!     Engine, SettingsBuilder, TransformContext, TransformResult, TransformResultHelper,
+//!     Transformer,
+//! };
+//! use std::collections::HashMap;
+//!
+//! struct Redact;
+//!
+//! impl Transformer for Redact {
+//!     fn transform(
+//!         &self,
+//!         _field_name: &str,
+//!         field_value: &str,
+//!         _ctx: &Option<TransformContext>,
+//!     ) -> TransformResult {
+//!         TransformResult::present("*".repeat(field_value.chars().count()))
+//!     }
+//! }
+//!
+//! let config = r#"
+//!   tables:
+//!     - name: users
+//!       rules:
+//!         phone:
+//!           custom:
+//!             name: redact
+//!         email:
+//!           email:
+//!             kind: Safe
+//! "#;
+//! let settings = SettingsBuilder::from_yaml(config)
+//!     .unwrap()
+//!     .transformer("redact", Redact)
+//!     .build()
+//!     .unwrap();
+//! let engine = Engine::new(settings);
+//!
+//! let mut record = HashMap::new();
+//! record.insert("id".to_string(), "1".to_string());
+//! record.insert("phone".to_string(), "555-12-34".to_string());
+//! record.insert("email".to_string(), "john@gmail.com".to_string());
+//!
+//! let record = engine.transform_record("users", &record).unwrap();
+//! assert_eq!(record["id"], "1");
+//! assert_eq!(record["phone"], "*********");
+//! assert_ne!(record["email"], "john@gmail.com");
+//!
+//! let phone = engine.transform_value("users", "phone", "12345").unwrap();
+//! assert_eq!(phone, "*****");
+//! ```
+
 mod engine;
 mod errors;
 mod locale;
@@ -10,10 +68,16 @@
 mod value;
 
 pub use engine::Engine;
+pub use errors::EngineError;
 pub use locale::{ExtData, LocaleConfig, Localized, LocalizedFaker};
-pub use settings::{Filter, Query, Settings, Table, TableList, Tables};
+pub use settings::{
+    Filter, ForeignTables, LargeObjectAction, LargeObjectRule, LargeObjects, MaterializedViews,
+    Query, Sample, SampleMethod, Settings, SettingsBuilder, Subset, SubsetRoot, Table, TableList,
+    TablePattern, Tables,
+};
 pub use transformer::{
-    TransformContext, TransformResult, Transformer, TransformerDefaults, TransformerInitContext,
+    TransformContext, TransformError, TransformResult, TransformResultHelper, Transformer,
+    TransformerDefaults, TransformerInitContext,
 };
 pub use transformers::{AsSqlValue, FkTransformer, Transformers};
 pub use value::StringValue;
@@ -0,0 +1,369 @@
+use super::{
+    ForeignTables, LargeObjects, MaterializedViews, Settings, Subset, Table, TemplatesCollection,
+};
+use crate::{
+    transformer::{TransformerDefaults, TransformerInitContext},
+    transformers::CustomTransformers,
+    Filter, Transformer,
+};
+use config::{Config, ConfigError, File, FileFormat};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+/// Builds `Settings` in code (e.g., for applications that embed the engine),
+/// or from a config with additional custom transformers.
+///
+/// # Example:
+///
+/// ```
+/// use datanymizer_engine::{
+///     transformers::EmailTransformer, SettingsBuilder, Table, Transformers,
+/// };
+///
+/// let settings = SettingsBuilder::new()
+///     .table(Table::new("users").with_rule(
+///         "email",
+///         Transformers::Email(EmailTransformer::default()),
+///     ))
+///     .build()
+///     .unwrap();
+/// assert!(settings.get_table("users").is_some());
+/// ```
+pub struct SettingsBuilder {
+    settings: Settings,
+    custom_transformers: CustomTransformers,
+}
+
+impl Default for SettingsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SettingsBuilder {
+    /// Settings without tables
+    pub fn new() -> Self {
+        Self::from_settings(Settings {
+            tables: vec![],
+            table_order: None,
+            default: TransformerDefaults::default(),
+            filter: None,
+            subset: None,
+            materialized_views: Default::default(),
+            foreign_tables: Default::default(),
+            large_objects: None,
+            globals: None,
+            templates: None,
+            transform_map: None,
+            table_patterns: vec![],
+        })
+    }
+
+    /// Settings from the config file (as `Settings::new`)
+    pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+        Self::from_source(File::with_name(path))
+    }
+
+    /// Settings from the YAML config (as `Settings::from_yaml`)
+    pub fn from_yaml(config: &str) -> Result<Self, ConfigError> {
+        Self::from_source(File::from_str(config, FileFormat::Yaml))
+    }
+
+    fn from_source<S>(source: S) -> Result<Self, ConfigError>
+    where
+        S: 'static + config::Source + Send + Sync,
+    {
+        let mut s = Config::new();
+        s.merge(source)?;
+
+        Ok(Self::from_settings(s.try_into()?))
+    }
+
+    fn from_settings(settings: Settings) -> Self {
+        Self {
+            settings,
+            custom_transformers: CustomTransformers::default(),
+        }
+    }
+
+    /// Adds the table config (rules for a table with the same name are replaced)
+    pub fn table(mut self, table: Table) -> Self {
+        self.settings.tables.retain(|t| t.name != table.name);
+        self.settings.tables.push(table);
+        self
+    }
+
+    /// The order of tables in the dump
+    pub fn table_order<T: Into<String>>(mut self, order: Vec<T>) -> Self {
+        self.settings.table_order = Some(order.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Default transformers configuration (e.g., the locale)
+    pub fn defaults(mut self, defaults: TransformerDefaults) -> Self {
+        self.settings.default = defaults;
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.settings.filter = Some(filter);
+        self
+    }
+
+    pub fn subset(mut self, subset: Subset) -> Self {
+        self.settings.subset = Some(subset);
+        self
+    }
+
+    pub fn materialized_views(mut self, materialized_views: MaterializedViews) -> Self {
+        self.settings.materialized_views = materialized_views;
+        self
+    }
+
+    pub fn foreign_tables(mut self, foreign_tables: ForeignTables) -> Self {
+        self.settings.foreign_tables = foreign_tables;
+        self
+    }
+
+    pub fn large_objects(mut self, large_objects: LargeObjects) -> Self {
+        self.settings.large_objects = Some(large_objects);
+        self
+    }
+
+    /// Adds the global value (it is visible in any template)
+    pub fn global<T: Into<String>>(mut self, name: T, value: JsonValue) -> Self {
+        self.settings
+            .globals
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), value);
+        self
+    }
+
+    pub fn templates(mut self, templates: TemplatesCollection) -> Self {
+        self.settings.templates = Some(templates);
+        self
+    }
+
+    /// Registers the transformer for `custom` rules with this name
+    /// (it replaces a transformer with the same name)
+    pub fn transformer<N, T>(mut self, name: N, transformer: T) -> Self
+    where
+        N: Into<String>,
+        T: 'static + Transformer + Send + Sync,
+    {
+        self.custom_transformers
+            .insert(name.into(), Arc::new(transformer));
+        self
+    }
+
+    /// Checks table name patterns and names of custom transformers, and initializes transformers
+    pub fn build(self) -> Result<Settings, ConfigError> {
+        let mut settings = self.settings;
+        settings
+            .compile_patterns()
+            .map_err(|e| ConfigError::Message(format!("Invalid table name pattern: {}", e)))?;
+        // Otherwise unknown transformers would fail only on the first transformed value
+        for table in settings.tables.iter() {
+            let names = table.rules.values().flat_map(|r| r.custom_names());
+            if let Some(name) = names
+                .into_iter()
+                .find(|n| !self.custom_transformers.contains_key(*n))
+            {
+                return Err(ConfigError::Message(format!(
+                    "Unknown custom transformer {} in the table {}",
+                    name, table.name
+                )));
+            }
+        }
+
+        let mut init_ctx = TransformerInitContext::from_defaults(settings.default.clone());
+        // Assign extend templates to context
+        if let Some(collection) = &settings.templates {
+            init_ctx.template_collection = collection.clone();
+        }
+        init_ctx.custom_transformers = self.custom_transformers;
+        settings.preprocess(&init_ctx);
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        settings::SubsetRoot,
+        transformer::{TransformContext, TransformResult, TransformResultHelper},
+        transformers::{CustomTransformer, FirstNameTransformer},
+        LocaleConfig, Transformers,
+    };
+
+    struct Upper;
+
+    impl Transformer for Upper {
+        fn transform(
+            &self,
+            _field_name: &str,
+            field_value: &str,
+            _ctx: &Option<TransformContext>,
+        ) -> TransformResult {
+            TransformResult::present(field_value.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn build() {
+        let settings = SettingsBuilder::new()
+            .table(
+                Table::new("users")
+                    .with_rule(
+                        "name",
+                        Transformers::FirstName(FirstNameTransformer::default()),
+                    )
+                    .with_rule(
+                        "code",
+                        Transformers::Custom(CustomTransformer::new("upper")),
+                    ),
+            )
+            .defaults(TransformerDefaults {
+                locale: LocaleConfig::RU,
+            })
+            .global("company", JsonValue::from("ACME"))
+            .transformer("upper", Upper)
+            .build()
+            .unwrap();
+
+        let rules = &settings.get_table("users").unwrap().rules;
+        assert!(
+            matches!(&rules["name"], Transformers::FirstName(t) if t.locale == Some(LocaleConfig::RU))
+        );
+        assert_eq!(
+            rules["code"].transform("users.code", "abc", &None).unwrap(),
+            Some("ABC".to_string())
+        );
+        assert_eq!(settings.transformers_for("users").unwrap().len(), 2);
+        assert_eq!(
+            settings.globals.unwrap()["company"],
+            JsonValue::from("ACME")
+        );
+    }
+
+    #[test]
+    fn from_yaml() {
+        let config = r#"
+            tables:
+              - name: users
+                rules:
+                  code:
+                    pipeline:
+                      pipes:
+                        - custom:
+                            name: upper
+                        - template:
+                            format: "{{ _0 }}!"
+            "#;
+        let settings = SettingsBuilder::from_yaml(config)
+            .unwrap()
+            .transformer("upper", Upper)
+            .build()
+            .unwrap();
+
+        let rule = &settings.get_table("users").unwrap().rules["code"];
+        assert_eq!(
+            rule.transform("users.code", "abc", &None).unwrap(),
+            Some("ABC!".to_string())
+        );
+    }
+
+    #[test]
+    fn replace_table() {
+        let settings = SettingsBuilder::new()
+            .table(Table::new("users"))
+            .table(Table::new("users").with_rule(
+                "name",
+                Transformers::FirstName(FirstNameTransformer::default()),
+            ))
+            .build()
+            .unwrap();
+
+        assert_eq!(settings.tables.len(), 1);
+        assert_eq!(settings.tables[0].rules.len(), 1);
+    }
+
+    #[test]
+    fn dump_options() {
+        let subset = Subset {
+            roots: vec![SubsetRoot {
+                table: String::from("users"),
+                condition: String::from("id < 10"),
+            }],
+        };
+        let settings = SettingsBuilder::new()
+            .table_order(vec!["users", "orders"])
+            .subset(subset.clone())
+            .materialized_views(MaterializedViews::Skip)
+            .foreign_tables(ForeignTables::Skip)
+            .large_objects(LargeObjects::default())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            settings.table_order,
+            Some(vec![String::from("users"), String::from("orders")])
+        );
+        assert_eq!(settings.subset, Some(subset));
+        assert_eq!(settings.materialized_views, MaterializedViews::Skip);
+        assert_eq!(settings.foreign_tables, ForeignTables::Skip);
+        assert_eq!(settings.large_objects, Some(LargeObjects::default()));
+    }
+
+    #[test]
+    fn unknown_custom() {
+        let config = r#"
+            tables:
+              - name: users
+                rules:
+                  code:
+                    custom:
+                      name: upper
+            "#;
+        let result = SettingsBuilder::from_yaml(config).unwrap().build();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unknown custom transformer upper in the table users"
+        );
+    }
+
+    #[test]
+    fn unknown_nested_custom() {
+        let config = r#"
+            tables:
+              - name: users
+                rules:
+                  code:
+                    pipeline:
+                      pipes:
+                        - custom:
+                            name: upper
+                        - template:
+                            format: "{{ _1 }}"
+                            rules:
+                              - custom:
+                                  name: lower
+            "#;
+        let result = SettingsBuilder::from_yaml(config)
+            .unwrap()
+            .transformer("upper", Upper)
+            .build();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unknown custom transformer lower in the table users"
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let result = SettingsBuilder::new().table(Table::new("/(/")).build();
+        assert!(result.is_err());
+    }
+}
@@ -1,11 +1,21 @@
+use super::TablePattern;
 use serde::Deserialize;
 
 /// Filter for include or exclude tables
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(from = "Config")]
 pub struct Filter {
-    pub schema: Option<TableList>,
-    pub data: Option<TableList>,
+    schema: Option<TableList>,
+    data: Option<TableList>,
+    // Compiled patterns of the lists (not to parse them for every table)
+    schema_patterns: Vec<TablePattern>,
+    data_patterns: Vec<TablePattern>,
+}
+
+impl PartialEq for Filter {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema == other.schema && self.data == other.data
+    }
 }
 
 impl From<Config> for Filter {
@@ -19,28 +29,65 @@ fn from(config: Config) -> Self {
 
 impl From<FullConfig> for Filter {
     fn from(full_config: FullConfig) -> Self {
-        Self {
-            schema: full_config.schema,
-            data: full_config.data,
-        }
+        Self::new(full_config.schema, full_config.data)
     }
 }
 
 impl Filter {
+    pub fn new(schema: Option<TableList>, data: Option<TableList>) -> Self {
+        Self {
+            schema_patterns: Self::compile(&schema),
+            data_patterns: Self::compile(&data),
+            schema,
+            data,
+        }
+    }
+
+    pub fn schema(&self) -> Option<&TableList> {
+        self.schema.as_ref()
+    }
+
+    pub fn data(&self) -> Option<&TableList> {
+        self.data.as_ref()
+    }
+
     pub fn filter_schema(&self, table: &str) -> bool {
-        Self::filter(&self.schema, table)
+        self.filter_schema_names(&[table])
     }
 
     pub fn filter_data(&self, table: &str) -> bool {
-        Self::filter(&self.data, table)
+        self.filter_data_names(&[table])
     }
 
-    fn filter(list: &Option<TableList>, table: &str) -> bool {
-        if let Some(l) = list {
-            l.filter(table)
-        } else {
-            true
-        }
+    /// Filters the schema of a table known by several names (see `TableList::filter_names`)
+    pub fn filter_schema_names<T: AsRef<str>>(&self, names: &[T]) -> bool {
+        Self::filter(&self.schema, &self.schema_patterns, names)
+    }
+
+    /// Filters the data of a table known by several names (see `TableList::filter_names`)
+    pub fn filter_data_names<T: AsRef<str>>(&self, names: &[T]) -> bool {
+        Self::filter(&self.data, &self.data_patterns, names)
+    }
+
+    /// Filters the schema and data of a table known by several names
+    /// (see `TableList::filter_names`)
+    pub fn filter_names<T: AsRef<str>>(&self, names: &[T]) -> bool {
+        self.filter_schema_names(names) && self.filter_data_names(names)
+    }
+
+    fn filter<T: AsRef<str>>(
+        list: &Option<TableList>,
+        patterns: &[TablePattern],
+        names: &[T],
+    ) -> bool {
+        list.as_ref()
+            .is_none_or(|l| l.filter_compiled(patterns, names))
+    }
+
+    fn compile(list: &Option<TableList>) -> Vec<TablePattern> {
+        list.as_ref()
+            .map(|l| TablePattern::compile_all(l.tables()))
+            .unwrap_or_default()
     }
 }
 
@@ -54,9 +101,24 @@ pub enum TableList {
 
 impl TableList {
     pub fn filter(&self, table: &str) -> bool {
+        self.filter_names(&[table])
+    }
+
+    /// Filters a table known by several names (e.g., a partition is also known by the names
+    /// of its parents). It is included if any of the names is in `only`,
+    /// and it is excluded if any of the names is in `except`.
+    pub fn filter_names<T: AsRef<str>>(&self, names: &[T]) -> bool {
+        self.filter_compiled(&TablePattern::compile_all(self.tables()), names)
+    }
+
+    // `patterns` are the compiled `tables`
+    fn filter_compiled<T: AsRef<str>>(&self, patterns: &[TablePattern], names: &[T]) -> bool {
+        let matches = names
+            .iter()
+            .any(|name| patterns.iter().any(|p| p.matches(name.as_ref())));
         match self {
-            Self::Only(tables) => tables.iter().any(|t| t == table),
-            Self::Except(tables) => !tables.iter().any(|t| t == table),
+            Self::Only(_) => matches,
+            Self::Except(_) => !matches,
         }
     }
 
@@ -166,6 +228,31 @@ fn filter() {
             assert!(list.filter(&String::from("users")));
         }
 
+        #[test]
+        fn filter_names() {
+            let partition = ["public.events_2024", "public.events"];
+
+            let list = TableList::Except(vec![String::from("public.events")]);
+            assert!(!list.filter_names(&partition));
+            assert!(list.filter_names(&["public.users"]));
+
+            let list = TableList::Only(vec![String::from("public.events")]);
+            assert!(list.filter_names(&partition));
+            assert!(!list.filter_names(&["public.users"]));
+        }
+
+        #[test]
+        fn filter_patterns() {
+            let list = TableList::Except(vec![
+                String::from("public.events_*"),
+                String::from(r"/^tenant_\d+\.logs$/"),
+            ]);
+            assert!(list.filter("public.users"));
+            assert!(!list.filter("public.events_2024_01"));
+            assert!(!list.filter("tenant_1.logs"));
+            assert!(list.filter("tenant_1.users"));
+        }
+
         #[test]
         fn tables() {
             let tables = vec![String::from("table_a"), String::from("table_b")];
@@ -193,13 +280,13 @@ fn short_deserialization() {
                 "#;
             assert_eq!(
                 deserialize(config),
-                Filter {
-                    schema: None,
-                    data: Some(TableList::Only(vec![
+                Filter::new(
+                    None,
+                    Some(TableList::Only(vec![
                         String::from("table1"),
                         String::from("table2")
-                    ])),
-                }
+                    ]))
+                )
             );
         }
 
@@ -214,13 +301,13 @@ fn full_deserialization_data() {
 
             assert_eq!(
                 deserialize(config),
-                Filter {
-                    schema: None,
-                    data: Some(TableList::Except(vec![
+                Filter::new(
+                    None,
+                    Some(TableList::Except(vec![
                         String::from("table1"),
                         String::from("table2")
-                    ])),
-                }
+                    ]))
+                )
             );
         }
 
@@ -235,13 +322,13 @@ fn full_deserialization_schema() {
 
             assert_eq!(
                 deserialize(config),
-                Filter {
-                    schema: Some(TableList::Only(vec![
+                Filter::new(
+                    Some(TableList::Only(vec![
                         String::from("table1"),
                         String::from("table2")
                     ])),
-                    data: None,
-                }
+                    None
+                )
             );
         }
 
@@ -259,48 +346,67 @@ fn full_deserialization_schema_and_data() {
 
             assert_eq!(
                 deserialize(config),
-                Filter {
-                    schema: Some(TableList::Except(vec![
+                Filter::new(
+                    Some(TableList::Except(vec![
                         String::from("table1"),
                         String::from("table2")
                     ])),
-                    data: Some(TableList::Only(vec![String::from("table1"),])),
-                }
+                    Some(TableList::Only(vec![String::from("table1"),]))
+                )
             );
         }
 
         #[test]
         fn filter_schema() {
-            let filter = Filter {
-                schema: Some(TableList::Except(vec![String::from("table1")])),
-                data: None,
-            };
+            let filter = Filter::new(Some(TableList::Except(vec![String::from("table1")])), None);
             assert!(!filter.filter_schema(&String::from("table1")));
             assert!(filter.filter_schema(&String::from("table2")));
 
-            let filter = Filter {
-                schema: None,
-                data: Some(TableList::Except(vec![String::from("table1")])),
-            };
+            let filter = Filter::new(None, Some(TableList::Except(vec![String::from("table1")])));
             assert!(filter.filter_schema(&String::from("table1")));
             assert!(filter.filter_schema(&String::from("table2")));
         }
 
         #[test]
         fn filter_data() {
-            let filter = Filter {
-                schema: Some(TableList::Except(vec![String::from("table1")])),
-                data: None,
-            };
+            let filter = Filter::new(Some(TableList::Except(vec![String::from("table1")])), None);
             assert!(filter.filter_data(&String::from("table1")));
             assert!(filter.filter_data(&String::from("table2")));
 
-            let filter = Filter {
-                schema: None,
-                data: Some(TableList::Only(vec![String::from("table1")])),
-            };
+            let filter = Filter::new(None, Some(TableList::Only(vec![String::from("table1")])));
             assert!(filter.filter_data(&String::from("table1")));
             assert!(!filter.filter_data(&String::from("table2")));
         }
+
+        #[test]
+        fn filter_names() {
+            let filter = Filter::new(
+                Some(TableList::Except(vec![String::from("events")])),
+                Some(TableList::Except(vec![String::from("logs")])),
+            );
+            assert!(!filter.filter_names(&["events_1", "events"]));
+            assert!(!filter.filter_names(&["logs_1", "logs"]));
+            assert!(filter.filter_names(&["users"]));
+        }
+
+        #[test]
+        fn filter_patterns() {
+            let filter = deserialize(
+                r#"
+                schema:
+                  except:
+                    - public.events_*
+                data:
+                  only:
+                    - /^public\.(users|posts)$/
+                "#,
+            );
+            assert!(!filter.filter_schema("public.events_2024"));
+            assert!(filter.filter_schema("public.events"));
+            assert!(filter.filter_data("public.users"));
+            assert!(!filter.filter_data("public.users_2024"));
+            assert!(filter.filter_data_names(&["public.posts_1", "public.posts"]));
+            assert!(!filter.filter_schema_names(&["public.events_1", "public.events"]));
+        }
     }
 }
@@ -0,0 +1,78 @@
+use serde::Deserialize;
+
+/// Rules for large objects (`pg_largeobject`)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct LargeObjects {
+    /// Action for large objects not matched by any rule
+    #[serde(default)]
+    pub default: LargeObjectAction,
+    /// Rules are checked in the config order, the first matching one is applied
+    #[serde(default)]
+    pub rules: Vec<LargeObjectRule>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LargeObjectRule {
+    /// OIDs of large objects
+    #[serde(default)]
+    pub oids: Vec<u32>,
+    /// SQL query returning OIDs of large objects (e.g., from a column with attachments)
+    pub query: Option<String>,
+    pub action: LargeObjectAction,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeObjectAction {
+    /// Dump the original content
+    Keep,
+    /// Don't dump the object at all
+    Drop,
+    /// Dump zero bytes instead of the content (the size is kept)
+    #[default]
+    Placeholder,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let config = r#"
+            default: drop
+            rules:
+              - oids: [16400, 16401]
+                action: keep
+              - query: "SELECT attachment FROM documents WHERE public"
+                action: placeholder
+            "#;
+        let cfg: LargeObjects = serde_yaml::from_str(config).unwrap();
+
+        assert_eq!(
+            cfg,
+            LargeObjects {
+                default: LargeObjectAction::Drop,
+                rules: vec![
+                    LargeObjectRule {
+                        oids: vec![16400, 16401],
+                        query: None,
+                        action: LargeObjectAction::Keep,
+                    },
+                    LargeObjectRule {
+                        oids: vec![],
+                        query: Some("SELECT attachment FROM documents WHERE public".to_string()),
+                        action: LargeObjectAction::Placeholder,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn default_action() {
+        let cfg: LargeObjects = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(cfg.default, LargeObjectAction::Placeholder);
+        assert!(cfg.rules.is_empty());
+    }
+}
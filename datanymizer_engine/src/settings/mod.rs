@@ -1,4 +1,10 @@
+mod builder;
 mod filter;
+mod large_objects;
+mod pattern;
+mod relations;
+mod sample;
+mod subset;
 mod table;
 mod templates;
 
@@ -8,12 +14,18 @@
     Transformer,
 };
 use anyhow::Result;
-use config::{Config, ConfigError, File, FileFormat};
+use config::ConfigError;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 
+pub use builder::SettingsBuilder;
 pub use filter::{Filter, TableList};
+pub use large_objects::{LargeObjectAction, LargeObjectRule, LargeObjects};
+pub use pattern::TablePattern;
+pub use relations::{ForeignTables, MaterializedViews};
+pub use sample::{Sample, SampleMethod};
+pub use subset::{Subset, SubsetRoot};
 pub use table::{Query, Table};
 pub use templates::TemplatesCollection;
 
@@ -26,7 +38,7 @@ pub struct Settings {
     /// Tables list with transformation rules
     pub tables: Tables,
 
-    /// Table order. All tables not listed are dumping at the beginning
+    /// Table order (names or patterns). All tables not listed are dumping at the beginning
     pub table_order: Option<Vec<String>>,
 
     /// Default transformers configuration
@@ -35,6 +47,20 @@ pub struct Settings {
 
     pub filter: Option<Filter>,
 
+    /// Dump only a referentially complete slice of the database
+    pub subset: Option<Subset>,
+
+    /// How to handle materialized views
+    #[serde(default)]
+    pub materialized_views: MaterializedViews,
+
+    /// How to handle foreign tables
+    #[serde(default)]
+    pub foreign_tables: ForeignTables,
+
+    /// Rules for large objects (they are not dumped with data if there are no rules)
+    pub large_objects: Option<LargeObjects>,
+
     /// Global values. Visible in any template.
     /// They may be shadowed by template variables.
     pub globals: Option<HashMap<String, JsonValue>>,
@@ -43,28 +69,19 @@ pub struct Settings {
 
     #[serde(skip)]
     transform_map: Option<HashMap<String, TransformList>>,
+
+    /// Compiled name patterns of `tables` (in the same order)
+    #[serde(skip)]
+    table_patterns: Vec<TablePattern>,
 }
 
 impl Settings {
     pub fn new(path: String) -> Result<Self, ConfigError> {
-        Self::from_source(File::with_name(&path))
+        SettingsBuilder::from_file(&path)?.build()
     }
 
     pub fn from_yaml(config: &str) -> Result<Self, ConfigError> {
-        Self::from_source(File::from_str(config, FileFormat::Yaml))
-    }
-
-    fn from_source<S: 'static>(source: S) -> Result<Self, ConfigError>
-    where
-        S: config::Source + Send + Sync,
-    {
-        let mut s = Config::new();
-        s.merge(source)?;
-
-        let mut settings: Self = s.try_into()?;
-        settings.preprocess();
-
-        Ok(settings)
+        SettingsBuilder::from_yaml(config)?.build()
     }
 
     pub fn transformers_for(&self, table: &str) -> Option<&TransformList> {
@@ -79,6 +96,9 @@ pub fn get_table(&self, name: &str) -> Option<&Table> {
         self.tables.iter().find(|t| t.name == name)
     }
 
+    /// Finds a table config by the table names (e.g., qualified and unqualified).
+    /// An exact name match takes precedence over patterns, otherwise the first table config
+    /// (in the config order) with a matching glob or regex pattern is returned.
     pub fn find_table<T: AsRef<str>>(&self, names: &[T]) -> Option<&Table> {
         for name in names {
             let table = self.get_table(name.as_ref());
@@ -86,20 +106,39 @@ pub fn find_table<T: AsRef<str>>(&self, names: &[T]) -> Option<&Table> {
                 return table;
             }
         }
-        None
-    }
 
-    fn preprocess(&mut self) {
-        let mut init_ctx = TransformerInitContext::from_defaults(self.default.clone());
+        self.tables
+            .iter()
+            .zip(self.table_patterns.iter())
+            .find(|(_, p)| !p.is_exact() && names.iter().any(|n| p.matches(n.as_ref())))
+            .map(|(t, _)| t)
+    }
 
-        // Assign extend templates to context
-        if let Some(collection) = &self.templates {
-            init_ctx.template_collection = collection.clone();
+    fn compile_patterns(&mut self) -> Result<(), regex::Error> {
+        self.table_patterns = self
+            .tables
+            .iter()
+            .map(|t| TablePattern::parse(&t.name))
+            .collect::<Result<_, _>>()?;
+
+        // Check other patterns
+        let mut patterns: Vec<&String> = self.table_order.iter().flatten().collect();
+        if let Some(filter) = &self.filter {
+            for list in [filter.schema(), filter.data()].into_iter().flatten() {
+                patterns.extend(list.tables());
+            }
         }
+        for p in patterns {
+            TablePattern::parse(p)?;
+        }
+
+        Ok(())
+    }
 
+    fn preprocess(&mut self, init_ctx: &TransformerInitContext) {
         for table in self.tables.iter_mut() {
             for (_name, rule) in table.rules.iter_mut() {
-                rule.init(&init_ctx);
+                rule.init(init_ctx);
             }
         }
 
@@ -185,6 +224,60 @@ fn find_table() {
         assert_eq!(t.unwrap().name, "other_schema.users");
     }
 
+    #[test]
+    fn find_table_by_pattern() {
+        let config = r#"
+            tables:
+              - name: public.events_*
+                rules:
+                  name:
+                    person_name: {}
+              - name: /^(public|archive)\.events_\d+$/
+                rules:
+                  name:
+                    first_name: {}
+              - name: public.events_special
+                rules:
+                  name:
+                    last_name: {}
+            "#;
+        let s = Settings::from_yaml(config).unwrap();
+
+        // The first matching pattern wins
+        let t = s.find_table(&["public.events_2024_01", "events_2024_01"]);
+        assert_eq!(t.unwrap().name, "public.events_*");
+
+        let t = s.find_table(&["archive.events_1", "events_1"]);
+        assert_eq!(t.unwrap().name, r"/^(public|archive)\.events_\d+$/");
+
+        // An exact name takes precedence over patterns
+        let t = s.find_table(&["public.events_special", "events_special"]);
+        assert_eq!(t.unwrap().name, "public.events_special");
+
+        let t = s.find_table(&["public.users", "users"]);
+        assert!(t.is_none());
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let config = r#"
+            tables:
+              - name: /events_(\d+/
+                rules:
+                  name:
+                    person_name: {}
+            "#;
+        assert!(Settings::from_yaml(config).is_err());
+
+        let config = r#"
+            tables: []
+            filter:
+              only:
+                - /events_(\d+/
+            "#;
+        assert!(Settings::from_yaml(config).is_err());
+    }
+
     mod transformers_for {
         use super::*;
 
@@ -240,6 +333,76 @@ fn order() {
         }
     }
 
+    #[test]
+    fn subset() {
+        let config = r#"
+            tables: []
+            subset:
+              roots:
+                - table: public.users
+                  condition: "id < 1000"
+                - table: companies
+                  condition: "name = 'Acme'"
+            "#;
+        let s = Settings::from_yaml(config).unwrap();
+
+        assert_eq!(
+            s.subset.unwrap().roots,
+            vec![
+                SubsetRoot {
+                    table: "public.users".to_string(),
+                    condition: "id < 1000".to_string(),
+                },
+                SubsetRoot {
+                    table: "companies".to_string(),
+                    condition: "name = 'Acme'".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn query_sample() {
+        let config = r#"
+            tables:
+              - name: users
+                rules: {}
+                query:
+                  sample: 5%
+              - name: orders
+                rules: {}
+                query:
+                  sample:
+                    percent: 2
+                    method: system
+                    seed: 7
+            "#;
+        let s = Settings::from_yaml(config).unwrap();
+
+        let sample = |name: &str| {
+            s.get_table(name)
+                .and_then(|t| t.query.clone())
+                .and_then(|q| q.sample)
+                .unwrap()
+        };
+        assert_eq!(
+            sample("users"),
+            Sample {
+                percent: 5.0,
+                method: SampleMethod::Bernoulli,
+                seed: None
+            }
+        );
+        assert_eq!(
+            sample("orders"),
+            Sample {
+                percent: 2.0,
+                method: SampleMethod::System,
+                seed: Some(7)
+            }
+        );
+    }
+
     mod templates_for {
         use super::*;
 
@@ -0,0 +1,128 @@
+use regex::Regex;
+
+/// Table name pattern (in table configs, filters and the table order).
+/// It can be:
+/// - an exact name (`users`, `public.users`);
+/// - a glob (`public.events_*`), where `*` matches any characters and `?` matches one character
+///   (except `.`, the schema separator);
+/// - a regular expression between slashes (`/^tenant_\d+\.users$/`).
+#[derive(Clone, Debug)]
+pub enum TablePattern {
+    Exact(String),
+    Glob(Regex),
+    Regex(Regex),
+}
+
+impl TablePattern {
+    pub fn parse(pattern: &str) -> Result<Self, regex::Error> {
+        if let Some(re) = regex_body(pattern) {
+            Ok(Self::Regex(Regex::new(re)?))
+        } else if Self::is_glob(pattern) {
+            Ok(Self::Glob(Regex::new(&glob_to_regex(pattern))?))
+        } else {
+            Ok(Self::Exact(pattern.to_string()))
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Exact(n) => n == name,
+            Self::Glob(re) | Self::Regex(re) => re.is_match(name),
+        }
+    }
+
+    /// Parses the patterns skipping invalid ones (so they never match)
+    pub fn compile_all<T: AsRef<str>>(patterns: &[T]) -> Vec<Self> {
+        patterns
+            .iter()
+            .filter_map(|p| Self::parse(p.as_ref()).ok())
+            .collect()
+    }
+
+    /// Is the pattern a glob (contains wildcards)?
+    pub fn is_glob(pattern: &str) -> bool {
+        regex_body(pattern).is_none() && pattern.contains(['*', '?'])
+    }
+
+    /// Is the pattern a regular expression (`/.../`)?
+    pub fn is_regex(pattern: &str) -> bool {
+        regex_body(pattern).is_some()
+    }
+}
+
+fn regex_body(pattern: &str) -> Option<&str> {
+    if pattern.len() > 1 {
+        pattern.strip_prefix('/')?.strip_suffix('/')
+    } else {
+        None
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str("[^.]*"),
+            '?' => re.push_str("[^.]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact() {
+        let p = TablePattern::parse("public.users").unwrap();
+        assert!(p.is_exact());
+        assert!(p.matches("public.users"));
+        assert!(!p.matches("public.users2"));
+    }
+
+    #[test]
+    fn glob() {
+        let p = TablePattern::parse("public.events_*").unwrap();
+        assert!(!p.is_exact());
+        assert!(p.matches("public.events_2024_01"));
+        assert!(p.matches("public.events_"));
+        assert!(!p.matches("other.events_2024_01"));
+        assert!(!p.matches("public.events_2024.x"));
+
+        let p = TablePattern::parse("*.users").unwrap();
+        assert!(p.matches("tenant_1.users"));
+        assert!(!p.matches("users"));
+
+        let p = TablePattern::parse("log_?").unwrap();
+        assert!(p.matches("log_1"));
+        assert!(!p.matches("log_10"));
+    }
+
+    #[test]
+    fn regex() {
+        let p = TablePattern::parse(r"/^tenant_\d+\.users$/").unwrap();
+        assert!(!p.is_exact());
+        assert!(p.matches("tenant_12.users"));
+        assert!(!p.matches("tenant_x.users"));
+
+        assert!(TablePattern::parse("/(/").is_err());
+        assert!(TablePattern::parse("/").unwrap().is_exact());
+    }
+
+    #[test]
+    fn compile_all() {
+        let patterns = TablePattern::compile_all(&["users", "public.events_*", "/(/"]);
+        assert_eq!(patterns.len(), 2);
+        let any_matches = |name| patterns.iter().any(|p| p.matches(name));
+        assert!(any_matches("users"));
+        assert!(any_matches("public.events_1"));
+        assert!(!any_matches("public.users"));
+    }
+}
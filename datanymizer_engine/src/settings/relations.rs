@@ -0,0 +1,55 @@
+use serde::Deserialize;
+
+/// How to handle materialized views
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterializedViews {
+    /// Refresh views after restoring data (they are computed from the anonymized data)
+    #[default]
+    Refresh,
+    /// Restore views without data (they must be refreshed manually)
+    Skip,
+    /// Dump rows of views (with transformation rules) into tables with the same names
+    /// (views are not refreshed)
+    Dump,
+}
+
+/// How to handle foreign tables
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForeignTables {
+    /// Dump definitions of foreign tables (but not their data)
+    #[default]
+    Keep,
+    /// Skip foreign tables completely
+    Skip,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        assert_eq!(
+            serde_yaml::from_str::<MaterializedViews>("skip").unwrap(),
+            MaterializedViews::Skip
+        );
+        assert_eq!(
+            serde_yaml::from_str::<MaterializedViews>("refresh").unwrap(),
+            MaterializedViews::Refresh
+        );
+        assert_eq!(
+            serde_yaml::from_str::<MaterializedViews>("dump").unwrap(),
+            MaterializedViews::Dump
+        );
+        assert_eq!(
+            serde_yaml::from_str::<ForeignTables>("skip").unwrap(),
+            ForeignTables::Skip
+        );
+        assert_eq!(
+            serde_yaml::from_str::<ForeignTables>("keep").unwrap(),
+            ForeignTables::Keep
+        );
+    }
+}
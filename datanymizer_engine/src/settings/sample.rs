@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+/// Percentage sampling of table rows
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "Config")]
+pub struct Sample {
+    /// Percent of rows (greater than 0 and not greater than 100)
+    pub percent: f64,
+    pub method: SampleMethod,
+    /// Seed for a repeatable sample
+    pub seed: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleMethod {
+    /// Row-level random sampling (`TABLESAMPLE BERNOULLI`)
+    #[default]
+    Bernoulli,
+    /// Block-level random sampling (`TABLESAMPLE SYSTEM`), faster, but less random
+    System,
+    /// Sampling by the primary key hash (the same rows are selected every time)
+    Hash,
+}
+
+impl TryFrom<Config> for Sample {
+    type Error = String;
+
+    fn try_from(config: Config) -> Result<Self, Self::Error> {
+        let full_config = match config {
+            Config::Number(percent) => FullConfig::from(percent),
+            Config::Short(percent) => FullConfig::from(parse_percent(&percent)?),
+            Config::Full(full_config) => full_config,
+        };
+
+        if !(full_config.percent > 0.0 && full_config.percent <= 100.0) {
+            return Err(format!(
+                "Sample percent must be in (0, 100], got {}",
+                full_config.percent
+            ));
+        }
+
+        Ok(Self {
+            percent: full_config.percent,
+            method: full_config.method,
+            seed: full_config.seed,
+        })
+    }
+}
+
+fn parse_percent(s: &str) -> Result<f64, String> {
+    s.trim()
+        .trim_end_matches('%')
+        .trim_end()
+        .parse()
+        .map_err(|_| format!("Invalid sample percent: {}", s))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Config {
+    Number(f64),
+    Short(String),
+    Full(FullConfig),
+}
+
+#[derive(Deserialize)]
+struct FullConfig {
+    percent: f64,
+    #[serde(default)]
+    method: SampleMethod,
+    seed: Option<u32>,
+}
+
+impl From<f64> for FullConfig {
+    fn from(percent: f64) -> Self {
+        Self {
+            percent,
+            method: SampleMethod::default(),
+            seed: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deserialize(config: &str) -> Result<Sample, serde_yaml::Error> {
+        serde_yaml::from_str(config)
+    }
+
+    #[test]
+    fn short() {
+        let expected = Sample {
+            percent: 5.0,
+            method: SampleMethod::Bernoulli,
+            seed: None,
+        };
+        assert_eq!(deserialize("5%").unwrap(), expected);
+        assert_eq!(deserialize("5").unwrap(), expected);
+        assert_eq!(deserialize("\"5 %\"").unwrap(), expected);
+        assert_eq!(deserialize("0.5%").unwrap().percent, 0.5);
+    }
+
+    #[test]
+    fn full() {
+        let config = r#"
+            percent: 10
+            method: hash
+            seed: 42
+            "#;
+        assert_eq!(
+            deserialize(config).unwrap(),
+            Sample {
+                percent: 10.0,
+                method: SampleMethod::Hash,
+                seed: Some(42),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(deserialize("0%").is_err());
+        assert!(deserialize("101%").is_err());
+        assert!(deserialize("five%").is_err());
+        assert!(deserialize("{percent: 5, method: random}").is_err());
+    }
+}
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+/// Subsetting configuration: dump only rows related (by foreign keys) to the root rows
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Subset {
+    /// Tables (with conditions) from which subsetting starts
+    pub roots: Vec<SubsetRoot>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SubsetRoot {
+    /// Table name (with or without schema)
+    pub table: String,
+    /// SQL condition (WHERE) for root rows
+    pub condition: String,
+}
@@ -1,4 +1,4 @@
-use super::TransformList;
+use super::{Sample, TransformList};
 use crate::Transformers;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -9,6 +9,11 @@
 pub struct Query {
     /// SQL limit
     pub limit: Option<usize>,
+    /// Percentage sampling of rows (e.g., `5%`)
+    pub sample: Option<Sample>,
+    /// SQL order (ORDER BY) for dumping, e.g. `created_at DESC`.
+    /// If it is not set, rows are ordered by the primary key when `limit` is used.
+    pub order_by: Option<String>,
     /// SQL condition (WHERE) for dumping
     pub dump_condition: Option<String>,
     /// SQL condition (WHERE) for transforming (anonymizing)
@@ -25,9 +30,33 @@ pub struct Table {
     pub rule_order: Option<Vec<String>>,
     /// Limit and conditions for the dumping query
     pub query: Option<Query>,
+    /// Columns dumped as NULL (their values are not read from the database)
+    #[serde(default)]
+    pub exclude_columns: Vec<String>,
+    /// Columns omitted from the dump (they get default values when restoring)
+    #[serde(default)]
+    pub drop_columns: Vec<String>,
 }
 
 impl Table {
+    /// A table config without rules
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            rules: Rules::new(),
+            rule_order: None,
+            query: None,
+            exclude_columns: vec![],
+            drop_columns: vec![],
+        }
+    }
+
+    /// Adds the rule for the column (it replaces an existing rule)
+    pub fn with_rule<T: Into<String>>(mut self, column: T, rule: Transformers) -> Self {
+        self.rules.insert(column.into(), rule);
+        self
+    }
+
     pub fn transform_list(&self) -> TransformList {
         let explicit_rule_order = self.rule_order.clone().unwrap_or_default();
         let mut transform_list: TransformList = self
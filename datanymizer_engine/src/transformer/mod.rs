@@ -16,7 +16,7 @@
     sync::{Arc, RwLock},
 };
 
-use crate::{settings::TemplatesCollection, LocaleConfig};
+use crate::{settings::TemplatesCollection, transformers::CustomTransformers, LocaleConfig};
 
 pub type TransformResult = result::Result<Option<String>, TransformError>;
 pub type Globals = HashMap<String, Value>;
@@ -40,6 +40,7 @@ pub struct TransformerInitContext {
     pub defaults: TransformerDefaults,
     pub template_store: TemplateStore,
     pub template_collection: TemplatesCollection,
+    pub custom_transformers: CustomTransformers,
 }
 
 impl TransformerInitContext {
@@ -48,6 +49,7 @@ pub fn from_defaults(defaults: TransformerDefaults) -> Self {
             defaults,
             template_store: TemplateStore::default(),
             template_collection: TemplatesCollection::default(),
+            custom_transformers: CustomTransformers::default(),
         }
     }
 }
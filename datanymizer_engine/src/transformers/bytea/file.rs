@@ -0,0 +1,102 @@
+use super::{encode, hex_digits};
+use crate::transformer::{TransformContext, TransformResult, TransformResultHelper, Transformer};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs};
+
+/// Replaces a `bytea` value with the content of a file (e.g., a stock avatar image).
+/// The file is read once, when the config is loaded. NULL values are kept.
+///
+/// # Example:
+///
+/// ```yaml
+/// #...
+/// rules:
+///   avatar:
+///     bytea_file:
+///       path: ./fixtures/avatar.png
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+#[serde(try_from = "ByteaFileConfig")]
+pub struct ByteaFileTransformer {
+    /// Path to the file
+    pub path: String,
+
+    /// The file content in the hex format
+    #[serde(skip)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ByteaFileConfig {
+    path: String,
+}
+
+impl TryFrom<ByteaFileConfig> for ByteaFileTransformer {
+    type Error = String;
+
+    fn try_from(cfg: ByteaFileConfig) -> Result<Self, Self::Error> {
+        Self::new(cfg.path)
+    }
+}
+
+impl ByteaFileTransformer {
+    pub fn new<T: Into<String>>(path: T) -> Result<Self, String> {
+        let path = path.into();
+        let bytes = fs::read(&path).map_err(|e| format!("can't read file `{}`: {}", path, e))?;
+        Ok(Self {
+            path,
+            content: encode(&bytes),
+        })
+    }
+}
+
+impl Transformer for ByteaFileTransformer {
+    fn transform(
+        &self,
+        field_name: &str,
+        field_value: &str,
+        _ctx: &Option<TransformContext>,
+    ) -> TransformResult {
+        match hex_digits(field_name, field_value) {
+            Ok(Some(_)) => TransformResult::present(&self.content),
+            Ok(None) => Ok(None),
+            Err(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transformers;
+    use std::{env, process};
+
+    fn fixture(name: &str, content: &[u8]) -> String {
+        let path = env::temp_dir().join(format!("{}_{}", process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn replace() {
+        let path = fixture("avatar.bin", &[0x89, 0x50, 0x4e, 0x47, 0]);
+        let config = format!("bytea_file:\n  path: {}", path);
+        let transformer: Transformers = serde_yaml::from_str(&config).unwrap();
+
+        assert_eq!(
+            transformer
+                .transform("avatar", "\\\\x0a0b", &None)
+                .unwrap()
+                .unwrap(),
+            "\\x89504e4700"
+        );
+        assert_eq!(transformer.transform("avatar", "\\N", &None).unwrap(), None);
+    }
+
+    #[test]
+    fn missing_file() {
+        let config = "bytea_file:\n  path: /nonexistent/avatar.png";
+        let err = serde_yaml::from_str::<Transformers>(config).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/avatar.png"));
+    }
+}
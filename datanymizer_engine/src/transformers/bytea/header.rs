@@ -0,0 +1,99 @@
+use super::{encode, hex_digits};
+use crate::transformer::{TransformContext, TransformResult, TransformResultHelper, Transformer};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LENGTH: usize = 16;
+
+/// Keeps only the first `len` bytes of a `bytea` value (default is 16).
+/// This is enough to preserve the content type signature (e.g., PNG or PDF magic bytes)
+/// and drop the content itself.
+///
+/// # Examples
+///
+/// With defaults:
+/// ```yaml
+/// #...
+/// rules:
+///   field_name:
+///     bytea_header: {}
+/// ```
+///
+/// with a custom length:
+/// ```yaml
+/// #...
+/// rules:
+///   field_name:
+///     bytea_header:
+///       len: 8
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+#[serde(default)]
+pub struct ByteaHeaderTransformer {
+    /// Length (in bytes)
+    pub len: usize,
+}
+
+impl Default for ByteaHeaderTransformer {
+    fn default() -> Self {
+        Self {
+            len: DEFAULT_LENGTH,
+        }
+    }
+}
+
+impl Transformer for ByteaHeaderTransformer {
+    fn transform(
+        &self,
+        field_name: &str,
+        field_value: &str,
+        _ctx: &Option<TransformContext>,
+    ) -> TransformResult {
+        match hex_digits(field_name, field_value) {
+            Ok(Some(digits)) => {
+                let end = digits.len().min(self.len * 2);
+                TransformResult::present(encode(&super::decode(&digits[..end])))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transformers;
+
+    #[test]
+    fn deserialize() {
+        let transformer: Transformers = serde_yaml::from_str("bytea_header: {}").unwrap();
+        assert_eq!(
+            transformer,
+            Transformers::ByteaHeader(ByteaHeaderTransformer { len: 16 })
+        );
+    }
+
+    #[test]
+    fn truncate() {
+        let transformer = ByteaHeaderTransformer { len: 4 };
+        assert_eq!(
+            transformer
+                .transform("data", "\\\\x89504E470d0a1a0a", &None)
+                .unwrap()
+                .unwrap(),
+            "\\x89504e47"
+        );
+    }
+
+    #[test]
+    fn shorter_value() {
+        let transformer = ByteaHeaderTransformer::default();
+        assert_eq!(
+            transformer
+                .transform("data", "\\x2550", &None)
+                .unwrap()
+                .unwrap(),
+            "\\x2550"
+        );
+    }
+}
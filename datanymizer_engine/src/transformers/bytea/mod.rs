@@ -0,0 +1,89 @@
+//! Transformers for binary (`bytea`) values.
+//!
+//! PostgreSQL outputs `bytea` values in the hex format (`\x0a0b...`). In the COPY text format
+//! the backslash is escaped, so transformers can receive `\\x0a0b...` as well.
+//! The results are returned in the hex format (with one backslash),
+//! the dumper escapes them for COPY.
+
+mod file;
+pub use file::ByteaFileTransformer;
+
+mod header;
+pub use header::ByteaHeaderTransformer;
+
+mod random;
+pub use random::ByteaRandomTransformer;
+
+use crate::transformer::{TransformResult, TransformResultHelper};
+use std::fmt::Write;
+
+const NULL_VALUE: &str = "\\N";
+
+/// Returns the hex digits of the value (without the prefix) or an error result
+/// if the value isn't in the hex format. `None` means the NULL value.
+fn hex_digits<'a>(
+    field_name: &str,
+    field_value: &'a str,
+) -> Result<Option<&'a str>, TransformResult> {
+    if field_value == NULL_VALUE {
+        return Ok(None);
+    }
+
+    let digits = field_value
+        .strip_prefix("\\\\x")
+        .or_else(|| field_value.strip_prefix("\\x"));
+    match digits {
+        Some(digits) if digits.len() % 2 == 0 && digits.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Ok(Some(digits))
+        }
+        _ => Err(TransformResult::error(
+            field_name,
+            field_value,
+            "The value is not a bytea in the hex format (set `bytea_output` to `hex`)",
+        )),
+    }
+}
+
+fn decode(digits: &str) -> Vec<u8> {
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap_or_default())
+        .collect()
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2 + 2);
+    hex.push_str("\\x");
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits() {
+        assert_eq!(hex_digits("f", "\\x0a0B").unwrap(), Some("0a0B"));
+        assert_eq!(hex_digits("f", "\\\\x0a0b").unwrap(), Some("0a0b"));
+        assert_eq!(hex_digits("f", "\\x").unwrap(), Some(""));
+        assert_eq!(hex_digits("f", "\\N").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_digits() {
+        assert!(hex_digits("f", "\\001\\002").is_err());
+        assert!(hex_digits("f", "\\x0a0").is_err());
+        assert!(hex_digits("f", "\\x0g").is_err());
+        assert!(hex_digits("f", "text").is_err());
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let bytes = decode("00ff0A");
+        assert_eq!(bytes, vec![0, 255, 10]);
+        assert_eq!(encode(&bytes), "\\x00ff0a");
+    }
+}
@@ -0,0 +1,70 @@
+use super::{encode, hex_digits};
+use crate::transformer::{TransformContext, TransformResult, TransformResultHelper, Transformer};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Replaces a `bytea` value with random bytes of the same length.
+///
+/// # Example:
+///
+/// ```yaml
+/// #...
+/// rules:
+///   field_name:
+///     bytea_random: {}
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug, Default)]
+pub struct ByteaRandomTransformer {}
+
+impl Transformer for ByteaRandomTransformer {
+    fn transform(
+        &self,
+        field_name: &str,
+        field_value: &str,
+        _ctx: &Option<TransformContext>,
+    ) -> TransformResult {
+        match hex_digits(field_name, field_value) {
+            Ok(Some(digits)) => {
+                let mut bytes = vec![0; digits.len() / 2];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                TransformResult::present(encode(&bytes))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transformers;
+
+    fn transform(value: &str) -> TransformResult {
+        let transformer: Transformers = serde_yaml::from_str("bytea_random: {}").unwrap();
+        transformer.transform("data", value, &None)
+    }
+
+    #[test]
+    fn same_length() {
+        let value = transform("\\\\x89504e470d0a1a0a").unwrap().unwrap();
+        assert!(value.starts_with("\\x"));
+        assert_eq!(value.len(), 18);
+        assert_ne!(value, "\\x89504e470d0a1a0a");
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(transform("\\x").unwrap().unwrap(), "\\x");
+    }
+
+    #[test]
+    fn null() {
+        assert_eq!(transform("\\N").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(transform("\\001").is_err());
+    }
+}
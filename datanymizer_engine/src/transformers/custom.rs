@@ -0,0 +1,129 @@
+use crate::transformer::{
+    TransformContext, TransformResult, TransformResultHelper, Transformer, TransformerInitContext,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Transformers registered by applications that embed the engine (name -> transformer)
+pub type CustomTransformers = HashMap<String, Arc<dyn Transformer + Send + Sync>>;
+
+/// Calls a transformer registered with `SettingsBuilder::transformer`.
+///
+/// # Example:
+///
+/// ```yaml
+/// #...
+/// rules:
+///   email:
+///     custom:
+///       name: hash_email
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct CustomTransformer {
+    /// The name of the registered transformer
+    pub name: String,
+
+    #[serde(skip)]
+    transformer: Option<Arc<dyn Transformer + Send + Sync>>,
+}
+
+impl CustomTransformer {
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            transformer: None,
+        }
+    }
+}
+
+impl PartialEq for CustomTransformer {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CustomTransformer {}
+
+impl Hash for CustomTransformer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl Debug for CustomTransformer {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CustomTransformer")
+            .field("name", &self.name)
+            .field("registered", &self.transformer.is_some())
+            .finish()
+    }
+}
+
+impl Transformer for CustomTransformer {
+    fn transform(
+        &self,
+        field_name: &str,
+        field_value: &str,
+        ctx: &Option<TransformContext>,
+    ) -> TransformResult {
+        match &self.transformer {
+            Some(t) => t.transform(field_name, field_value, ctx),
+            None => TransformResult::error(
+                field_name,
+                field_value,
+                format!("Unknown custom transformer: {}", self.name).as_str(),
+            ),
+        }
+    }
+
+    fn init(&mut self, ctx: &TransformerInitContext) {
+        self.transformer = ctx.custom_transformers.get(&self.name).cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Reverse;
+
+    impl Transformer for Reverse {
+        fn transform(
+            &self,
+            _field_name: &str,
+            field_value: &str,
+            _ctx: &Option<TransformContext>,
+        ) -> TransformResult {
+            TransformResult::present(field_value.chars().rev().collect::<String>())
+        }
+    }
+
+    #[test]
+    fn parse_and_transform() {
+        let config = "name: reverse";
+        let mut transformer: CustomTransformer = serde_yaml::from_str(config).unwrap();
+        assert_eq!(transformer, CustomTransformer::new("reverse"));
+
+        let mut ctx = TransformerInitContext::default();
+        ctx.custom_transformers
+            .insert("reverse".to_string(), Arc::new(Reverse));
+        transformer.init(&ctx);
+
+        let value = transformer.transform("field", "abc", &None).unwrap();
+        assert_eq!(value, Some("cba".to_string()));
+    }
+
+    #[test]
+    fn unknown() {
+        let mut transformer = CustomTransformer::new("unknown");
+        transformer.init(&TransformerInitContext::default());
+
+        let err = transformer.transform("field", "abc", &None).unwrap_err();
+        assert_eq!(err.reason, "Unknown custom transformer: unknown");
+    }
+}
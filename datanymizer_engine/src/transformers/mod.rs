@@ -16,6 +16,12 @@
 mod capitalize;
 pub use capitalize::CapitalizeTransformer;
 
+mod sql;
+pub use sql::SqlTransformer;
+
+mod custom;
+pub use custom::{CustomTransformer, CustomTransformers};
+
 mod template;
 pub use template::TemplateTransformer;
 
@@ -28,6 +34,9 @@
 mod token;
 pub use token::{Base64TokenTransformer, Base64UrlTokenTransformer, HexTokenTransformer};
 
+mod bytea;
+pub use bytea::{ByteaFileTransformer, ByteaHeaderTransformer, ByteaRandomTransformer};
+
 mod fk;
 pub use fk::sql_value::AsSqlValue;
 pub use fk::*;
@@ -48,6 +57,9 @@ pub enum Transformers {
         }
 
         impl Transformers {
+            /// Names of all transformers (as in the config)
+            pub const NAMES: &'static [&'static str] = &[$($ser),*];
+
             fn transformer(&self) -> &dyn Transformer {
                 match self {
                     $(
@@ -78,11 +90,17 @@ fn mut_transformer(&mut self) -> &mut dyn Transformer {
     ("random_num", RandomNum, RandomNumberTransformer),
     ("password", Password, PasswordTransformer),
     ("datetime", DateTime, RandomDateTimeTransformer),
+    ("sql", Sql, SqlTransformer),
+    ("custom", Custom, CustomTransformer),
 
     ("hex_token", HexToken, HexTokenTransformer),
     ("base64_token", Base64Token, Base64TokenTransformer),
     ("base64url_token", Base64UrlToken, Base64UrlTokenTransformer),
 
+    ("bytea_random", ByteaRandom, ByteaRandomTransformer),
+    ("bytea_file", ByteaFile, ByteaFileTransformer),
+    ("bytea_header", ByteaHeader, ByteaHeaderTransformer),
+
     ("city", City, CityTransformer),
     ("city_prefix", CityPrefix, CityPrefixTransformer),
     ("city_suffix", CitySuffix, CitySuffixTransformer),
@@ -161,6 +179,23 @@ fn mut_transformer(&mut self) -> &mut dyn Transformer {
     ("currency_symbol", CurrencySymbol, CurrencySymbolTransformer)
 ];
 
+impl Transformers {
+    /// Names of `custom` rules (including rules nested in `pipeline` and `template` rules)
+    pub fn custom_names(&self) -> Vec<&str> {
+        match self {
+            Self::Custom(t) => vec![t.name.as_str()],
+            Self::Pipeline(t) => t.pipes.iter().flat_map(|p| p.custom_names()).collect(),
+            Self::Template(t) => t
+                .rules
+                .iter()
+                .flatten()
+                .flat_map(|r| r.custom_names())
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
 impl Transformer for Transformers {
     fn transform(
         &self,
@@ -192,4 +227,31 @@ fn init() {
 
         assert!(matches!(ts, Transformers::FirstName(t) if t.locale == Some(LocaleConfig::RU)));
     }
+
+    #[test]
+    fn custom_names() {
+        let config = r#"
+            pipeline:
+              pipes:
+                - custom:
+                    name: first
+                - template:
+                    format: "{{ _1 }}"
+                    rules:
+                      - custom:
+                          name: second
+                      - capitalize: ~
+        "#;
+        let ts: Transformers = serde_yaml::from_str(config).unwrap();
+        assert_eq!(ts.custom_names(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Transformers::NAMES.first(), Some(&"none"));
+        assert!(Transformers::NAMES.contains(&"bytea_random"));
+
+        let names: std::collections::HashSet<_> = Transformers::NAMES.iter().collect();
+        assert_eq!(names.len(), Transformers::NAMES.len());
+    }
 }
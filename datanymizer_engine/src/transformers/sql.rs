@@ -0,0 +1,48 @@
+use crate::transformer::{TransformContext, TransformResult, Transformer};
+use serde::{Deserialize, Serialize};
+
+/// The value is a SQL expression evaluated by the database server (e.g., `md5(email)`).
+/// The dumper injects the expression into the query instead of reading the original value,
+/// so the transformer itself just keeps the (already transformed) value.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+#[serde(from = "String", into = "String")]
+pub struct SqlTransformer {
+    pub expression: String,
+}
+
+impl From<String> for SqlTransformer {
+    fn from(expression: String) -> Self {
+        Self { expression }
+    }
+}
+
+impl From<SqlTransformer> for String {
+    fn from(transformer: SqlTransformer) -> Self {
+        transformer.expression
+    }
+}
+
+impl Transformer for SqlTransformer {
+    fn transform(
+        &self,
+        _field_name: &str,
+        _field_value: &str,
+        _ctx: &Option<TransformContext>,
+    ) -> TransformResult {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Transformer, Transformers};
+
+    #[test]
+    fn parse_and_transform() {
+        let config = r#"sql: "md5(email)""#;
+        let transformer: Transformers = serde_yaml::from_str(config).unwrap();
+
+        assert!(matches!(&transformer, Transformers::Sql(t) if t.expression == "md5(email)"));
+        assert_eq!(transformer.transform("field", "value", &None), Ok(None));
+    }
+}